@@ -0,0 +1,59 @@
+//! Build script
+//!
+//! Rofi loads a single mode from each plugin, so the mode exported by the library
+//! is selected at build time with the `ROFI_VSCODE_EXPORT_MODE` environment variable.
+//! Its name can be changed with `ROFI_VSCODE_MODE_NAME`, to load the same mode from several plugins,
+//! e.g. bound to different flavors with `ROFI_VSCODE_MODE_FLAVOR`.
+
+use std::env;
+
+const ENV_EXPORT_MODE: &str = "ROFI_VSCODE_EXPORT_MODE";
+const ENV_MODE_NAME: &str = "ROFI_VSCODE_MODE_NAME";
+
+/// Modes that can be exported, the first one is the default
+const MODES: &[&str] = &[
+    "recent",
+    "profiles",
+    "ssh",
+    "containers",
+    "extensions",
+    "tasks",
+    "files",
+    "projects",
+    "codespaces",
+    "wsl",
+];
+
+fn main() {
+    println!("cargo::rerun-if-changed=build.rs");
+    println!("cargo::rerun-if-env-changed={}", ENV_EXPORT_MODE);
+    println!("cargo::rerun-if-env-changed={}", ENV_MODE_NAME);
+
+    let values = MODES
+        .iter()
+        .map(|m| format!("\"{}\"", m))
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!(
+        "cargo::rustc-check-cfg=cfg(export_mode, values({}))",
+        values
+    );
+
+    let mode = env::var(ENV_EXPORT_MODE).unwrap_or_else(|_| MODES[0].to_string());
+    if !MODES.contains(&mode.as_str()) {
+        panic!(
+            "{}=\"{}\" does not match any mode, expected one of: {}",
+            ENV_EXPORT_MODE, mode, values
+        );
+    }
+    println!("cargo::rustc-cfg=export_mode=\"{}\"", mode);
+
+    let name = env::var(ENV_MODE_NAME).unwrap_or_else(|_| format!("vscode-{}", mode));
+    if name.is_empty() || name.contains(|c: char| c == '\0' || c.is_whitespace()) {
+        panic!(
+            "{}=\"{}\" is not a valid mode name, it must be a single word",
+            ENV_MODE_NAME, name
+        );
+    }
+    println!("cargo::rustc-env={}={}", ENV_MODE_NAME, name);
+}
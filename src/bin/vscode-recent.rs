@@ -1,83 +1,1232 @@
 //! Print paths of recent Visual Studio Code workspacess and files
 //!
 //! This plugin can be configured with environment variables:
-//! - `ROFI_VSCODE_FLAVOR=[code|code-insiders|code-oss|vscodium]` sets the preferred VSCode flavor to be used
+//! - `ROFI_VSCODE_FLAVOR=[code|code-insiders|code-oss|vscodium|cursor|windsurf|all]` sets the preferred VSCode flavor to be used
+//! - `ROFI_VSCODE_CMD` and `ROFI_VSCODE_CONFIG_DIR` override the command and configuration directory of the flavor
+//! - `ROFI_VSCODE_USER_DATA_DIR` sets the `--user-data-dir` the flavor is run with, which holds its configuration
+//! - `ROFI_VSCODE_PROFILE=[all|name]` also prints the items of every profile, or only those of the named one
+//! - `ROFI_VSCODE_SERVER_HOST` names this host in the URIs printed when opening the items of a VSCode Server
+//! - `ROFI_VSCODE_DB` sets the path of the `state.vscdb` database holding the recent items
+//! - `ROFI_VSCODE_DEDUP=[workspace|folder]` collapses folders that are also part of a workspace
+//! - `ROFI_VSCODE_MAX_ENTRIES` limits the number of printed items
+//! - `ROFI_VSCODE_KINDS=workspaces,folders` shows only the items of the given kinds
+//! - `ROFI_VSCODE_RESOLVE_SYMLINKS=true` resolves symlinks in the paths, showing each item once
+//! - `ROFI_VSCODE_WITHIN` shows only the items under a directory
+//! - `ROFI_VSCODE_SORT=[recency|last-opened|most-used]` sorts the items by recency, by when they were last opened or by how often they are opened
+//! - `ROFI_VSCODE_REMOTE=[mixed|last|hidden]` places the remote items after the local ones, or hides them
+//! - `ROFI_VSCODE_LOCAL_ONLY=true` prints only the local items, as `ROFI_VSCODE_REMOTE=hidden` does
+//! - `ROFI_VSCODE_HIDE_VIRTUAL=true` hides the items in virtual filesystems, e.g. GitHub repositories, keeping the other remote ones
+//! - `ROFI_VSCODE_BROWSER_FALLBACK=true` opens the virtual and remote items in VSCode for the Web when the flavor cannot open them
+//! - `ROFI_VSCODE_NOTIFY=[errors|all]` sends a desktop notification when opening an item fails, or whenever one is opened
+//! - `ROFI_VSCODE_NO_USAGE=true` stops recording the opened items in the usage statistics
+//! - `ROFI_VSCODE_GROUP=workspaces,folders,files` groups the items by kind, in the given order
+//! - `ROFI_VSCODE_LABEL` lays out the labels of the items, e.g. `{basename}  {dir}`
 //!
 //! For more details please see the README in the repository.
 
-use clap::{Parser, ValueEnum};
+use anyhow::{anyhow, Context};
+use std::{
+    env, fmt, fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+    thread,
+    time::Duration,
+};
+
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 use rofi_vscode_mode::{
-    utils::determine_vscode_flavor,
+    config::{self, config_file_path},
+    git::git_dir,
+    utils::{
+        determine_label_template, determine_recent_options, determine_vscode_flavors,
+        escape_desktop_value, escape_tsv_field, open_recent, quote_csv_field,
+        quote_desktop_exec_arg, relative_path,
+    },
     vscode::{
-        workspaces::{recently_opened_from_storage, Recent},
-        Flavor,
+        label::{Field, LabelTemplate},
+        untildify,
+        workspace_storage::orphaned_storages,
+        workspaces::{
+            add_recently_opened, clear_recently_opened, diff_recents, history_from_json,
+            history_location, history_to_json, import_recently_opened, prune_recently_opened,
+            recently_opened_from_flavors, recently_opened_from_storage, remove_recently_opened_if,
+            sync_recently_opened, touch_recent, DedupPreference, Recent, RecentChange, RecentKind,
+            RecentSort, RemotePlacement,
+        },
+        EditorFlavor, Flavor,
     },
 };
 
+/// Set by rofi when running a script mode, see `man rofi-script`
+const ROFI_RETV: &str = "ROFI_RETV";
+/// Set by rofi to the `info` option of the selected row, the URI and the kind separated by a tab
+const ROFI_INFO: &str = "ROFI_INFO";
+
+/// Settings of the plugin checked by `doctor`
+const ENV_ICON_MODE: &str = "ROFI_VSCODE_ICON_MODE";
+const ENV_ICON_FONT: &str = "ROFI_VSCODE_ICON_FONT";
+/// File name of the plugin built by cargo
+const PLUGIN_FILE_NAME: &str = "librofi_vscode_mode.so";
+/// Directories where Rofi usually looks for plugins, when pkg-config does not tell
+const PLUGIN_DIRS: &[&str] = &[
+    "/usr/lib/rofi",
+    "/usr/lib64/rofi",
+    "/usr/lib/x86_64-linux-gnu/rofi",
+    "/usr/lib/aarch64-linux-gnu/rofi",
+    "/usr/local/lib/rofi",
+];
+
 /// How each item should be shown
-#[derive(Debug, Clone, Copy, ValueEnum)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub enum OutputFormat {
     /// Label (if provided), otherise tildified path
     ///
     /// Shows only local items
+    #[default]
     Label,
     /// Absolute path
     ///
     /// Shows only local items
     AbsolutePath,
+    /// Path relative to the home directory, or to the base directory given as `relative:{base}`
+    ///
+    /// Paths outside of the base are absolute. Shows only local items
+    Relative(Option<PathBuf>),
     /// URI
     ///
     /// Shows all items
     Uri,
+    /// Label followed by the icon, and the URI and kind as info, in the extended dmenu format of `rofi -dmenu` and fuzzel
+    ///
+    /// Shows all items
+    Dmenu,
+    /// Label, URI and kind separated by tabs, for `fzf --delimiter '\t' --with-nth 1`
+    ///
+    /// Shows all items
+    Fzf,
+    /// The fields chosen with `--columns`, separated by tabs
+    ///
+    /// Shows all items
+    Tsv,
+    /// The fields chosen with `--columns`, separated by commas
+    ///
+    /// Shows all items
+    Csv,
 }
 
-impl Default for OutputFormat {
-    fn default() -> Self {
-        Self::Label
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, base) = match s.split_once(':') {
+            Some((name, base)) => (name, Some(base)),
+            None => (s, None),
+        };
+        match (name.trim().to_lowercase().as_str(), base) {
+            ("label", None) => Ok(OutputFormat::Label),
+            ("absolute-path", None) => Ok(OutputFormat::AbsolutePath),
+            ("relative", None) => Ok(OutputFormat::Relative(None)),
+            ("relative", Some(base)) if !base.is_empty() => {
+                Ok(OutputFormat::Relative(Some(untildify(base))))
+            }
+            ("uri", None) => Ok(OutputFormat::Uri),
+            ("dmenu", None) => Ok(OutputFormat::Dmenu),
+            ("fzf", None) => Ok(OutputFormat::Fzf),
+            ("tsv", None) => Ok(OutputFormat::Tsv),
+            ("csv", None) => Ok(OutputFormat::Csv),
+            _ => Err(anyhow!(
+                "\"{}\" is not an output format, expected one of: label, absolute-path, relative[:base], uri, dmenu, fzf, tsv, csv",
+                s
+            )),
+        }
     }
 }
 
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Label => write!(f, "label"),
+            OutputFormat::AbsolutePath => write!(f, "absolute-path"),
+            OutputFormat::Relative(None) => write!(f, "relative"),
+            OutputFormat::Relative(Some(base)) => write!(f, "relative:{}", base.display()),
+            OutputFormat::Uri => write!(f, "uri"),
+            OutputFormat::Dmenu => write!(f, "dmenu"),
+            OutputFormat::Fzf => write!(f, "fzf"),
+            OutputFormat::Tsv => write!(f, "tsv"),
+            OutputFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+/// Actions on the recent items instead of printing them
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Clear the whole list of recent items
+    Clear {
+        /// Do not ask for confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Remove the local items whose paths do not exist anymore, printing them
+    Prune {
+        /// Only print the items that would be removed
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+    /// Put items at the top of the list, so that they show up in VSCode and in the plugin
+    ///
+    /// Items already in the list are moved to the top. The items are added in order, so the last one ends up first.
+    Add {
+        /// Paths or URIs of the items
+        #[arg(required = true)]
+        targets: Vec<String>,
+        /// Kind of the items (workspace, folder, file), guessed from the paths if not given
+        #[arg(short = 'k', long)]
+        kind: Option<RecentKind>,
+    },
+    /// Put a directory at the top of the list, meant to be run from a shell prompt hook
+    ///
+    /// Nothing is written if it is already the most recent item. The home directory is skipped.
+    Touch {
+        /// Directory to record, the current one if not given
+        dir: Option<PathBuf>,
+        /// Record the root of the git repository containing the directory instead, if any
+        #[arg(short = 'r', long)]
+        repo: bool,
+    },
+    /// Remove the items matching any of the targets, printing them
+    ///
+    /// Each target is the label, path or URI of an item, and may contain the wildcards `*` and `?`.
+    Rm {
+        /// Labels, paths or URIs of the items
+        #[arg(required = true)]
+        targets: Vec<String>,
+        /// Only print the items that would be removed
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+    /// Write all the recent items as JSON, e.g. for backups
+    Export {
+        /// File to write to, the standard output if not given
+        file: Option<PathBuf>,
+    },
+    /// Replace the recent items of the preferred flavor with the ones exported as JSON
+    Import {
+        /// File to read from, the standard input if not given
+        file: Option<PathBuf>,
+        /// Keep the current items after the imported ones
+        #[arg(short = 'm', long)]
+        merge: bool,
+    },
+    /// Merge the recent items of a flavor into the ones of another flavor
+    ///
+    /// The items of both flavors are interleaved, without duplicates, and stored into the target flavor only.
+    Sync {
+        /// Flavor to copy the items from
+        #[arg(long)]
+        from: Flavor,
+        /// Flavor to store the merged items into
+        #[arg(long)]
+        to: Flavor,
+    },
+    /// Open a recent item with the flavor it comes from
+    ///
+    /// Local paths that are not among the recent items are opened with the preferred flavor.
+    Open {
+        /// Label, path or URI of the item, read from the standard input if not given
+        ///
+        /// Lines printed with `--fzf` are also accepted.
+        #[arg(required_unless_present = "stdin")]
+        target: Option<String>,
+        /// Read the item from the standard input
+        #[arg(long, conflicts_with = "target")]
+        stdin: bool,
+    },
+    /// Print the changes to the recent items as JSON lines, until interrupted
+    ///
+    /// Each line has the `event` (`added` or `removed`), the `flavor`, the `position` in the list and the `entry`.
+    Watch {
+        /// Seconds between two checks of the list
+        #[arg(short = 'i', long, default_value_t = 1)]
+        interval: u64,
+    },
+    /// Write a desktop entry whose actions open the most recent items, for docks and launchers with jump lists
+    ///
+    /// Run it again, e.g. periodically, to update the actions.
+    Desktop {
+        /// Number of recent items to list as actions
+        #[arg(short = 'n', long, default_value_t = 10)]
+        count: usize,
+        /// File to write, `$XDG_DATA_HOME/applications/vscode-recent.desktop` by default
+        output: Option<PathBuf>,
+    },
+    /// Print the per-workspace storage folders of workspaces removed long ago, with their size
+    ///
+    /// A folder is removed when its workspace is not among the recent items or does not exist anymore,
+    /// and it has not been used for some time.
+    Gc {
+        /// Minimum number of days since the folder was last used
+        #[arg(short = 'd', long, default_value_t = 90)]
+        days: u64,
+        /// Delete the folders instead of only printing them
+        #[arg(long)]
+        delete: bool,
+    },
+    /// Check the setup, printing what is wrong and how to fix it
+    ///
+    /// It checks the configuration file, the detection of the flavors, their configuration directories
+    /// and recent items, the icon font and where the Rofi plugin is installed.
+    Doctor,
+    /// Install the Rofi plugin into the directory Rofi loads plugins from, and check that Rofi lists its mode
+    ///
+    /// Writing to the system directory usually requires root, e.g. `sudo vscode-recent install-plugin`.
+    InstallPlugin {
+        /// Built plugin, looked up next to this program and in `target/release` if not given
+        plugin: Option<PathBuf>,
+        /// Directory to install into, Rofi's plugin directory if not given
+        #[arg(short = 'd', long)]
+        dir: Option<PathBuf>,
+        /// Create a symbolic link instead of a copy, so that rebuilding updates the plugin
+        #[arg(short = 's', long)]
+        symlink: bool,
+    },
+}
+
 /// Print paths of recent Visual Studio Code workspaces and files
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 struct Args {
-    /// Visual Studio Code flavor (code, code-insiders, code-oss, vscodium)
+    /// Visual Studio Code flavor (code, code-insiders, code-oss, vscodium, cursor, windsurf)
     #[arg(short = 'c', long)]
     flavor: Option<Flavor>,
 
-    /// Output format
-    #[arg(short = 'F', long, value_enum, default_value_t = OutputFormat::default())]
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    /// Terminate each item with a NUL character instead of a newline, e.g. for `xargs -0`
+    ///
+    /// It cannot be used with the dmenu output format, which already separates the fields of the items with NUL characters.
+    #[arg(short = '0', long)]
+    print0: bool,
+
+    /// Output format (label, absolute-path, `relative[:base]`, uri, dmenu, fzf, tsv, csv)
+    ///
+    /// `relative` prints the paths relative to the home directory, or to the given base directory,
+    /// e.g. `relative:.` for the current directory.
+    #[arg(short = 'F', long, value_name = "FORMAT", default_value_t = OutputFormat::default())]
     output_format: OutputFormat,
+
+    /// Fields printed by the tsv and csv output formats (label, basename, dir, path, kind, remote, flavor, uri)
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "FIELDS",
+        default_value = "kind,label,path,remote,flavor"
+    )]
+    columns: Vec<Field>,
+
+    /// Print the names of the columns first, with the tsv and csv output formats
+    #[arg(long)]
+    header: bool,
+
+    /// Shorthand for `--output-format fzf`
+    #[arg(long, conflicts_with = "output_format")]
+    fzf: bool,
+
+    /// Collapse entries pointing to the same directory, keeping the workspace or the folder
+    #[arg(short = 'd', long, value_name = "workspace|folder")]
+    dedup: Option<DedupPreference>,
+
+    /// Maximum number of items to print
+    #[arg(short = 'n', long, visible_alias = "limit", value_name = "N")]
+    max_entries: Option<usize>,
+
+    /// Print only the items of the given kinds (workspace, folder, file)
+    ///
+    /// The option can be repeated or given a comma-separated list, e.g. `--kind workspace --kind folder`.
+    #[arg(short = 'k', long, value_delimiter = ',', value_name = "KIND")]
+    kind: Option<Vec<RecentKind>>,
+
+    /// Resolve symlinks in the paths, printing each item once
+    #[arg(short = 'r', long)]
+    resolve_symlinks: bool,
+
+    /// Print only the items under this directory
+    #[arg(short = 'w', long, value_name = "DIR")]
+    within: Option<PathBuf>,
+
+    /// Sort the items by recency, by when they were last opened or by how often they are opened (recency, last-opened, most-used)
+    #[arg(short = 's', long)]
+    sort: Option<RecentSort>,
+
+    /// Print only the items matching this fuzzy pattern, from the best to the worst match
+    #[arg(short = 'q', long, value_name = "PATTERN")]
+    query: Option<String>,
+
+    /// Group the items by kind, in the given order (e.g. workspaces,folders,files)
+    #[arg(short = 'g', long, value_delimiter = ',')]
+    group: Option<Vec<RecentKind>>,
+
+    /// Place the remote and virtual items among the local ones, after them, or hide them (mixed, last, hidden)
+    #[arg(long, value_name = "PLACEMENT")]
+    remote: Option<RemotePlacement>,
+
+    /// Hide the items in virtual filesystems, e.g. GitHub repositories, but not the other remote ones
+    #[arg(long)]
+    hide_virtual: bool,
+
+    /// Lay out the labels with placeholders (label, basename, dir, path, kind, remote, flavor, uri)
+    ///
+    /// For example `--label '{basename}  {dir}  {remote}'`.
+    #[arg(short = 'l', long, value_name = "TEMPLATE")]
+    label: Option<LabelTemplate>,
 }
 
-fn format_entry(entry: &Recent, output_format: &OutputFormat) -> anyhow::Result<String> {
+/// Format an item coming from the flavor named `flavor`, laying out its label with `template` if given
+fn format_entry(
+    entry: &Recent,
+    output_format: &OutputFormat,
+    resolve_symlinks: bool,
+    template: Option<&LabelTemplate>,
+    columns: &[Field],
+    flavor: &str,
+) -> anyhow::Result<String> {
+    let label = || match template {
+        Some(template) => template.render(entry, flavor),
+        None => entry.label().map(|s| s.to_string()),
+    };
     match output_format {
-        OutputFormat::Label => entry.label().map(|s| s.to_string()),
-        OutputFormat::AbsolutePath => entry.file_path().map(|p| p.to_string_lossy().to_string()),
+        OutputFormat::Label => Ok(label()?),
+        OutputFormat::AbsolutePath if resolve_symlinks => {
+            Ok(entry.canonical_path()?.to_string_lossy().to_string())
+        }
+        OutputFormat::AbsolutePath => Ok(entry.file_path()?.to_string_lossy().to_string()),
+        OutputFormat::Relative(base) => {
+            let path = match resolve_symlinks {
+                true => entry.canonical_path()?,
+                false => entry.file_path()?,
+            };
+            Ok(relative_path(&path, &relative_base(base.as_deref())?))
+        }
         OutputFormat::Uri => Ok(entry.url().to_string()),
+        OutputFormat::Fzf => Ok(format!("{}\t{}\t{}", label()?, entry.url(), entry.kind())),
+        OutputFormat::Tsv | OutputFormat::Csv => {
+            let values = columns
+                .iter()
+                .map(|column| match column {
+                    Field::Label => label(),
+                    column => column.render(entry, flavor),
+                })
+                .collect::<Result<Vec<String>, _>>()?;
+            Ok(format_row(&values, output_format))
+        }
+        OutputFormat::Dmenu => Ok(format!(
+            "{}\0icon\x1f{}\x1finfo\x1f{}\t{}",
+            label()?,
+            entry.icon_name(),
+            entry.url(),
+            entry.kind()
+        )),
+    }
+}
+
+/// Join the `values` of a row of the tsv or csv output formats
+fn format_row(values: &[String], output_format: &OutputFormat) -> String {
+    match output_format {
+        OutputFormat::Csv => values
+            .iter()
+            .map(|v| quote_csv_field(v))
+            .collect::<Vec<_>>()
+            .join(","),
+        _ => values
+            .iter()
+            .map(|v| escape_tsv_field(v))
+            .collect::<Vec<_>>()
+            .join("\t"),
+    }
+}
+
+/// The directory that the paths are made relative to, from the base given to `--output-format relative`
+///
+/// This is the home directory if no base is given, and relative bases are resolved from the current directory.
+fn relative_base(base: Option<&Path>) -> anyhow::Result<PathBuf> {
+    match base {
+        Some(base) if base.is_absolute() => Ok(base.to_path_buf()),
+        Some(base) => Ok(env::current_dir()?.join(base)),
+        None => dirs::home_dir().ok_or_else(|| anyhow!("Could not determine the home directory")),
+    }
+}
+
+/// Ask the user a yes/no question on the terminal, defaulting to no
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    eprint!("{} [y/N] ", question);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Clear the recent items of the flavors, after confirmation unless `yes` is set
+fn clear(flavors: &[Box<dyn EditorFlavor>], yes: bool) -> anyhow::Result<()> {
+    for flavor in flavors {
+        let count = recently_opened_from_storage(flavor.as_ref(), false)?.len();
+        let question = format!("Clear the {} recent items of {}?", count, flavor.name());
+        if count > 0 && (yes || confirm(&question)?) {
+            clear_recently_opened(flavor.as_ref())?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove the stale items of the flavors, printing them
+fn prune(flavors: &[Box<dyn EditorFlavor>], dry_run: bool) -> anyhow::Result<()> {
+    for flavor in flavors {
+        for entry in prune_recently_opened(flavor.as_ref(), dry_run)? {
+            if let Ok(s) = format_entry(
+                &entry,
+                &OutputFormat::Label,
+                false,
+                None,
+                &[],
+                flavor.name(),
+            ) {
+                println!("{}", s)
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Add the `targets` to the recent items of the preferred flavor
+fn add(
+    flavors: &[Box<dyn EditorFlavor>],
+    targets: &[String],
+    kind: Option<RecentKind>,
+) -> anyhow::Result<()> {
+    for target in targets {
+        let recent = Recent::from_target(target, kind)?;
+        add_recently_opened(flavors[0].as_ref(), &recent)?;
+    }
+    Ok(())
+}
+
+/// Record `dir`, or the current directory, as the most recent folder of the preferred flavor
+///
+/// With `repo`, the root of the enclosing git repository is recorded instead, and nothing if there is none.
+fn touch(
+    flavors: &[Box<dyn EditorFlavor>],
+    dir: Option<PathBuf>,
+    repo: bool,
+) -> anyhow::Result<()> {
+    let dir = match dir {
+        Some(dir) => std::path::absolute(untildify(&dir.to_string_lossy()))?,
+        None => env::current_dir()?,
+    };
+    let dir = match repo {
+        true => match dir.ancestors().find(|d| git_dir(d).is_some()) {
+            Some(root) => root.to_path_buf(),
+            None => return Ok(()),
+        },
+        false => dir,
+    };
+    if dirs::home_dir().is_some_and(|home| home == dir) {
+        return Ok(());
+    }
+    touch_recent(flavors[0].as_ref(), &dir)?;
+    Ok(())
+}
+
+/// Remove the items of the flavors matching any of the `targets`, printing them
+fn rm(flavors: &[Box<dyn EditorFlavor>], targets: &[String], dry_run: bool) -> anyhow::Result<()> {
+    let mut removed = Vec::new();
+    for flavor in flavors {
+        let matches = |r: &Recent| targets.iter().any(|t| r.matches_pattern(t));
+        removed.extend(remove_recently_opened_if(
+            flavor.as_ref(),
+            matches,
+            dry_run,
+        )?);
+    }
+    for entry in &removed {
+        if let Ok(s) = format_entry(entry, &OutputFormat::Label, false, None, &[], "") {
+            println!("{}", s)
+        }
+    }
+    match targets
+        .iter()
+        .find(|t| !removed.iter().any(|r| r.matches_pattern(t)))
+    {
+        Some(target) => Err(anyhow!("Could not find {} among the recent items", target)),
+        None => Ok(()),
+    }
+}
+
+/// Write the recent items of the flavors as JSON to `file`, or to the standard output
+fn export(flavors: &[Box<dyn EditorFlavor>], file: Option<PathBuf>) -> anyhow::Result<()> {
+    let entries: Vec<Recent> = recently_opened_from_flavors(flavors, false)?
+        .into_iter()
+        .map(|(_, r)| r)
+        .collect();
+    let json = history_to_json(&entries)?;
+    match file {
+        Some(file) => fs::write(&file, json).with_context(|| format!("Could not write {:?}", file)),
+        None => {
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
+
+/// Read the recent items as JSON from `file`, or from the standard input, into the preferred flavor
+fn import(
+    flavors: &[Box<dyn EditorFlavor>],
+    file: Option<PathBuf>,
+    merge: bool,
+) -> anyhow::Result<()> {
+    let json = match file {
+        Some(file) => {
+            fs::read_to_string(&file).with_context(|| format!("Could not read {:?}", file))?
+        }
+        None => io::read_to_string(io::stdin())?,
+    };
+    Ok(import_recently_opened(
+        flavors[0].as_ref(),
+        history_from_json(&json)?,
+        merge,
+    )?)
+}
+
+/// Read the target of a command from the first line of the standard input
+///
+/// For lines printed with `--fzf`, the URI is taken.
+fn read_target() -> anyhow::Result<String> {
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    let line = line.trim_end_matches(['\n', '\r', '\0']);
+    Ok(line.split('\t').nth(1).unwrap_or(line).to_string())
+}
+
+/// Open the recent item designated by `target`, or else the local path
+///
+/// If the item must be opened from a client, e.g. on a host with only the VSCode Server, its URI is printed.
+fn open(flavors: &[Box<dyn EditorFlavor>], target: Option<String>) -> anyhow::Result<()> {
+    let target = match target {
+        Some(target) => target,
+        None => read_target()?,
+    };
+    let entries = recently_opened_from_flavors(flavors, false)?;
+    if let Some((flavor, recent)) = entries.iter().find(|(_, r)| r.matches_target(&target)) {
+        if let Some(url) = open_recent(flavors[*flavor].as_ref(), recent)? {
+            println!("{}", url);
+        }
+        return Ok(());
+    }
+    let path = untildify(&target);
+    if path.exists() {
+        Ok(flavors[0].open_local_path(path.as_os_str())?)
+    } else {
+        Err(anyhow!("Could not find {} among the recent items", target))
+    }
+}
+
+/// Print the changes to the recent items of the flavors as JSON lines, checking them every `interval`
+///
+/// VSCode writes its state in batches, so changes show up with some delay.
+fn watch(flavors: &[Box<dyn EditorFlavor>], interval: Duration) -> anyhow::Result<()> {
+    let mut lists = flavors
+        .iter()
+        .map(|f| recently_opened_from_storage(f.as_ref(), false))
+        .collect::<Result<Vec<_>, _>>()?;
+    loop {
+        thread::sleep(interval);
+        for (flavor, list) in flavors.iter().zip(lists.iter_mut()) {
+            // The database may be briefly unavailable while VSCode writes it
+            let Ok(current) = recently_opened_from_storage(flavor.as_ref(), false) else {
+                continue;
+            };
+            for change in diff_recents(list, &current) {
+                let (event, position, entry) = match change {
+                    RecentChange::Added(i, r) => ("added", i, r),
+                    RecentChange::Removed(i, r) => ("removed", i, r),
+                };
+                let line = serde_json::json!({
+                    "event": event,
+                    "flavor": flavor.name(),
+                    "position": position,
+                    "entry": entry,
+                });
+                println!("{}", line);
+            }
+            io::stdout().flush()?;
+            *list = current;
+        }
+    }
+}
+
+/// Write a desktop entry with an action to open each of the `count` most recent items
+fn desktop(
+    flavors: &[Box<dyn EditorFlavor>],
+    count: usize,
+    output: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let output = match output {
+        Some(output) => output,
+        None => dirs::data_dir()
+            .ok_or_else(|| anyhow!("Could not find the data directory"))?
+            .join("applications")
+            .join("vscode-recent.desktop"),
+    };
+    // Launchers do not share our PATH, so refer to this very executable
+    let exe = env::current_exe()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "vscode-recent".to_string());
+    let exe = quote_desktop_exec_arg(&exe);
+
+    let mut options = determine_recent_options()?;
+    options.max_entries = Some(count);
+    let entries = options.apply(flavors, recently_opened_from_flavors(flavors, false)?);
+
+    let mut actions = String::new();
+    let mut ids = Vec::new();
+    for (i, (_, entry)) in entries.iter().enumerate() {
+        let Ok(label) = entry.label() else {
+            continue;
+        };
+        let id = format!("recent-{}", i);
+        actions.push_str(&format!(
+            "\n[Desktop Action {}]\nName={}\nIcon={}\nExec={} open {}\n",
+            id,
+            escape_desktop_value(&label),
+            entry.icon_name(),
+            exe,
+            quote_desktop_exec_arg(entry.url().as_str())
+        ));
+        ids.push(id);
+    }
+    let flavor = &flavors[0];
+    let contents = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name={} (recent)\n\
+         Icon=visual-studio-code\n\
+         Exec={}\n\
+         Actions={};\n{}",
+        escape_desktop_value(flavor.name()),
+        quote_desktop_exec_arg(flavor.cmd()),
+        ids.join(";"),
+        actions
+    );
+
+    if let Some(dir) = output.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Could not create {:?}", dir))?;
+    }
+    fs::write(&output, contents).with_context(|| format!("Could not write {:?}", output))
+}
+
+/// Format a size in bytes with a binary unit, e.g. `1.5 MiB`
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} {}", bytes, units[0]),
+        _ => format!("{:.1} {}", size, units[unit]),
+    }
+}
+
+/// Print and optionally delete the orphaned storage folders of the flavors
+fn gc(flavors: &[Box<dyn EditorFlavor>], days: u64, delete: bool) -> anyhow::Result<()> {
+    let min_age = Duration::from_secs(days * 24 * 60 * 60);
+    let mut total = 0;
+    for flavor in flavors {
+        let recents = recently_opened_from_storage(flavor.as_ref(), false)?;
+        for storage in orphaned_storages(flavor.as_ref(), &recents, min_age)? {
+            let size = storage.size();
+            let uri = storage.uri.as_ref().map(|u| u.as_str()).unwrap_or("?");
+            println!(
+                "{}\t{}\t{}",
+                format_size(size),
+                storage.dir.to_string_lossy(),
+                uri
+            );
+            if delete {
+                storage.remove()?;
+            }
+            total += size;
+        }
+    }
+    let verb = if delete { "Freed" } else { "Could free" };
+    eprintln!("{} {}", verb, format_size(total));
+    Ok(())
+}
+
+/// Outcome of a check of `doctor`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Print the outcome of a check of `doctor`, with a `hint` to fix it if any
+fn report(status: Status, what: &str, detail: &str, hint: Option<&str>) {
+    let tag = match status {
+        Status::Ok => "ok",
+        Status::Warning => "warn",
+        Status::Error => "FAIL",
+    };
+    println!("[{:>4}] {}: {}", tag, what, detail);
+    if let Some(hint) = hint {
+        println!("       {}", hint);
     }
 }
 
+/// Check the setup of the plugin and of the flavors, failing if any check failed
+fn doctor(flavor: Option<Flavor>) -> anyhow::Result<()> {
+    let mut failed = 0;
+    let mut check = |status: Status, what: &str, detail: &str, hint: Option<&str>| {
+        if status == Status::Error {
+            failed += 1;
+        }
+        report(status, what, detail, hint);
+    };
+
+    // Configuration file
+    match (config_file_path(), config::read_config_file()) {
+        (Some(path), Ok(table)) if path.exists() => check(
+            Status::Ok,
+            "config file",
+            &format!("{} ({} settings)", path.to_string_lossy(), table.len()),
+            None,
+        ),
+        (_, Ok(_)) => check(Status::Ok, "config file", "none, using defaults", None),
+        (_, Err(e)) => check(
+            Status::Error,
+            "config file",
+            &format!("{:#}", e),
+            Some("Fix the syntax of the file, see https://toml.io"),
+        ),
+    }
+
+    // Flavors
+    let installed = Flavor::installed();
+    if installed.is_empty() {
+        check(
+            Status::Warning,
+            "installed flavors",
+            "none detected",
+            Some("Install VSCode or one of its forks, or set ROFI_VSCODE_CMD and ROFI_VSCODE_CONFIG_DIR"),
+        );
+    } else {
+        let names: Vec<_> = installed.iter().map(|f| f.name()).collect();
+        check(Status::Ok, "installed flavors", &names.join(", "), None);
+    }
+    let flavors: Vec<Box<dyn EditorFlavor>> = match flavor {
+        Some(flavor) => vec![Box::new(flavor)],
+        None => match determine_vscode_flavors() {
+            Ok(flavors) => flavors,
+            Err(e) => {
+                check(
+                    Status::Error,
+                    "selected flavor",
+                    &format!("{:#}", e),
+                    Some("Check ROFI_VSCODE_FLAVOR, or set ROFI_VSCODE_CMD and ROFI_VSCODE_CONFIG_DIR"),
+                );
+                Vec::new()
+            }
+        },
+    };
+    for flavor in &flavors {
+        let what = format!("{} command", flavor.name());
+        let program = flavor.cmd().split_whitespace().next().unwrap_or_default();
+        match which::which(program) {
+            Ok(path) => check(Status::Ok, &what, &path.to_string_lossy(), None),
+            Err(_) => check(
+                Status::Warning,
+                &what,
+                &format!("{} not found in PATH", program),
+                Some("Items cannot be opened, set ROFI_VSCODE_CMD to the command of the flavor"),
+            ),
+        }
+
+        let what = format!("{} config dir", flavor.name());
+        match flavor.config_dir() {
+            Some(dir) => check(Status::Ok, &what, &dir.to_string_lossy(), None),
+            None => check(
+                Status::Error,
+                &what,
+                "not found",
+                Some("Run the flavor once, or set ROFI_VSCODE_CONFIG_DIR to its configuration directory"),
+            ),
+        }
+
+        let what = format!("{} recent items", flavor.name());
+        match history_location(flavor.as_ref()) {
+            Ok((path, true)) => {
+                let count = recently_opened_from_storage(flavor.as_ref(), false)
+                    .map(|r| r.len())
+                    .unwrap_or_default();
+                check(
+                    Status::Ok,
+                    &what,
+                    &format!("{} in {}", count, path.to_string_lossy()),
+                    None,
+                )
+            }
+            Ok((path, false)) => check(
+                Status::Warning,
+                &what,
+                &format!("no list in {}", path.to_string_lossy()),
+                Some("Open a folder with the flavor so that it records its recent items"),
+            ),
+            Err(e) => check(
+                Status::Error,
+                &what,
+                &format!("{:#}", e),
+                Some("Set ROFI_VSCODE_DB to the state.vscdb database of the flavor"),
+            ),
+        }
+    }
+
+    // Icons
+    let icon_mode = config::var(ENV_ICON_MODE).map(|m| m.to_lowercase());
+    if icon_mode.as_deref() == Some("nerd") {
+        let font = config::var(ENV_ICON_FONT).unwrap_or_else(|| "monospace".to_string());
+        let families = Command::new("fc-list").args([":", "family"]).output();
+        match families {
+            Ok(output) => {
+                let families = String::from_utf8_lossy(&output.stdout).to_lowercase();
+                if font == "monospace" || families.contains(&font.to_lowercase()) {
+                    check(Status::Ok, "icon font", &font, None)
+                } else {
+                    check(
+                        Status::Error,
+                        "icon font",
+                        &format!("{} is not installed", font),
+                        Some("Install a Nerd Font, or set ROFI_VSCODE_ICON_FONT to one listed by `fc-list : family`"),
+                    )
+                }
+            }
+            Err(_) => check(
+                Status::Warning,
+                "icon font",
+                &format!("could not check {} without fc-list", font),
+                None,
+            ),
+        }
+    } else {
+        let mode = icon_mode.unwrap_or_else(|| "theme".to_string());
+        check(Status::Ok, "icons", &format!("{} mode", mode), None);
+    }
+
+    // Rofi plugin
+    match which::which("rofi") {
+        Ok(path) => check(Status::Ok, "rofi", &path.to_string_lossy(), None),
+        Err(_) => check(
+            Status::Warning,
+            "rofi",
+            "not found in PATH",
+            Some("Install Rofi to use the plugin, the command line tool works without it"),
+        ),
+    }
+    let dirs = plugin_dirs();
+    let plugins: Vec<PathBuf> = dirs
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("librofi_vscode") && n.ends_with(".so"))
+        })
+        .collect();
+    if plugins.is_empty() {
+        let dirs: Vec<_> = dirs.iter().map(|d| d.to_string_lossy()).collect();
+        let detail = match dirs.is_empty() {
+            true => "no plugin directory found".to_string(),
+            false => format!("not found in {}", dirs.join(", ")),
+        };
+        check(
+            Status::Warning,
+            "rofi plugin",
+            &detail,
+            Some("Run `make plugin install.plugin`, or set ROFI_PLUGIN_PATH to where it is installed"),
+        );
+    } else {
+        for plugin in plugins {
+            check(Status::Ok, "rofi plugin", &plugin.to_string_lossy(), None);
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        1 => Err(anyhow!("1 check failed")),
+        n => Err(anyhow!("{} checks failed", n)),
+    }
+}
+
+/// Built plugin, next to this program as in `target/release` or in the current build directory
+fn find_built_plugin() -> Option<PathBuf> {
+    let beside = env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|d| d.join(PLUGIN_FILE_NAME)));
+    let built = PathBuf::from("target")
+        .join("release")
+        .join(PLUGIN_FILE_NAME);
+    beside.into_iter().chain([built]).find(|p| p.is_file())
+}
+
+/// Name of the mode exported by the plugin with the file name `name`, as the Makefile names the plugins
+///
+/// Plugins built with a custom mode name cannot be told apart, they are assumed to be named after it.
+fn plugin_mode_name(name: &str) -> String {
+    match name.strip_suffix(".so").unwrap_or(name) {
+        "librofi_vscode_mode" => "vscode-recent".to_string(),
+        stem => stem
+            .strip_prefix("librofi_")
+            .unwrap_or(stem)
+            .replace('_', "-"),
+    }
+}
+
+/// Create a symbolic link at `target` pointing to the `plugin`
+#[cfg(unix)]
+fn symlink_plugin(plugin: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(plugin, target)
+}
+
+/// Create a symbolic link at `target` pointing to the `plugin`
+///
+/// Creating one requires the developer mode or an administrator.
+#[cfg(windows)]
+fn symlink_plugin(plugin: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(plugin, target)
+}
+
+/// Symbolic links are not supported on this platform
+#[cfg(not(any(unix, windows)))]
+fn symlink_plugin(_plugin: &Path, _target: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "symbolic links are not supported on this platform, install without --symlink",
+    ))
+}
+
+/// Install the `plugin` into `dir`, copying or linking it, and check that Rofi lists its mode
+fn install_plugin(
+    plugin: Option<PathBuf>,
+    dir: Option<PathBuf>,
+    symlink: bool,
+) -> anyhow::Result<()> {
+    let plugin = match plugin.or_else(find_built_plugin) {
+        Some(plugin) => plugin,
+        None => {
+            return Err(anyhow!(
+                "Could not find {}, build it with `make plugin` or pass its path",
+                PLUGIN_FILE_NAME
+            ))
+        }
+    };
+    let plugin =
+        fs::canonicalize(&plugin).with_context(|| format!("Could not find {:?}", plugin))?;
+    let dir = dir
+        .or_else(|| plugin_dirs().into_iter().next())
+        .ok_or_else(|| anyhow!("Could not find Rofi's plugin directory, pass it with --dir"))?;
+    let name = plugin
+        .file_name()
+        .ok_or_else(|| anyhow!("{:?} is not a file", plugin))?;
+    let target = dir.join(name);
+
+    fs::create_dir_all(&dir).with_context(|| format!("Could not create {:?}", dir))?;
+    if target.symlink_metadata().is_ok() {
+        fs::remove_file(&target).with_context(|| format!("Could not replace {:?}", target))?;
+    }
+    let res = match symlink {
+        true => symlink_plugin(&plugin, &target),
+        false => fs::copy(&plugin, &target).map(|_| ()),
+    };
+    res.with_context(|| format!("Could not install the plugin into {:?}", dir))?;
+    eprintln!("Installed {}", target.to_string_lossy());
+
+    // Rofi lists the modes of the plugins it loaded in its help
+    let mode = plugin_mode_name(&name.to_string_lossy());
+    let Ok(output) = Command::new("rofi").arg("-help").output() else {
+        eprintln!("Rofi is not installed, could not check that it loads the plugin");
+        return Ok(());
+    };
+    if String::from_utf8_lossy(&output.stdout).contains(&mode) {
+        eprintln!(
+            "Rofi lists the {} mode, show it with `rofi -show {}`",
+            mode, mode
+        );
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Rofi does not list the {} mode, set ROFI_PLUGIN_PATH={} if it is not its plugin directory",
+            mode,
+            dir.to_string_lossy()
+        ))
+    }
+}
+
+/// Directories where Rofi looks for plugins
+///
+/// These are the ones in `ROFI_PLUGIN_PATH`, then the one of the installed Rofi according to pkg-config,
+/// or else the usual ones that exist.
+fn plugin_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = env::var_os("ROFI_PLUGIN_PATH")
+        .map(|p| env::split_paths(&p).collect())
+        .unwrap_or_default();
+    let pkg_config = Command::new("pkg-config")
+        .args(["--variable", "pluginsdir", "rofi"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|d| !d.is_empty());
+    match pkg_config {
+        Some(dir) => dirs.push(PathBuf::from(dir)),
+        None => dirs.extend(PLUGIN_DIRS.iter().map(PathBuf::from).filter(|d| d.is_dir())),
+    }
+    dirs
+}
+
 fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+    let retv = env::var(ROFI_RETV).ok();
+    let mut argv: Vec<String> = env::args().collect();
+    // Rofi appends the selected entry to the arguments after the first call
+    let selection = match retv.as_deref() {
+        Some("0") | None => None,
+        Some(_) if argv.len() > 1 => argv.pop(),
+        Some(_) => None,
+    };
+    let mut args = Args::parse_from(argv);
+    if args.fzf {
+        args.output_format = OutputFormat::Fzf;
+    }
+    if args.print0 && matches!(args.output_format, OutputFormat::Dmenu) {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--print0 cannot be used with the dmenu output format, whose rows already contain NUL characters",
+            )
+            .exit();
+    }
+
+    // Diagnose the setup before the flavors are determined, since this may be what fails,
+    // and install the plugin, which does not need them
+    match args.command {
+        Some(Commands::Doctor) => return doctor(args.flavor),
+        Some(Commands::InstallPlugin {
+            plugin,
+            dir,
+            symlink,
+        }) => return install_plugin(plugin, dir, symlink),
+        _ => {}
+    }
 
     // Determine the flavor
-    let flavor = match args.flavor {
-        Some(flavor) => flavor,             // use provided
-        None => determine_vscode_flavor()?, // fallback to ENV variable or detect
+    let flavors: Vec<Box<dyn EditorFlavor>> = match args.flavor {
+        Some(flavor) => vec![Box::new(flavor)], // use provided
+        None => determine_vscode_flavors()?,    // fallback to ENV variable or detect
     };
 
+    // Run the subcommand, if any
+    match args.command {
+        Some(Commands::Clear { yes }) => return clear(&flavors, yes),
+        Some(Commands::Prune { dry_run }) => return prune(&flavors, dry_run),
+        Some(Commands::Add { targets, kind }) => return add(&flavors, &targets, kind),
+        Some(Commands::Touch { dir, repo }) => return touch(&flavors, dir, repo),
+        Some(Commands::Rm { targets, dry_run }) => return rm(&flavors, &targets, dry_run),
+        Some(Commands::Export { file }) => return export(&flavors, file),
+        Some(Commands::Import { file, merge }) => return import(&flavors, file, merge),
+        Some(Commands::Sync { from, to }) => {
+            let added = sync_recently_opened(&from, &to)?;
+            eprintln!("Added {} recent items to {}", added, to.name());
+            return Ok(());
+        }
+        Some(Commands::Open { target, .. }) => return open(&flavors, target),
+        Some(Commands::Watch { interval }) => {
+            return watch(&flavors, Duration::from_secs(interval.max(1)))
+        }
+        Some(Commands::Desktop { count, output }) => return desktop(&flavors, count, output),
+        Some(Commands::Gc { days, delete }) => return gc(&flavors, days, delete),
+        Some(Commands::Doctor | Commands::InstallPlugin { .. }) | None => {}
+    }
+
+    // Run as a rofi script mode
+    match retv.as_deref() {
+        // Print the rows on the first call
+        Some("0") => {
+            args.output_format = OutputFormat::Dmenu;
+            args.print0 = false;
+        }
+        // Open the selected row, or the text entered by the user
+        Some("1") => {
+            let info = env::var(ROFI_INFO).ok();
+            let uri = info.map(|i| i.split('\t').next().unwrap_or_default().to_string());
+            return open(&flavors, uri.or(selection));
+        }
+        Some("2") => return open(&flavors, selection),
+        Some(_) => return Ok(()),
+        None => {}
+    }
+
     // Include non-local items? Only if we are able to open them from command line with a URI
     let local_only = match args.output_format {
-        OutputFormat::Uri => false,
-        OutputFormat::Label | OutputFormat::AbsolutePath => true,
+        OutputFormat::Uri
+        | OutputFormat::Dmenu
+        | OutputFormat::Fzf
+        | OutputFormat::Tsv
+        | OutputFormat::Csv => false,
+        OutputFormat::Label | OutputFormat::AbsolutePath | OutputFormat::Relative(_) => true,
+    };
+
+    // Use the provided options, falling back to ENV variables
+    let mut options = determine_recent_options()?;
+    options.kinds = args.kind.unwrap_or(options.kinds);
+    options.resolve_symlinks |= args.resolve_symlinks;
+    options.within = args.within.or(options.within);
+    options.dedup = args.dedup.or(options.dedup);
+    options.sort = args.sort.unwrap_or(options.sort);
+    options.group = args.group.unwrap_or(options.group);
+    options.remote = args.remote.unwrap_or(options.remote);
+    options.hide_virtual |= args.hide_virtual;
+    options.query = args.query;
+    options.max_entries = args.max_entries.or(options.max_entries);
+
+    let template = match args.label {
+        Some(template) => Some(template),
+        None => determine_label_template()?,
     };
 
     // Query and print the entries
-    let entries = recently_opened_from_storage(&flavor, local_only)?;
-    for entry in entries {
-        if let Ok(s) = format_entry(&entry, &args.output_format) {
-            println!("{}", s)
+    let entries = options.apply(
+        &flavors,
+        recently_opened_from_flavors(&flavors, local_only)?,
+    );
+    let terminator = if args.print0 { '\0' } else { '\n' };
+    if args.header && matches!(args.output_format, OutputFormat::Tsv | OutputFormat::Csv) {
+        let names: Vec<String> = args.columns.iter().map(Field::to_string).collect();
+        print!("{}{}", format_row(&names, &args.output_format), terminator);
+    }
+    for (index, entry) in entries {
+        if let Ok(s) = format_entry(
+            &entry,
+            &args.output_format,
+            options.resolve_symlinks,
+            template.as_ref(),
+            &args.columns,
+            flavors[index].name(),
+        ) {
+            print!("{}{}", s, terminator)
         }
     }
     Ok(())
@@ -0,0 +1,111 @@
+//! GitHub Codespaces utilities
+//!
+//! [Codespaces](https://docs.github.com/en/codespaces) are queried and opened in VSCode
+//! with the [GitHub CLI](https://cli.github.com/manual/gh_codespace), which must be authenticated.
+
+use crate::error::{other, Context, Result};
+use crate::vscode::EditorFlavor;
+use std::process::Command;
+
+use serde::Deserialize;
+
+/// Fields requested to `gh codespace list --json`
+const LIST_FIELDS: &str = "name,displayName,repository,state";
+
+/// State of a codespace
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum CodespaceState {
+    /// The codespace is running and can be connected to
+    Available,
+    /// The codespace is stopped, it is started when connecting to it
+    Shutdown,
+    /// Any transitional state, e.g. `Starting` or `Rebuilding`
+    #[serde(other)]
+    Other,
+}
+
+/// A codespace of the authenticated user
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Codespace {
+    /// Unique name of the codespace, used to open it
+    pub name: String,
+    /// Name shown to the user
+    #[serde(default)]
+    pub display_name: String,
+    /// Repository the codespace was created from, of the form `{owner}/{repo}`
+    pub repository: String,
+    /// Current state
+    pub state: CodespaceState,
+}
+
+impl Codespace {
+    /// Tells whether the codespace is running
+    pub fn is_running(&self) -> bool {
+        self.state == CodespaceState::Available
+    }
+
+    /// Open the codespace in VSCode, starting it if needed
+    ///
+    /// Only the stable and insiders flavors are supported by the GitHub CLI,
+    /// the latter is recognized by its command.
+    ///
+    /// # Errors
+    /// The call fails if `gh` cannot be executed.
+    pub fn open(&self, flavor: &dyn EditorFlavor) -> Result<()> {
+        let mut cmd = Command::new("gh");
+        cmd.args(["codespace", "code", "-c", &self.name]);
+        if flavor.cmd() == "code-insiders" {
+            cmd.arg("--insiders");
+        }
+        cmd.spawn()
+            .map(|_| ())
+            .context("Could not execute gh, is the GitHub CLI installed?")
+    }
+}
+
+/// Parse the output of `gh codespace list --json`
+fn parse_codespaces(output: &str) -> Result<Vec<Codespace>> {
+    serde_json::from_str(output).context("Could not parse codespaces")
+}
+
+/// List the codespaces of the authenticated user
+///
+/// # Errors
+/// The call fails if `gh` cannot be executed, e.g. because it is not authenticated,
+/// or if its output cannot be parsed.
+pub fn list_codespaces() -> Result<Vec<Codespace>> {
+    let output = Command::new("gh")
+        .args(["codespace", "list", "--json", LIST_FIELDS])
+        .output()
+        .context("Could not execute gh, is the GitHub CLI installed?")?;
+    if !output.status.success() {
+        return Err(other!(
+            "Could not list codespaces: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    parse_codespaces(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_codespaces, CodespaceState};
+
+    #[test]
+    fn parse_codespace_list() {
+        let output = r#"[
+            {"displayName":"fuzzy robot","name":"dwight-fuzzy-robot-5g4q","repository":"dunder/paper","state":"Available"},
+            {"displayName":"","name":"jim-pranks-7x2c","repository":"dunder/pranks","state":"Shutdown"},
+            {"displayName":"new","name":"pam-art-9k1z","repository":"dunder/art","state":"Starting"}
+        ]"#;
+
+        let codespaces = parse_codespaces(output).expect("could not parse");
+        assert_eq!(codespaces.len(), 3);
+        assert_eq!(codespaces[0].display_name, "fuzzy robot");
+        assert!(codespaces[0].is_running());
+        assert_eq!(codespaces[1].state, CodespaceState::Shutdown);
+        assert_eq!(codespaces[2].state, CodespaceState::Other);
+        assert!(!codespaces[2].is_running());
+    }
+}
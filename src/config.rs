@@ -0,0 +1,193 @@
+//! User configuration
+//!
+//! Each setting can be given with an environment variable, e.g. `ROFI_VSCODE_ICON_MODE`,
+//! or in the configuration file `$XDG_CONFIG_HOME/rofi-vscode-mode/config.toml`
+//! with the lowercase name stripped of the `ROFI_VSCODE_` prefix, e.g. `icon_mode = "nerd"`.
+//! Environment variables take precedence over the configuration file.
+
+use std::{env, fs, path::PathBuf, sync::OnceLock};
+
+use crate::error::{Context, Result};
+use crate::logging::error;
+use toml::{Table, Value};
+
+const ENV_PREFIX: &str = "ROFI_VSCODE_";
+
+/// The contents of the configuration file, read once
+static CONFIG_FILE: OnceLock<Table> = OnceLock::new();
+
+/// Path of the configuration file, which may not exist
+pub fn config_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|p| p.join("rofi-vscode-mode").join("config.toml"))
+}
+
+/// Read and parse the configuration file
+///
+/// A missing file is the same as an empty one.
+///
+/// # Errors
+/// The call fails if the file cannot be read or is not valid TOML.
+pub fn read_config_file() -> Result<Table> {
+    let Some(path) = config_file_path().filter(|p| p.exists()) else {
+        return Ok(Table::new());
+    };
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("Could not read {:?}", path))?;
+    contents
+        .parse()
+        .with_context(|| format!("Could not parse configuration file {:?}", path))
+}
+
+/// Key in the configuration file corresponding to the environment variable `name`
+fn file_key(name: &str) -> String {
+    name.strip_prefix(ENV_PREFIX).unwrap_or(name).to_lowercase()
+}
+
+/// Convert a value of the configuration file to the string that would be set in the environment
+///
+/// Arrays become comma-separated lists, and tables comma-separated `key=value` pairs.
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_owned(),
+        Value::Array(values) => values
+            .iter()
+            .map(value_to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        Value::Table(table) => table
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, value_to_string(v)))
+            .collect::<Vec<_>>()
+            .join(","),
+        v => v.to_string(),
+    }
+}
+
+/// Value for the environment variable `name` in the configuration file, read the first time
+///
+/// Errors in the configuration file are reported on stderr, and the file is ignored.
+fn file_value(name: &str) -> Option<&'static Value> {
+    CONFIG_FILE
+        .get_or_init(|| {
+            read_config_file().unwrap_or_else(|e| {
+                error!("{:?}", e);
+                Table::new()
+            })
+        })
+        .get(&file_key(name))
+}
+
+/// Look up the setting with the environment variable `name`, falling back to the configuration file
+///
+/// Errors in the configuration file are reported on stderr, and the file is ignored.
+pub fn var(name: &str) -> Option<String> {
+    if let Ok(value) = env::var(name) {
+        return Some(value);
+    }
+    file_value(name).map(value_to_string)
+}
+
+/// Look up the setting with the environment variable `name` holding command line arguments
+///
+/// The arguments are separated by whitespace and can be quoted as in a shell, e.g. `--profile "My Work"`.
+/// In the configuration file they can also be given as an array, one argument per item.
+pub fn args(name: &str) -> Option<Vec<String>> {
+    if let Ok(value) = env::var(name) {
+        return Some(split_args(&value));
+    }
+    match file_value(name)? {
+        Value::Array(values) => Some(values.iter().map(value_to_string).collect()),
+        value => Some(split_args(&value_to_string(value))),
+    }
+}
+
+/// Split `s` into arguments like a shell, honoring single quotes, double quotes and backslashes
+fn split_args(s: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    // The argument being read, if any, since quotes may start an empty one
+    let mut current: Option<String> = None;
+    let mut quote = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None | Some('"'), '\\') => {
+                let escaped = chars.next().unwrap_or('\\');
+                current.get_or_insert_with(String::new).push(escaped);
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => args.extend(current.take()),
+            (_, c) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(current);
+    args
+}
+
+/// Look up the boolean setting with the environment variable `name`
+///
+/// The setting is enabled by `1`, `true`, `yes` or `on` (case-insensitive), and disabled otherwise or if it is not set.
+pub fn flag(name: &str) -> bool {
+    var(name).is_some_and(|v| {
+        matches!(
+            v.trim().to_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use toml::Table;
+
+    use super::{file_key, split_args, value_to_string};
+
+    #[test]
+    fn split_arguments() {
+        assert_eq!(
+            split_args("  --profile Work --new-window "),
+            vec!["--profile", "Work", "--new-window"]
+        );
+        assert_eq!(
+            split_args(r#"--profile "Beet Farm" "" --user-data-dir='~/my dir' a\ b"#),
+            vec![
+                "--profile",
+                "Beet Farm",
+                "",
+                "--user-data-dir=~/my dir",
+                "a b"
+            ]
+        );
+        assert!(split_args("").is_empty());
+    }
+
+    #[test]
+    fn config_file_values() {
+        let config: Table = r#"
+            flavor = "vscodium"
+            ssh_paths = ["beet-farm=/srv/beets", "paper=/home/michael"]
+            enabled = true
+
+            [keys]
+            kb-custom-1 = "copy-path"
+            kb-accept-alt = "new-window"
+        "#
+        .parse()
+        .expect("could not parse");
+
+        assert_eq!(file_key("ROFI_VSCODE_SSH_PATHS"), "ssh_paths");
+        assert_eq!(value_to_string(&config["flavor"]), "vscodium");
+        assert_eq!(
+            value_to_string(&config["ssh_paths"]),
+            "beet-farm=/srv/beets,paper=/home/michael"
+        );
+        assert_eq!(value_to_string(&config["enabled"]), "true");
+        assert_eq!(
+            value_to_string(&config["keys"]),
+            "kb-accept-alt=new-window,kb-custom-1=copy-path"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Container utilities
+//!
+//! Running containers can be attached to with VSCode's
+//! [Dev Containers](https://code.visualstudio.com/docs/devcontainers/attach-container) extension.
+//! The containers are queried with the `docker` command line tool, or any compatible one like `podman`.
+
+use std::process::Command;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::error::{other, Context, Result};
+
+/// A running container
+///
+/// Both the output of `docker ps` and the one of `podman ps` are read,
+/// the latter having an `Id` and an array of `Names`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    /// Identifier of the container
+    #[serde(rename = "ID", alias = "Id")]
+    pub id: String,
+    /// Name of the container
+    #[serde(rename = "Names", deserialize_with = "deserialize_name")]
+    pub name: String,
+    /// Image the container was created from
+    #[serde(rename = "Image")]
+    pub image: String,
+    /// Human-readable status, e.g. `Up 2 hours`
+    #[serde(rename = "Status", default)]
+    pub status: String,
+}
+
+/// Deserialize the name of a container, given as is or as the first of an array of names
+fn deserialize_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Names {
+        One(String),
+        Many(Vec<String>),
+    }
+    match Names::deserialize(deserializer)? {
+        Names::One(name) => Ok(name),
+        Names::Many(names) => names
+            .into_iter()
+            .next()
+            .ok_or_else(|| serde::de::Error::custom("container without a name")),
+    }
+}
+
+impl Container {
+    /// The remote authority used by VSCode to attach to this container
+    ///
+    /// It has the form `attached-container+{hex}`, where `{hex}` is the hex-encoded
+    /// JSON description of the container.
+    pub fn remote_authority(&self) -> String {
+        let description = format!("{{\"containerName\":\"/{}\"}}", self.name);
+        format!("attached-container+{}", hex_encode(description.as_bytes()))
+    }
+}
+
+/// List the running containers
+///
+/// The `cli` command (e.g. `docker`, `podman`) is used to query them.
+///
+/// # Errors
+/// The call fails if the command cannot be executed or its output cannot be parsed.
+pub fn running_containers(cli: &str) -> Result<Vec<Container>> {
+    let output = Command::new(cli)
+        .args(["ps", "--format", "{{json .}}"])
+        .output()
+        .with_context(|| format!("Could not execute {}", cli))?;
+    if !output.status.success() {
+        return Err(other!(
+            "Could not list containers with {}: {}",
+            cli,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    parse_containers(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Get the working directory of the container, if it is set
+///
+/// # Errors
+/// The call fails if the command cannot be executed.
+pub fn working_dir(cli: &str, container: &Container) -> Result<Option<String>> {
+    let output = Command::new(cli)
+        .args([
+            "inspect",
+            "--format",
+            "{{.Config.WorkingDir}}",
+            &container.id,
+        ])
+        .output()
+        .with_context(|| format!("Could not execute {}", cli))?;
+    let dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok(Some(dir).filter(|d| output.status.success() && !d.is_empty()))
+}
+
+/// Parse the output of `ps --format '{{json .}}'`, one container per line
+fn parse_containers(output: &str) -> Result<Vec<Container>> {
+    output
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            serde_json::from_str(l).with_context(|| format!("Could not parse container {}", l))
+        })
+        .collect()
+}
+
+/// Encode bytes as a lowercase hexadecimal string
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_containers;
+
+    #[test]
+    fn parse_docker_ps() {
+        let output = r#"{"Command":"\"sleep infinity\"","ID":"4f1c2a","Image":"mcr.microsoft.com/devcontainers/rust:1","Names":"scranton-dev","State":"running","Status":"Up 2 hours"}
+{"ID":"9b8e7d","Image":"postgres:16","Names":"sabre-db","Status":"Up 5 minutes"}
+"#;
+        let containers = parse_containers(output).expect("could not parse containers");
+        assert_eq!(containers.len(), 2);
+        assert_eq!(containers[0].id, "4f1c2a");
+        assert_eq!(containers[0].name, "scranton-dev");
+        assert_eq!(
+            containers[0].image,
+            "mcr.microsoft.com/devcontainers/rust:1"
+        );
+        assert_eq!(containers[1].status, "Up 5 minutes");
+    }
+
+    #[test]
+    fn parse_podman_ps() {
+        let output = r#"{"AutoRemove":false,"Command":["sleep","infinity"],"Id":"7c3d5e0a","Image":"docker.io/library/postgres:16","Names":["sabre-db"],"State":"running","Status":"Up 5 minutes"}
+"#;
+        let containers = parse_containers(output).expect("could not parse containers");
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].id, "7c3d5e0a");
+        assert_eq!(containers[0].name, "sabre-db");
+        assert_eq!(containers[0].image, "docker.io/library/postgres:16");
+        assert_eq!(containers[0].status, "Up 5 minutes");
+    }
+
+    #[test]
+    fn container_remote_authority() {
+        let containers =
+            parse_containers(r#"{"ID":"9b8e7d","Image":"postgres:16","Names":"db"}"#).unwrap();
+        // hex of {"containerName":"/db"}
+        assert_eq!(
+            containers[0].remote_authority(),
+            "attached-container+7b22636f6e7461696e65724e616d65223a222f6462227d"
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Failures of the library
+//!
+//! The functions of the library return an [Error], which callers can match on.
+//! Along the way the failures are wrapped in [Error::Context], which tells what was being done,
+//! and [Error::root] finds the failure underneath:
+//! ```no_run
+//! use rofi_vscode_mode::{error::Error, utils::determine_vscode_flavor};
+//!
+//! match determine_vscode_flavor() {
+//!     Ok(flavor) => println!("Using {}", flavor.name()),
+//!     Err(e) if matches!(e.root(), Error::FlavorNotFound) => println!("Is VSCode installed?"),
+//!     Err(e) => eprintln!("{:?}", e),
+//! }
+//! ```
+
+use std::{fmt, io, path::PathBuf};
+
+use url::Url;
+
+/// Result of the functions of the library
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A failure of the library
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// No flavor is installed or configured
+    #[error("Could not find any suitable VSCode flavor")]
+    FlavorNotFound,
+    /// The flavor with the given name has no database holding its recent items
+    #[error("Could not find the state database for {0}")]
+    DbNotFound(String),
+    /// The database is locked by a running instance of VSCode for too long
+    #[error("Database {0:?} is locked")]
+    DbLocked(PathBuf),
+    /// The recent item at the given index could not be deserialized
+    #[error("Invalid recent item #{index}")]
+    DeserializeEntry {
+        index: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    /// The command could not be run
+    #[error("Could not run {cmd}")]
+    LaunchFailed {
+        cmd: String,
+        #[source]
+        source: io::Error,
+    },
+    /// The operation requires a local item, but the item has another URL
+    #[error("Not a file url {0}")]
+    NotLocal(Url),
+    /// A file or a command could not be read or written
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// A database could not be queried
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    /// Some JSON could not be parsed or written
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// A URL could not be parsed
+    #[error(transparent)]
+    Url(#[from] url::ParseError),
+    /// The configuration file could not be parsed
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+    /// A number could not be parsed
+    #[error(transparent)]
+    ParseInt(#[from] std::num::ParseIntError),
+    /// Any other failure, described by the message
+    #[error("{0}")]
+    Other(String),
+    /// The failure `source`, which happened while doing what `context` tells
+    #[error("{context}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Wrap the failure in [Error::Context] with the given description
+    pub fn context<C: fmt::Display>(self, context: C) -> Error {
+        Error::Context {
+            context: context.to_string(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The failure wrapped by the contexts, see [Error::Context]
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.root(),
+            e => e,
+        }
+    }
+}
+
+/// Tell what was being done when a failure happened, like [anyhow::Context]
+pub trait Context<T> {
+    /// Wrap the failure in [Error::Context] with the given description
+    fn context<C: fmt::Display>(self, context: C) -> Result<T>;
+
+    /// Wrap the failure in [Error::Context] with the description returned by `f`, which is only called on failure
+    fn with_context<C: fmt::Display, F: FnOnce() -> C>(self, f: F) -> Result<T>;
+}
+
+impl<T, E: Into<Error>> Context<T> for std::result::Result<T, E> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T> {
+        self.with_context(|| context)
+    }
+
+    fn with_context<C: fmt::Display, F: FnOnce() -> C>(self, f: F) -> Result<T> {
+        self.map_err(|e| e.into().context(f()))
+    }
+}
+
+/// A missing value is reported with the description alone
+impl<T> Context<T> for Option<T> {
+    fn context<C: fmt::Display>(self, context: C) -> Result<T> {
+        self.with_context(|| context)
+    }
+
+    fn with_context<C: fmt::Display, F: FnOnce() -> C>(self, f: F) -> Result<T> {
+        self.ok_or_else(|| Error::Other(f().to_string()))
+    }
+}
+
+/// An [Error::Other] with the formatted message
+macro_rules! other {
+    ($($arg:tt)*) => {
+        $crate::error::Error::Other(format!($($arg)*))
+    };
+}
+
+pub(crate) use other;
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{Context, Error};
+
+    #[test]
+    fn root_of_contexts() {
+        let res: Result<(), Error> = Err(Error::FlavorNotFound);
+        let error = res
+            .context("Could not list the recent items")
+            .context("Could not show the menu")
+            .unwrap_err();
+        assert!(matches!(error.root(), Error::FlavorNotFound));
+        assert_eq!(error.to_string(), "Could not show the menu");
+
+        let error = Err::<(), _>(io::Error::from(io::ErrorKind::NotFound))
+            .with_context(|| format!("Could not read {:?}", "storage.json"))
+            .unwrap_err();
+        assert!(matches!(error.root(), Error::Io(_)));
+        let error = None::<()>.context("Nothing here").unwrap_err();
+        assert!(matches!(error, Error::Other(_)));
+        assert_eq!(error.to_string(), "Nothing here");
+    }
+}
@@ -0,0 +1,142 @@
+//! Git repository utilities
+//!
+//! The branch of a repository is read directly from its `.git` directory, without running `git`,
+//! so that it is cheap enough to be done for every shown entry.
+//! Checking for uncommitted changes requires comparing the working tree with the index,
+//! which is left to `git status`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::error::{other, Context, Result};
+
+use crate::vscode::workspaces::{workspace_folders, Recent};
+
+/// Length of the abbreviated commit hash shown for a detached `HEAD`
+const SHORT_HASH_LEN: usize = 7;
+
+/// Find the git directory of the repository whose working tree is `dir`
+///
+/// In linked worktrees and submodules `.git` is a file pointing to the actual directory.
+pub fn git_dir(dir: &Path) -> Option<PathBuf> {
+    let dot_git = dir.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let contents = fs::read_to_string(&dot_git).ok()?;
+    let target = contents.trim().strip_prefix("gitdir:")?.trim();
+    Some(dir.join(target)).filter(|p| p.is_dir())
+}
+
+/// Get the branch checked out in the repository whose working tree is `dir`
+///
+/// A detached `HEAD` is shown as its abbreviated commit hash.
+/// Returns `None` if `dir` is not the root of a repository.
+pub fn current_branch(dir: &Path) -> Option<String> {
+    let head = fs::read_to_string(git_dir(dir)?.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref:") {
+        Some(reference) => {
+            let reference = reference.trim();
+            Some(
+                reference
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(reference)
+                    .to_string(),
+            )
+        }
+        None => Some(head.chars().take(SHORT_HASH_LEN).collect()),
+    }
+}
+
+/// State of the working tree of a repository
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoStatus {
+    /// The checked out branch, see [current_branch]
+    pub branch: Option<String>,
+    /// Whether there are uncommitted changes, see [is_dirty]
+    pub dirty: bool,
+}
+
+/// Tell whether the repository whose working tree is `dir` has uncommitted changes to tracked files
+///
+/// Untracked files are not considered, since listing them requires walking the whole tree.
+///
+/// # Errors
+/// The call fails if `git` cannot be executed or reports an error.
+pub fn is_dirty(dir: &Path) -> Result<bool> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args([
+            "status",
+            "--porcelain",
+            "--untracked-files=no",
+            "--ignore-submodules",
+        ])
+        .output()
+        .context("Could not execute git")?;
+    if !output.status.success() {
+        return Err(other!(
+            "git status failed in {:?}: {}",
+            dir,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(!output.stdout.is_empty())
+}
+
+/// The working tree of the repository of a recent item, if it is a local git repository
+///
+/// For a multi-root workspace, this is the first of its folders that is a repository.
+pub fn recent_repo(recent: &Recent) -> Option<PathBuf> {
+    let path = recent.file_path().ok()?;
+    match recent {
+        Recent::Folder { .. } => Some(path).filter(|p| git_dir(p).is_some()),
+        Recent::Workspace { .. } => workspace_folders(&path)
+            .ok()?
+            .into_iter()
+            .find(|p| git_dir(p).is_some()),
+        Recent::File { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::current_branch;
+
+    #[test]
+    fn read_current_branch() {
+        let dir = env::temp_dir().join(format!("rofi-vscode-mode-git-{}", process::id()));
+        let worktree = dir.join("worktree");
+        fs::create_dir_all(dir.join("repo").join(".git")).expect("could not create repo");
+        fs::create_dir_all(dir.join("linked")).expect("could not create git dir");
+        fs::create_dir_all(&worktree).expect("could not create worktree");
+
+        fs::write(
+            dir.join("repo").join(".git").join("HEAD"),
+            "ref: refs/heads/feature/beets\n",
+        )
+        .expect("could not write HEAD");
+        assert_eq!(
+            current_branch(&dir.join("repo")).as_deref(),
+            Some("feature/beets")
+        );
+
+        fs::write(worktree.join(".git"), "gitdir: ../linked\n").expect("could not write .git");
+        fs::write(
+            dir.join("linked").join("HEAD"),
+            "0dd79faa9035cb0db768dbe5812a110cc0814402\n",
+        )
+        .expect("could not write HEAD");
+        assert_eq!(current_branch(&worktree).as_deref(), Some("0dd79fa"));
+
+        assert_eq!(current_branch(&dir), None);
+        fs::remove_dir_all(&dir).expect("could not remove repo");
+    }
+}
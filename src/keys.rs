@@ -0,0 +1,240 @@
+//! Keybindings of the recent mode
+//!
+//! Besides Enter and Shift+Delete, the recent mode reacts to `kb-accept-alt` and to the custom keybindings
+//! `kb-custom-1` to `kb-custom-19`, each running a [KeyAction].
+//! The [Keymap] holds which action each of them runs,
+//! and can be changed with a list of `key=action` pairs, e.g. `kb-custom-1=copy-path,kb-accept-alt=new-window`.
+
+use std::{fmt, str::FromStr};
+
+use crate::error::{other, Error, Result};
+
+/// Number of custom keybindings of Rofi
+pub const CUSTOM_KEYS: u8 = 19;
+
+/// A keybinding of Rofi that runs an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// `kb-accept-alt`, Shift+Enter by default
+    AcceptAlt,
+    /// `kb-custom-N`, with the number of its custom command starting from 0 for `kb-custom-1`
+    Custom(u8),
+}
+
+impl FromStr for Key {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        if matches!(s.as_str(), "kb-accept-alt" | "alt-accept") {
+            return Ok(Key::AcceptAlt);
+        }
+        s.strip_prefix("kb-custom-")
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|n| (1..=CUSTOM_KEYS).contains(n))
+            .map(|n| Key::Custom(n - 1))
+            .ok_or_else(|| {
+                other!(
+                    "\"{}\" is not a keybinding, expected kb-accept-alt or kb-custom-1 to kb-custom-{}",
+                    s,
+                    CUSTOM_KEYS
+                )
+            })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::AcceptAlt => write!(f, "kb-accept-alt"),
+            Key::Custom(number) => write!(f, "kb-custom-{}", number + 1),
+        }
+    }
+}
+
+/// What a keybinding does with the selected entry, or with the whole list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    /// Open the entry, as Enter does
+    Open,
+    /// Browse the folder, or open anything else
+    Browse,
+    /// Mark the file, or unmark it, to compare two files
+    Mark,
+    /// Open the entry with the alternate arguments
+    OpenAlt,
+    /// Open the entry in a new window
+    NewWindow,
+    /// Copy the path of the entry, or its URI if it is remote
+    CopyPath,
+    /// Open a terminal in the directory of the entry
+    Terminal,
+    /// Remove the entry from its history, after confirmation if required, as Shift+Delete does
+    Delete,
+    /// Undo the last deletion
+    Undo,
+    /// Clear the whole history, after confirmation
+    Clear,
+    /// Move the entry to the top of its history
+    Bump,
+    /// Show the history of the next installed flavor
+    SwitchFlavor,
+    /// Open the entry with the installed flavor with this index
+    OpenWith(usize),
+}
+
+impl FromStr for KeyAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        if let Some(n) = s.strip_prefix("open-with-") {
+            return n
+                .parse::<usize>()
+                .ok()
+                .filter(|n| *n >= 1)
+                .map(|n| KeyAction::OpenWith(n - 1))
+                .ok_or_else(|| other!("\"{}\" does not number an installed flavor from 1", s));
+        }
+        match s.as_str() {
+            "open" => Ok(KeyAction::Open),
+            "browse" => Ok(KeyAction::Browse),
+            "mark" => Ok(KeyAction::Mark),
+            "open-alt" => Ok(KeyAction::OpenAlt),
+            "new-window" => Ok(KeyAction::NewWindow),
+            "copy-path" => Ok(KeyAction::CopyPath),
+            "terminal" => Ok(KeyAction::Terminal),
+            "delete-permanent" | "delete" => Ok(KeyAction::Delete),
+            "undo" => Ok(KeyAction::Undo),
+            "clear" => Ok(KeyAction::Clear),
+            "bump" => Ok(KeyAction::Bump),
+            "switch-flavor" => Ok(KeyAction::SwitchFlavor),
+            _ => Err(other!(
+                "\"{}\" is not an action, expected one of: open, browse, mark, open-alt, new-window, \
+                 copy-path, terminal, delete-permanent, undo, clear, bump, switch-flavor, open-with-N",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for KeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyAction::Open => write!(f, "open"),
+            KeyAction::Browse => write!(f, "browse"),
+            KeyAction::Mark => write!(f, "mark"),
+            KeyAction::OpenAlt => write!(f, "open-alt"),
+            KeyAction::NewWindow => write!(f, "new-window"),
+            KeyAction::CopyPath => write!(f, "copy-path"),
+            KeyAction::Terminal => write!(f, "terminal"),
+            KeyAction::Delete => write!(f, "delete-permanent"),
+            KeyAction::Undo => write!(f, "undo"),
+            KeyAction::Clear => write!(f, "clear"),
+            KeyAction::Bump => write!(f, "bump"),
+            KeyAction::SwitchFlavor => write!(f, "switch-flavor"),
+            KeyAction::OpenWith(i) => write!(f, "open-with-{}", i + 1),
+        }
+    }
+}
+
+/// The action run by each keybinding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    /// Action of `kb-accept-alt`
+    accept_alt: KeyAction,
+    /// Actions of the custom keybindings, by the number of their custom command
+    custom: Vec<Option<KeyAction>>,
+}
+
+impl Keymap {
+    /// The default keymap, with the given action for `kb-accept-alt` and `flavors` installed flavors
+    ///
+    /// `kb-custom-1` to `kb-custom-4` undo deletions, clear the history, move an entry to the top
+    /// and switch flavors, and the following ones open the selected entry with each installed flavor.
+    pub fn new(accept_alt: KeyAction, flavors: usize) -> Self {
+        let mut custom = vec![
+            Some(KeyAction::Undo),
+            Some(KeyAction::Clear),
+            Some(KeyAction::Bump),
+            Some(KeyAction::SwitchFlavor),
+        ];
+        custom.extend((0..flavors).map(|i| Some(KeyAction::OpenWith(i))));
+        custom.resize(usize::from(CUSTOM_KEYS), None);
+        Self { accept_alt, custom }
+    }
+
+    /// Bind the keys to the actions of comma-separated `key=action` pairs, keeping the other bindings
+    ///
+    /// # Errors
+    /// The call fails if a pair does not name a keybinding and an action.
+    pub fn bind(&mut self, pairs: &str) -> Result<()> {
+        for pair in pairs.split(',').filter(|p| !p.trim().is_empty()) {
+            let (key, action) = pair
+                .split_once('=')
+                .ok_or_else(|| other!("\"{}\" does not bind a key as key=action", pair))?;
+            let action = action.parse()?;
+            match key.parse()? {
+                Key::AcceptAlt => self.accept_alt = action,
+                Key::Custom(number) => self.custom[usize::from(number)] = Some(action),
+            }
+        }
+        Ok(())
+    }
+
+    /// The action run by `key`, if any
+    pub fn action(&self, key: Key) -> Option<KeyAction> {
+        match key {
+            Key::AcceptAlt => Some(self.accept_alt),
+            Key::Custom(number) => self.custom.get(usize::from(number)).copied().flatten(),
+        }
+    }
+
+    /// The first key running `action`, if any
+    pub fn key(&self, action: KeyAction) -> Option<Key> {
+        if self.accept_alt == action {
+            return Some(Key::AcceptAlt);
+        }
+        self.custom
+            .iter()
+            .position(|a| *a == Some(action))
+            .and_then(|n| u8::try_from(n).ok())
+            .map(Key::Custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, KeyAction, Keymap};
+
+    #[test]
+    fn default_keymap() {
+        let keymap = Keymap::new(KeyAction::Browse, 2);
+        assert_eq!(keymap.action(Key::AcceptAlt), Some(KeyAction::Browse));
+        assert_eq!(keymap.action(Key::Custom(0)), Some(KeyAction::Undo));
+        assert_eq!(keymap.action(Key::Custom(3)), Some(KeyAction::SwitchFlavor));
+        assert_eq!(keymap.action(Key::Custom(5)), Some(KeyAction::OpenWith(1)));
+        assert_eq!(keymap.action(Key::Custom(6)), None);
+        assert_eq!(keymap.key(KeyAction::Clear), Some(Key::Custom(1)));
+        assert_eq!(keymap.key(KeyAction::Terminal), None);
+    }
+
+    #[test]
+    fn bind_keys() {
+        let mut keymap = Keymap::new(KeyAction::Browse, 0);
+        keymap
+            .bind("kb-custom-1=copy-path, kb-custom-10=open-with-2,alt-accept=new-window")
+            .unwrap();
+        assert_eq!(keymap.action(Key::Custom(0)), Some(KeyAction::CopyPath));
+        assert_eq!(keymap.action(Key::Custom(1)), Some(KeyAction::Clear));
+        assert_eq!(keymap.action(Key::Custom(9)), Some(KeyAction::OpenWith(1)));
+        assert_eq!(keymap.action(Key::AcceptAlt), Some(KeyAction::NewWindow));
+        assert_eq!(keymap.key(KeyAction::Undo), None);
+        assert_eq!(Key::Custom(9).to_string(), "kb-custom-10");
+
+        assert!(keymap.bind("kb-custom-20=undo").is_err());
+        assert!(keymap.bind("kb-custom-1=fly").is_err());
+        assert!(keymap.bind("kb-custom-1").is_err());
+        assert!(keymap.bind("kb-custom-1=open-with-0").is_err());
+    }
+}
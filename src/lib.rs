@@ -1,12 +1,64 @@
 //! A very handy Rofi menu to open recent Visual Studio Code workspacess and files
 //!
 //! [rofi::VSCodeRecentMode] provides a [Rofi](https://github.com/davatorium/rofi) mode named `vscode-recent` to open recent items in VSCode.
+//! [rofi::profiles::VSCodeProfilesMode] provides a mode named `vscode-profiles` to open VSCode with one of its profiles.
+//! [rofi::ssh::VSCodeSshMode] provides a mode named `vscode-ssh` to connect to the hosts in the SSH configuration and to the [tunnels].
+//! [rofi::containers::VSCodeContainersMode] provides a mode named `vscode-containers` to attach VSCode to running containers.
+//! [rofi::extensions::VSCodeExtensionsMode] provides a mode named `vscode-extensions` to manage the installed extensions.
+//! [rofi::tasks::VSCodeTasksMode] provides a mode named `vscode-tasks` to run the tasks of a recent workspace.
+//! [rofi::files::VSCodeFilesMode] provides a mode named `vscode-files` to open the files recently edited in a workspace.
+//! [rofi::projects::VSCodeProjectsMode] provides a mode named `vscode-projects` to open the projects saved with the Project Manager extension.
+//! [rofi::codespaces::VSCodeCodespacesMode] provides a mode named `vscode-codespaces` to open GitHub Codespaces.
+//! [rofi::wsl::VSCodeWslMode] provides a mode named `vscode-wsl` to open VSCode in the installed WSL distributions.
 //!
-//! This plugin can be configured with environment variables:
-//! - `ROFI_VSCODE_FLAVOR=[code|code-insiders|code-oss|vscodium]` sets the preferred VSCode flavor to be used
+//! Since Rofi loads a single mode from each plugin, the exported mode is chosen at build time
+//! by setting `ROFI_VSCODE_EXPORT_MODE=[recent|profiles|ssh|containers|extensions|tasks|files|projects|codespaces|wsl]`.
+//!
+//! This plugin can be configured with environment variables,
+//! or with the equivalent keys of the [config] file (e.g. `icon_mode` for `ROFI_VSCODE_ICON_MODE`):
+//! - `ROFI_VSCODE_FLAVOR=[code|code-insiders|code-oss|vscodium|cursor|windsurf|all]` sets the preferred VSCode flavor to be used
+//! - `ROFI_VSCODE_CMD` and `ROFI_VSCODE_CONFIG_DIR` override the command and configuration directory of the flavor
+//! - `ROFI_VSCODE_USER_DATA_DIR` sets the `--user-data-dir` the flavor is run with, which holds its configuration
+//! - `ROFI_VSCODE_ARGS` sets extra arguments passed to the flavor whenever it opens something, e.g. `--new-window`
+//! - `ROFI_VSCODE_ALT_ARGS` sets the arguments used instead when an entry is opened with `kb-accept-alt`
+//! - `ROFI_VSCODE_PROFILE=[all|name]` also shows the recent items of every profile, or only those of the named one
+//! - `ROFI_VSCODE_SERVER_HOST` names this host in the URIs printed when opening the items of a VSCode Server, see [vscode::server]
+//! - `ROFI_VSCODE_DB` sets the path of the `state.vscdb` database holding the recent items
+//! - `ROFI_VSCODE_DEDUP=[workspace|folder]` collapses folders that are also part of a workspace
+//! - `ROFI_VSCODE_MAX_ENTRIES` limits the number of recent items shown
+//! - `ROFI_VSCODE_KINDS=workspaces,folders` shows only the recent items of the given kinds
+//! - `ROFI_VSCODE_RESOLVE_SYMLINKS=true` resolves symlinks in the paths of recent items, showing each item once
+//! - `ROFI_VSCODE_WITHIN` shows only the recent items under a directory, e.g. `~/work`
+//! - `ROFI_VSCODE_SORT=[recency|last-opened|most-used]` sorts the recent items by recency, by when they were last opened or by how often they are opened
+//! - `ROFI_VSCODE_REMOTE=[mixed|last|hidden]` places the remote and virtual items after the local ones, or hides them
+//! - `ROFI_VSCODE_LOCAL_ONLY=true` shows only the local items, as `ROFI_VSCODE_REMOTE=hidden` does
+//! - `ROFI_VSCODE_HIDE_VIRTUAL=true` hides the items in virtual filesystems, e.g. GitHub repositories, keeping the other remote ones
+//! - `ROFI_VSCODE_BROWSER_FALLBACK=true` opens the virtual and remote items in VSCode for the Web when the flavor cannot open them, see [remote]
+//! - `ROFI_VSCODE_SHOW_TIME=true` shows when each workspace or folder was last opened
+//! - `ROFI_VSCODE_SHOW_BRANCH=true` shows the git branch checked out in each workspace or folder
+//! - `ROFI_VSCODE_SHOW_DIRTY=true` marks the workspaces and folders with uncommitted changes
+//! - `ROFI_VSCODE_SHOW_FOLDERS=[count|names]` shows the folders of each multi-root workspace
+//! - `ROFI_VSCODE_FOCUS_EXISTING=true` activates the window already showing a workspace or folder instead of opening it, see [window]
+//! - `ROFI_VSCODE_KEYS=kb-custom-1=copy-path,...` binds the keybindings of `vscode-recent` to other actions, see [keys]
+//! - `ROFI_VSCODE_TERMINAL` sets the terminal opened by the `terminal` action, e.g. `kitty`
+//! - `ROFI_VSCODE_MULTI_SELECT=true` marks files with `kb-accept-alt`, to compare two of them with `--diff`
+//! - `ROFI_VSCODE_PRUNE_ON_WRITE=true` drops the items whose paths do not exist anymore whenever the history is written
+//! - `ROFI_VSCODE_NOTIFY=[errors|all]` sends a desktop notification when opening an item fails, or whenever one is opened, see [notify]
+//! - `ROFI_VSCODE_NO_USAGE=true` stops recording the items opened through the plugin or the command line, see [stats]
+//! - `ROFI_VSCODE_CONFIRM_DELETE=true` requires deleting an entry twice to remove it from the history
+//! - `ROFI_VSCODE_SOURCES=xbel,zoxide,repos` also shows the items of other [sources], e.g. the source code files recently used with any application
+//! - `ROFI_VSCODE_REPOS_ROOT` sets the directory scanned for repositories by the `repos` source, instead of asking ghq
+//! - `ROFI_VSCODE_LABEL` lays out the labels from placeholders, e.g. `{basename}  {dir}  {remote}`, see [vscode::label]
+//! - `ROFI_VSCODE_SHORTEN` shortens the labels longer than the given number of characters, also per mode, e.g. `ROFI_VSCODE_SHORTEN_FILES`
+//! - `ROFI_VSCODE_GROUP=workspaces,folders,files` groups the recent items by kind, in the given order
 //! - `ROFI_VSCODE_ICON_MODE=[none|theme|nerd]` controls how icons are displayed
 //! - `ROFI_VSCODE_ICON_FONT` controls the font to render the icon glyphs in case the `nerd` option is chosen
 //! - `ROFI_VSCODE_ICON_COLOR` controls the color of the font in case the `nerd` option is chosen
+//! - `ROFI_VSCODE_THUMBNAILS=true` shows the thumbnails of local images instead of their icons
+//! - `ROFI_VSCODE_SSH_PATHS=host=path,...` sets the remote path opened by default on each SSH host or tunnel
+//! - `ROFI_VSCODE_CONTAINER_CLI=[docker|podman|...]` sets the command used to list the containers
+//! - `ROFI_VSCODE_TASK_TERMINAL` sets the terminal command to run tasks into, e.g. `alacritty -e`
+//! - `ROFI_VSCODE_LOG=[error|warn|info|debug|trace]` sets which diagnostic messages are written to stderr, see [logging]
 //!
 //! For more details please see the README in the repository.
 
@@ -16,6 +68,36 @@ pub mod vscode;
 
 pub mod utils;
 
+pub mod config;
+
+pub mod error;
+
+pub mod sources;
+
+pub mod logging;
+
+pub mod remote;
+
+pub mod ssh;
+
+pub mod containers;
+
+pub mod codespaces;
+
+pub mod tunnels;
+
+pub mod wsl;
+
+pub mod git;
+
+pub mod window;
+
+pub mod keys;
+
+pub mod stats;
+
+pub mod notify;
+
 #[cfg(feature = "rofi")]
 pub mod rofi;
 
@@ -24,5 +106,32 @@ pub use rofi_mode;
 
 // Export modes
 
-#[cfg(feature = "rofi")]
-rofi_mode::export_mode!(rofi::VSCodeRecentMode);
+#[cfg(all(feature = "rofi", export_mode = "recent"))]
+rofi_mode::export_mode!(rofi::Exported<rofi::VSCodeRecentMode>);
+
+#[cfg(all(feature = "rofi", export_mode = "profiles"))]
+rofi_mode::export_mode!(rofi::Exported<rofi::profiles::VSCodeProfilesMode>);
+
+#[cfg(all(feature = "rofi", export_mode = "ssh"))]
+rofi_mode::export_mode!(rofi::Exported<rofi::ssh::VSCodeSshMode>);
+
+#[cfg(all(feature = "rofi", export_mode = "containers"))]
+rofi_mode::export_mode!(rofi::Exported<rofi::containers::VSCodeContainersMode>);
+
+#[cfg(all(feature = "rofi", export_mode = "extensions"))]
+rofi_mode::export_mode!(rofi::Exported<rofi::extensions::VSCodeExtensionsMode>);
+
+#[cfg(all(feature = "rofi", export_mode = "tasks"))]
+rofi_mode::export_mode!(rofi::Exported<rofi::tasks::VSCodeTasksMode>);
+
+#[cfg(all(feature = "rofi", export_mode = "files"))]
+rofi_mode::export_mode!(rofi::Exported<rofi::files::VSCodeFilesMode>);
+
+#[cfg(all(feature = "rofi", export_mode = "projects"))]
+rofi_mode::export_mode!(rofi::Exported<rofi::projects::VSCodeProjectsMode>);
+
+#[cfg(all(feature = "rofi", export_mode = "codespaces"))]
+rofi_mode::export_mode!(rofi::Exported<rofi::codespaces::VSCodeCodespacesMode>);
+
+#[cfg(all(feature = "rofi", export_mode = "wsl"))]
+rofi_mode::export_mode!(rofi::Exported<rofi::wsl::VSCodeWslMode>);
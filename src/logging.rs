@@ -0,0 +1,185 @@
+//! Diagnostic messages on stderr
+//!
+//! Messages are written when their level is at most the one set with `ROFI_VSCODE_LOG=[error|warn|info|debug|trace]`,
+//! which defaults to `error`. Unlike the other settings, it is only read from the environment,
+//! so that problems with the configuration file can be logged too.
+//!
+//! Each message is tagged with its level and the module it comes from, e.g.
+//! `[DEBUG rofi_vscode_mode::vscode] Running "code" "--folder-uri" "file:///home/dwight/beet-farm"`.
+//! [Span]s time an operation, logging when it starts and ends.
+
+use std::{
+    env, fmt,
+    str::FromStr,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use crate::error::{other, Error, Result};
+
+const ENV_LOG: &str = "ROFI_VSCODE_LOG";
+
+/// Severity of a message, from the most to the least severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl FromStr for Level {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "warn" | "warning" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            "trace" => Ok(Self::Trace),
+            _ => Err(other!("Unrecognized log level {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "ERROR"),
+            Self::Warn => write!(f, "WARN"),
+            Self::Info => write!(f, "INFO"),
+            Self::Debug => write!(f, "DEBUG"),
+            Self::Trace => write!(f, "TRACE"),
+        }
+    }
+}
+
+/// The maximum level of the messages to write, read once
+static MAX_LEVEL: OnceLock<Level> = OnceLock::new();
+
+/// Tells whether messages of the given `level` are written
+pub fn enabled(level: Level) -> bool {
+    let max_level = MAX_LEVEL.get_or_init(|| {
+        env::var(ENV_LOG)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(Level::Error)
+    });
+    level <= *max_level
+}
+
+/// Write a message from the module `target`, if its `level` is enabled
+///
+/// Within the crate, the `error!`, `warning!`, `info!`, `debug!` and `trace!` macros fill in the target.
+pub fn log(level: Level, target: &str, args: fmt::Arguments<'_>) {
+    if enabled(level) {
+        eprintln!("[{} {}] {}", level, target, args);
+    }
+}
+
+/// Format the duration of an operation, e.g. `12.3 ms`
+fn format_duration(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// An operation being timed, logged at the debug level when it starts and when it is dropped
+///
+/// Within the crate, create it with the `span!` macro.
+pub struct Span {
+    target: &'static str,
+    name: String,
+    start: Instant,
+}
+
+impl Span {
+    /// Start timing the operation `name` of the module `target`
+    ///
+    /// Nothing is logged nor allocated if the debug level is disabled.
+    pub fn enter(target: &'static str, name: fmt::Arguments<'_>) -> Option<Self> {
+        if !enabled(Level::Debug) {
+            return None;
+        }
+        let name = name.to_string();
+        log(Level::Debug, target, format_args!("> {}", name));
+        Some(Self {
+            target,
+            name,
+            start: Instant::now(),
+        })
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        log(
+            Level::Debug,
+            self.target,
+            format_args!(
+                "< {} ({})",
+                self.name,
+                format_duration(self.start.elapsed())
+            ),
+        );
+    }
+}
+
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)*) => {
+        $crate::logging::log($level, module_path!(), format_args!($($arg)*))
+    };
+}
+
+macro_rules! error {
+    ($($arg:tt)*) => { $crate::logging::log_at!($crate::logging::Level::Error, $($arg)*) };
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => { $crate::logging::log_at!($crate::logging::Level::Warn, $($arg)*) };
+}
+
+#[allow(unused_macros)]
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::logging::log_at!($crate::logging::Level::Info, $($arg)*) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::logging::log_at!($crate::logging::Level::Debug, $($arg)*) };
+}
+
+macro_rules! trace {
+    ($($arg:tt)*) => { $crate::logging::log_at!($crate::logging::Level::Trace, $($arg)*) };
+}
+
+/// Time the enclosing scope, see [Span]
+///
+/// The span must be bound to a variable, e.g. `let _span = span!("Reading {:?}", path);`.
+macro_rules! span {
+    ($($arg:tt)*) => {
+        $crate::logging::Span::enter(module_path!(), format_args!($($arg)*))
+    };
+}
+
+#[allow(unused_imports)]
+pub(crate) use {debug, error, info, log_at, span, trace, warning};
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{format_duration, Level};
+
+    #[test]
+    fn parse_level() {
+        assert_eq!("debug".parse::<Level>().unwrap(), Level::Debug);
+        assert_eq!("WARNING".parse::<Level>().unwrap(), Level::Warn);
+        assert!("verbose".parse::<Level>().is_err());
+        assert!(Level::Error < Level::Trace);
+    }
+
+    #[test]
+    fn duration() {
+        assert_eq!(format_duration(Duration::from_micros(12345)), "12.3 ms");
+    }
+}
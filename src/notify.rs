@@ -0,0 +1,155 @@
+//! Desktop notifications
+//!
+//! Rofi closes as soon as an item is selected, so a command that fails afterwards goes unnoticed,
+//! and so do the errors of the command line tool when it is bound to a key.
+//! With the `ROFI_VSCODE_NOTIFY` setting, see [NotifyLevel], the launches are reported with `notify-send` from libnotify.
+//! The commands are then run by [watched], which sends a notification if they exit with an error.
+
+use std::{fmt, process::Command, str::FromStr};
+
+use crate::error::{other, Context, Error, Result};
+
+/// Name of the application sending the notifications
+const APP_NAME: &str = "rofi-vscode-mode";
+/// Icon of the notifications about errors
+const ERROR_ICON: &str = "dialog-error";
+
+/// Which launches are notified
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NotifyLevel {
+    /// None of them (default)
+    #[default]
+    Never,
+    /// Only those that fail
+    Errors,
+    /// Every one, e.g. "Opening beet-farm in VSCodium…"
+    All,
+}
+
+impl FromStr for NotifyLevel {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "never" | "none" | "off" | "false" => Ok(Self::Never),
+            "errors" | "error" => Ok(Self::Errors),
+            "all" | "always" | "true" => Ok(Self::All),
+            _ => Err(other!("Unrecognized notification level {}", s)),
+        }
+    }
+}
+
+/// Urgency of a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Normal,
+    Critical,
+}
+
+impl fmt::Display for Urgency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Urgency::Normal => write!(f, "normal"),
+            Urgency::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+/// Arguments of `notify-send` before the summary and the body
+fn notify_args(urgency: Urgency) -> Vec<String> {
+    let mut args = vec![
+        "--app-name".to_string(),
+        APP_NAME.to_string(),
+        "--urgency".to_string(),
+        urgency.to_string(),
+    ];
+    if urgency == Urgency::Critical {
+        args.extend(["--icon".to_string(), ERROR_ICON.to_string()]);
+    }
+    args
+}
+
+/// Send a notification with `notify-send`
+///
+/// # Errors
+/// The call fails if `notify-send` cannot be executed or reports an error.
+pub fn notify(urgency: Urgency, summary: &str, body: &str) -> Result<()> {
+    let status = Command::new("notify-send")
+        .args(notify_args(urgency))
+        .arg(summary)
+        .arg(body)
+        .status()
+        .context("Could not execute notify-send")?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(other!("notify-send exited with {}", status)),
+    }
+}
+
+/// A command running `cmd` in a shell, which sends a notification titled `summary` if it exits with an error
+///
+/// The environment and working directory of `cmd` are kept, and the shell exits with the same status.
+pub fn watched(cmd: &Command, summary: &str) -> Command {
+    let script = format!(
+        r#""$@" || {{ status=$?; notify-send {} "$0" "$1 exited with status $status"; exit $status; }}"#,
+        notify_args(Urgency::Critical).join(" ")
+    );
+    let mut sh = Command::new("sh");
+    sh.arg("-c")
+        .arg(script)
+        .arg(summary)
+        .arg(cmd.get_program())
+        .args(cmd.get_args());
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => sh.env(key, value),
+            None => sh.env_remove(key),
+        };
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        sh.current_dir(dir);
+    }
+    sh
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{process::Command, str::FromStr};
+
+    use super::{watched, NotifyLevel};
+
+    #[test]
+    fn parse_levels() {
+        assert_eq!(
+            NotifyLevel::from_str("errors").unwrap(),
+            NotifyLevel::Errors
+        );
+        assert_eq!(NotifyLevel::from_str(" All ").unwrap(), NotifyLevel::All);
+        assert_eq!(NotifyLevel::from_str("off").unwrap(), NotifyLevel::Never);
+        assert!(NotifyLevel::from_str("sometimes").is_err());
+        assert!(NotifyLevel::Errors < NotifyLevel::All);
+    }
+
+    #[test]
+    fn watched_command() {
+        let mut cmd = Command::new("false");
+        cmd.arg("--folder-uri").arg("file:///srv/beet farm");
+        cmd.env("ROFI_VSCODE_TEST", "1").current_dir("/srv");
+        let sh = watched(&cmd, "Could not open beet farm");
+
+        assert_eq!(sh.get_program(), "sh");
+        let args: Vec<_> = sh.get_args().collect();
+        assert_eq!(args[0], "-c");
+        assert_eq!(
+            args[2..],
+            [
+                "Could not open beet farm",
+                "false",
+                "--folder-uri",
+                "file:///srv/beet farm"
+            ]
+        );
+        assert_eq!(sh.get_envs().count(), 1);
+        assert_eq!(sh.get_current_dir(), cmd.get_current_dir());
+    }
+}
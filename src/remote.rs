@@ -0,0 +1,253 @@
+//! Remote authorities
+//!
+//! Items opened with the [Remote Development](https://code.visualstudio.com/docs/remote/remote-overview)
+//! extensions are located by a remote authority of the form `{kind}+{id}`, e.g. `ssh-remote+devbox`.
+//! Some kinds encode their identifier as hex-encoded JSON, e.g. the containers.
+//! Virtual items, e.g. the GitHub repositories opened with `vscode-vfs://github/...` URIs, have no authority.
+//!
+//! Some of them can be opened in the browser as well, see [web_fallback].
+
+use std::path::Path;
+
+use percent_encoding::percent_decode_str;
+use serde_json::Value;
+use url::Url;
+
+/// The web page opening an item in VSCode for the Web, and the extension the flavor needs to open it instead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebFallback {
+    /// URL of the page
+    pub url: String,
+    /// Identifier of the extension, compared without case
+    pub extension: &'static str,
+}
+
+/// Describe the remote `authority` for display, e.g. `SSH: devbox` for `ssh-remote+devbox`
+///
+/// The kinds are named as in VSCode's status bar, and unknown ones are described by their raw identifier.
+pub fn describe(authority: &str) -> String {
+    let (kind, id) = authority.split_once('+').unwrap_or((authority, ""));
+    match kind {
+        "ssh-remote" => format!("SSH: {}", ssh_host(id)),
+        "wsl" => format!("WSL: {}", id),
+        "dev-container" => format!("Dev Container: {}", container_name(id)),
+        "attached-container" => format!("Container: {}", container_name(id)),
+        "codespaces" => format!("Codespaces: {}", id),
+        "tunnel" => format!("Tunnel: {}", id),
+        _ if id.is_empty() => authority.to_string(),
+        _ => format!("{}: {}", kind, id),
+    }
+}
+
+/// A location in a GitHub repository opened as a virtual workspace, e.g. `vscode-vfs://github/{owner}/{repo}/{path}`
+///
+/// These are opened with the GitHub Repositories extension, which fetches the files on demand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GithubLocation {
+    /// The repository, of the form `{owner}/{repo}`
+    pub repo: String,
+    /// Path inside the repository, empty for its root
+    pub path: String,
+}
+
+impl GithubLocation {
+    /// The location as shown to the user, e.g. `{owner}/{repo}/src`
+    pub fn display(&self) -> String {
+        if self.path.is_empty() {
+            self.repo.clone()
+        } else {
+            format!("{}/{}", self.repo, self.path)
+        }
+    }
+}
+
+/// The location in a GitHub repository of the item at `url`, if it is one
+///
+/// The authority may carry an encoded reference, e.g. `github+7b22...`, and the branch may be given as the `ref` query parameter.
+/// Both are kept in `url` when the item is opened.
+pub fn github_location(url: &Url) -> Option<GithubLocation> {
+    if url.scheme() != "vscode-vfs" || split_host(url)?.0 != "github" {
+        return None;
+    }
+    let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+    let repo = format!("{}/{}", segments.next()?, segments.next()?);
+    let path: Vec<String> = segments
+        .map(|s| percent_decode_str(s).decode_utf8_lossy().into_owned())
+        .collect();
+    Some(GithubLocation {
+        repo: percent_decode_str(&repo).decode_utf8_lossy().into_owned(),
+        path: path.join("/"),
+    })
+}
+
+/// Kind and identifier of the host of a virtual or remote `url`, e.g. `github` and an encoded reference
+fn split_host(url: &Url) -> Option<(&str, &str)> {
+    let host = url.host_str()?;
+    Some(
+        host.split_once('+')
+            .or_else(|| host.split_once("%2B"))
+            .or_else(|| host.split_once("%2b"))
+            .unwrap_or((host, "")),
+    )
+}
+
+/// The web page that opens the item located by `url`, if there is one
+///
+/// - GitHub repositories are opened in [github.dev](https://github.dev), and Azure Repos in [vscode.dev](https://vscode.dev)
+/// - Codespaces are opened in their own page, e.g. `https://fuzzy-robot.github.dev`
+/// - Tunnels are opened in [vscode.dev](https://vscode.dev), with the path of the item
+///
+/// Other remotes, e.g. SSH hosts, are only reachable from the desktop flavors.
+pub fn web_fallback(url: &Url) -> Option<WebFallback> {
+    let (kind, id) = split_host(url)?;
+    let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+    let (url, extension) = match (url.scheme(), kind) {
+        ("vscode-vfs", "github") => (
+            format!("https://github.dev/{}", github_location(url)?.repo),
+            "github.remotehub",
+        ),
+        ("vscode-vfs", "azurerepos") if segments.len() >= 3 => (
+            format!("https://vscode.dev/azurerepos/{}", segments[..3].join("/")),
+            "ms-vscode.azure-repos",
+        ),
+        ("vscode-remote", "codespaces") if !id.is_empty() => {
+            (format!("https://{}.github.dev", id), "github.codespaces")
+        }
+        ("vscode-remote", "tunnel") if !id.is_empty() => (
+            format!("https://vscode.dev/tunnel/{}{}", id, url.path()),
+            "ms-vscode.remote-server",
+        ),
+        _ => return None,
+    };
+    Some(WebFallback { url, extension })
+}
+
+/// Decode a hex-encoded identifier, as the ones of containers
+fn hex_decode(hex: &str) -> Option<String> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// The host of an SSH identifier, which is either the host itself or hex-encoded JSON with a `hostName`
+fn ssh_host(id: &str) -> String {
+    hex_decode(id)
+        .and_then(|json| serde_json::from_str::<Value>(&json).ok())
+        .and_then(|value| value.get("hostName")?.as_str().map(String::from))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// The name of a container from its hex-encoded identifier
+///
+/// This is either JSON with the `containerName`, see [crate::containers::Container::remote_authority],
+/// JSON with the `hostPath` of the folder holding the container configuration, or that path alone.
+/// The folder name is used in the latter cases.
+fn container_name(id: &str) -> String {
+    let Some(decoded) = hex_decode(id) else {
+        return id.to_string();
+    };
+    let folder_name = |path: &str| {
+        Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string())
+    };
+    match serde_json::from_str::<Value>(&decoded) {
+        Ok(value) => {
+            if let Some(name) = value.get("containerName").and_then(Value::as_str) {
+                name.trim_start_matches('/').to_string()
+            } else if let Some(path) = value.get("hostPath").and_then(Value::as_str) {
+                folder_name(path)
+            } else {
+                decoded
+            }
+        }
+        Err(_) => folder_name(&decoded),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::{describe, github_location, web_fallback};
+
+    #[test]
+    fn describe_authorities() {
+        assert_eq!(describe("ssh-remote+devbox"), "SSH: devbox");
+        // {"hostName":"devbox"}
+        assert_eq!(
+            describe("ssh-remote+7b22686f73744e616d65223a22646576626f78227d"),
+            "SSH: devbox"
+        );
+        assert_eq!(describe("wsl+Ubuntu"), "WSL: Ubuntu");
+        // {"containerName":"/db"}
+        assert_eq!(
+            describe("attached-container+7b22636f6e7461696e65724e616d65223a222f6462227d"),
+            "Container: db"
+        );
+        // /home/dwight/beet-farm
+        assert_eq!(
+            describe("dev-container+2f686f6d652f6477696768742f626565742d6661726d"),
+            "Dev Container: beet-farm"
+        );
+        assert_eq!(
+            describe("codespaces+fuzzy-robot"),
+            "Codespaces: fuzzy-robot"
+        );
+        assert_eq!(describe("k8s-container+pod"), "k8s-container: pod");
+        assert_eq!(describe("mystery"), "mystery");
+    }
+
+    #[test]
+    fn github_locations() {
+        let location = |url: &str| github_location(&Url::parse(url).unwrap());
+        let root = location("vscode-vfs://github/dunder/paper?ref=main").unwrap();
+        assert_eq!(root.repo, "dunder/paper");
+        assert_eq!(root.display(), "dunder/paper");
+        assert_eq!(
+            location("vscode-vfs://github%2B7b2276223a317d/dunder/paper/sales%20deck/q3.md")
+                .unwrap()
+                .display(),
+            "dunder/paper/sales deck/q3.md"
+        );
+        assert_eq!(location("vscode-vfs://github/dunder"), None);
+        assert_eq!(location("vscode-vfs://azurerepos/dunder/paper/sales"), None);
+        assert_eq!(location("file:///srv/dunder/paper"), None);
+    }
+
+    #[test]
+    fn web_fallbacks() {
+        let web = |url: &str| web_fallback(&Url::parse(url).unwrap()).map(|w| w.url);
+        let github = web_fallback(&Url::parse("vscode-vfs://github/dunder/paper/src").unwrap());
+        assert_eq!(
+            github.as_ref().map(|w| w.url.as_str()),
+            Some("https://github.dev/dunder/paper")
+        );
+        assert_eq!(github.map(|w| w.extension), Some("github.remotehub"));
+        assert_eq!(
+            web("vscode-vfs://github%2B7b2276223a317d/dunder/paper").as_deref(),
+            Some("https://github.dev/dunder/paper")
+        );
+        assert_eq!(web("vscode-vfs://github/dunder"), None);
+        assert_eq!(
+            web("vscode-vfs://azurerepos/dunder/paper/sales").as_deref(),
+            Some("https://vscode.dev/azurerepos/dunder/paper/sales")
+        );
+        assert_eq!(
+            web("vscode-remote://codespaces+fuzzy-robot/workspaces/paper").as_deref(),
+            Some("https://fuzzy-robot.github.dev")
+        );
+        assert_eq!(
+            web("vscode-remote://tunnel+scranton/home/michael").as_deref(),
+            Some("https://vscode.dev/tunnel/scranton/home/michael")
+        );
+        assert_eq!(web("vscode-remote://ssh-remote+devbox/srv"), None);
+        assert_eq!(web("file:///srv/beet-farm"), None);
+    }
+}
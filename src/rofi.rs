@@ -1,20 +1,80 @@
 //! Rofi modes and related utilities
 
-use std::env;
-
-use super::utils::determine_vscode_flavor;
+use super::config;
+use super::error::Error;
+use super::git::{current_branch, is_dirty, recent_repo, RepoStatus};
+use super::keys::{Key, KeyAction, Keymap};
+use super::logging::{debug, error, trace};
+use super::sources::{all_sources, fetch_recents, FlavorHistory, RecentSource};
+use super::utils::{
+    copy_to_clipboard, determine_keymap, determine_label_template, determine_recent_options,
+    determine_shorten, determine_sources, determine_vscode_flavors, expand_path,
+    format_relative_time, open_recent, open_terminal, shorten_path, split_location,
+    with_extra_args,
+};
 use super::vscode::{
-    untildify,
-    workspaces::{recently_opened_from_storage, store_recently_opened, Recent},
-    Flavor,
+    icons::thumbnail,
+    label::LabelTemplate,
+    workspace_storage::LastOpened,
+    workspaces::{workspace_folder_names, Recent},
+    EditorFlavor, Flavor, WithArgs,
 };
-use anyhow::anyhow;
-use pangocairo::{self, cairo, pango};
+use super::window::{detect_backend, focus_existing, WindowBackend};
+use anyhow::{anyhow, Context};
+use browser::Browser;
+use pangocairo::{self, cairo, glib, pango};
 use rofi_mode::{self as rofi, Action, Api, Event, Matcher};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+use url::Url;
+
+mod browser;
+pub mod codespaces;
+pub mod containers;
+pub mod extensions;
+pub mod files;
+pub mod profiles;
+pub mod projects;
+pub mod ssh;
+pub mod tasks;
+pub mod wsl;
 
 const ENV_ICON_MODE: &str = "ROFI_VSCODE_ICON_MODE";
 const ENV_ICON_FONT: &str = "ROFI_VSCODE_ICON_FONT";
 const ENV_ICON_COLOR: &str = "ROFI_VSCODE_ICON_COLOR";
+const ENV_THUMBNAILS: &str = "ROFI_VSCODE_THUMBNAILS";
+const ENV_SHOW_TIME: &str = "ROFI_VSCODE_SHOW_TIME";
+const ENV_SHOW_BRANCH: &str = "ROFI_VSCODE_SHOW_BRANCH";
+const ENV_SHOW_DIRTY: &str = "ROFI_VSCODE_SHOW_DIRTY";
+const ENV_SHOW_FOLDERS: &str = "ROFI_VSCODE_SHOW_FOLDERS";
+const ENV_CONFIRM_DELETE: &str = "ROFI_VSCODE_CONFIRM_DELETE";
+const ENV_ALT_ARGS: &str = "ROFI_VSCODE_ALT_ARGS";
+const ENV_MULTI_SELECT: &str = "ROFI_VSCODE_MULTI_SELECT";
+const ENV_FOCUS_EXISTING: &str = "ROFI_VSCODE_FOCUS_EXISTING";
+
+/// Time within which a deletion must be confirmed
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(5);
+/// Number of deletions that can be undone
+const UNDO_STACK_SIZE: usize = 16;
+/// How often the databases are checked for changes while the menu is open
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shown before the entries with uncommitted changes
+const DIRTY_MARKER: &str = "\u{25cf}";
+/// Shown before the marked entries
+const MARK_MARKER: &str = "\u{2713}";
+/// Shown while the entries are loaded in the background
+const LOADING_MESSAGE: &str = "Loading\u{2026}";
 
 /// How to show icons next to items
 #[derive(Debug, Default)]
@@ -28,6 +88,54 @@ pub enum IconMode {
     Nerd,
 }
 
+/// A nerd font glyph drawn in the background
+#[derive(Debug)]
+enum Glyph {
+    /// Being drawn
+    Pending,
+    /// Drawn successfully
+    Drawn(GlyphPixels),
+    /// Could not be drawn
+    Failed,
+}
+
+/// The pixels of a drawn glyph, which unlike a Cairo surface can be shared between threads
+#[derive(Debug)]
+struct GlyphPixels {
+    /// ARGB32 pixel data
+    data: Vec<u8>,
+    /// Width and height of the glyph
+    size: i32,
+    /// Length of a row of `data`, in bytes
+    stride: i32,
+}
+
+impl GlyphPixels {
+    /// Draw the `glyph` of the nerd font
+    fn draw(glyph: &str, font: &str, color: RGBAColor, size: u32) -> anyhow::Result<Self> {
+        let mut surface = draw_nerd_icon(glyph, font, color, size)?;
+        surface.flush();
+        let data = surface.data()?.to_vec();
+        Ok(Self {
+            data,
+            size: surface.width(),
+            stride: surface.stride(),
+        })
+    }
+
+    /// A new surface with the pixels of the glyph
+    fn surface(&self) -> anyhow::Result<cairo::Surface> {
+        let surface = cairo::ImageSurface::create_for_data(
+            self.data.clone(),
+            cairo::Format::ARgb32,
+            self.size,
+            self.size,
+            self.stride,
+        )?;
+        Ok((*surface).clone())
+    }
+}
+
 /// Configuration for the icons
 #[derive(Debug)]
 pub struct IconConfig {
@@ -37,20 +145,827 @@ pub struct IconConfig {
     font: String,
     /// Color to render icon font
     color: RGBAColor,
+    /// Glyphs of the nerd font drawn so far, by glyph and height
+    glyphs: Arc<Mutex<HashMap<(String, u32), Glyph>>>,
+    /// Show the thumbnails of images instead of their icons
+    thumbnails: bool,
+}
+
+impl IconConfig {
+    /// Get an icon according to the configured mode, or `None` until it is ready
+    ///
+    /// Depending on the [IconMode], the icon is either looked up from the theme by its `name`
+    /// or drawn from the `glyph` of the nerd font.
+    /// Either way this happens in the background, and Rofi is asked to redraw the entries once it is done.
+    fn icon(
+        &self,
+        api: &mut Api<'_>,
+        name: &str,
+        glyph: &str,
+        height: u32,
+    ) -> Option<cairo::Surface> {
+        match self.mode {
+            IconMode::None => None,
+            IconMode::Theme => theme_icon(api, name, height),
+            IconMode::Nerd => self.nerd_icon(glyph, height),
+        }
+    }
+
+    /// Get the icon of a recent item, or `None` until it is ready
+    ///
+    /// If thumbnails are enabled, local images are shown scaled down, see [thumbnail].
+    /// Rofi loads them in its workers as it does for theme icons.
+    fn recent_icon(
+        &self,
+        api: &mut Api<'_>,
+        recent: &Recent,
+        height: u32,
+    ) -> Option<cairo::Surface> {
+        let image = match (&self.mode, recent) {
+            (IconMode::None, _) => None,
+            (_, Recent::File { .. }) if self.thumbnails => recent
+                .file_path()
+                .ok()
+                .and_then(|path| thumbnail(&path, height)),
+            _ => None,
+        };
+        match image {
+            Some(image) => theme_icon(api, &image.to_string_lossy(), height),
+            None => self.icon(api, recent.icon_name(), recent.nerd_icon(), height),
+        }
+    }
+
+    /// Get the `glyph` of the nerd font, drawing it in a worker thread the first time
+    ///
+    /// There are only a few distinct glyphs, each drawn once rather than for every entry.
+    fn nerd_icon(&self, glyph: &str, height: u32) -> Option<cairo::Surface> {
+        let mut glyphs = self.glyphs.lock().ok()?;
+        match glyphs.get(&(glyph.to_string(), height)) {
+            Some(Glyph::Drawn(pixels)) => {
+                return pixels.surface().map_err(|e| error!("{:?}", e)).ok()
+            }
+            Some(_) => return None,
+            None => {}
+        }
+        let key = (glyph.to_string(), height);
+        glyphs.insert(key.clone(), Glyph::Pending);
+        let (all_glyphs, font, color) = (self.glyphs.clone(), self.font.clone(), self.color);
+        thread::spawn(move || {
+            let drawn = match GlyphPixels::draw(&key.0, &font, color, key.1) {
+                Ok(pixels) => Glyph::Drawn(pixels),
+                Err(e) => {
+                    error!("{:?}", e);
+                    Glyph::Failed
+                }
+            };
+            if let Ok(mut glyphs) = all_glyphs.lock() {
+                glyphs.insert(key, drawn);
+            }
+            // Rofi must be called from its own thread
+            glib::idle_add_once(|| unsafe { rofi::ffi::view::reload() });
+        });
+        None
+    }
+}
+
+/// How the folders of multi-root workspaces are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FoldersDisplay {
+    /// Their number
+    Count,
+    /// Their number and names
+    Names,
+}
+
+/// Details shown next to the label of an entry
+///
+/// They are looked up only when the entry is displayed, since this requires reading files.
+#[derive(Debug, Clone, Default)]
+struct EntryDetails {
+    /// Git status, if the entry is a repository
+    repo: Option<RepoStatus>,
+    /// Names of the folders, if the entry is a multi-root workspace
+    folders: Option<Vec<String>>,
+}
+
+/// An action that awaits confirmation
+#[derive(Debug, PartialEq, Eq)]
+enum PendingAction {
+    /// Delete the entry with the given URL
+    Delete(Url),
+    /// Clear the whole history
+    Clear,
+}
+
+/// Whether `pending` is the same `action`, requested recently enough to be confirmed
+fn confirms(pending: Option<&(PendingAction, Instant)>, action: &PendingAction) -> bool {
+    pending.is_some_and(|(pending, requested)| {
+        pending == action && requested.elapsed() < CONFIRM_TIMEOUT
+    })
+}
+
+/// What the recent mode loads in the background, since it requires reading databases and files
+struct Loaded {
+    /// The selected VSCode flavors
+    flavors: Vec<Box<dyn EditorFlavor>>,
+    /// The sources of entries other than the histories of the flavors
+    sources: Vec<Box<dyn RecentSource>>,
+    /// The entries, with the index of their source
+    entries: Vec<(usize, Recent)>,
+    /// When the entries were last opened, if it is shown
+    last_opened: Option<LastOpened>,
+}
+
+/// The receiving end of [VSCodeRecentMode::load_in_background]
+struct Updates {
+    rx: Receiver<anyhow::Result<Loaded>>,
+    /// Keeps the loading thread running, which only holds a weak reference to it
+    _alive: Arc<()>,
+}
+
+/// An entry deleted during the session, which can be restored
+#[derive(Debug)]
+struct DeletedEntry {
+    /// Index of the entry in the displayed list
+    line: usize,
+    /// Position of the item in its source, if it was found there
+    position: Option<usize>,
+    /// The entry, with the index of its source
+    entry: (usize, Recent),
 }
 
 // Open recent workspaces, files and folders with VSCode
+//
+// The entries come from the history of each selected flavor, followed by the other sources.
+// When multiple flavors are selected, each entry is opened with the flavor it comes from,
+// and the entries of the other sources with the first flavor.
+//
+// Entering the path of a directory, or selecting a folder with kb-accept-alt, browses it instead,
+// until Escape is pressed in the directory browsed first.
+// If `ROFI_VSCODE_ALT_ARGS` is set, kb-accept-alt rather opens the entry with those arguments.
+// If `ROFI_VSCODE_MULTI_SELECT` is set, kb-accept-alt rather marks files, and two marked files are compared.
+//
+// kb-custom-3 moves the selected entry to the top of its history without opening it.
+// kb-custom-4 cycles through the installed flavors, showing the history of one at a time,
+// and back to the selected ones.
+// Starting from kb-custom-5, the custom commands open the selected entry with each installed flavor.
+// Any of these keybindings can run another action instead, see [Keymap].
 pub struct VSCodeRecentMode<'rofi> {
     /// Binding to the Rofi api
     api: Api<'rofi>,
-    /// The entries that will be displayed
-    entries: Vec<Recent>,
-    /// The selected VSCode flavor
-    flavor: Flavor,
+    /// The entries that will be displayed, with the index of their source
+    entries: Vec<(usize, Recent)>,
+    /// The selected VSCode flavors, the first one opens custom input
+    ///
+    /// The index of each flavor is the index of its history among the sources.
+    flavors: Vec<Box<dyn EditorFlavor>>,
+    /// The sources of entries other than the histories of the flavors
+    sources: Vec<Box<dyn RecentSource>>,
+    /// The installed flavors, which the custom commands open the selected entry with
+    installed: Vec<Box<dyn EditorFlavor>>,
+    /// Index of the installed flavor shown instead of the selected ones, if switched to
+    switched: Option<usize>,
+    /// Whether the time the entries were last opened is shown
+    show_time: bool,
+    /// The arguments that the `open-alt` action opens entries with, instead of the default ones
+    alt_args: Option<Vec<String>>,
+    /// The action run by kb-accept-alt and by each custom command
+    keymap: Keymap,
+    /// URLs of the marked files, in the order they were marked
+    marked: Vec<Url>,
+    /// Activates the window already showing an entry instead of opening it again, if enabled
+    window_backend: Option<Box<dyn WindowBackend>>,
+    /// When the entries were last opened, if it is shown
+    last_opened: Option<LastOpened>,
+    /// How the labels are laid out, if not as usual
+    label_template: Option<LabelTemplate>,
+    /// Length beyond which the displayed labels are shortened, if any
+    shorten: Option<usize>,
+    /// Whether the git branch of the entries is shown
+    show_branch: bool,
+    /// Whether the entries with uncommitted changes are marked
+    show_dirty: bool,
+    /// How the folders of multi-root workspaces are shown, if at all
+    show_folders: Option<FoldersDisplay>,
+    /// Details of the entries that have been displayed, cached by URL
+    details: Mutex<HashMap<Url, EntryDetails>>,
+    /// Whether deleting an entry must be confirmed by deleting it again
+    confirm_delete: bool,
+    /// The action that awaits confirmation, and when it was requested
+    pending: Option<(PendingAction, Instant)>,
+    /// The entries deleted during the session, the most recent last
+    deleted: Vec<DeletedEntry>,
+    /// The last error, shown in the message bar until the next action
+    error: Option<anyhow::Error>,
+    /// The last notice, shown in the message bar until the next action
+    notice: Option<String>,
+    /// Receives what is loaded in the background, at startup and whenever the databases change
+    updates: Mutex<Updates>,
+    /// Whether the entries are still being loaded at startup
+    loading: bool,
+    /// The directories being browsed, if any, whose contents replace the entries
+    browser: Option<Browser>,
     /// Configuration to render icons
     icon_config: IconConfig,
 }
 
+impl VSCodeRecentMode<'_> {
+    /// Determine the flavors, the other sources and the recent items to show
+    ///
+    /// If `switched` is given, the installed flavor with this index is the only one, see [Flavor::installed].
+    fn load(show_time: bool, switched: Option<usize>) -> anyhow::Result<Loaded> {
+        let flavors = match switched {
+            None => determine_vscode_flavors()?,
+            Some(i) => {
+                let flavor = Flavor::installed()
+                    .into_iter()
+                    .nth(i)
+                    .ok_or(Error::FlavorNotFound)?;
+                vec![with_extra_args(Box::new(flavor))]
+            }
+        };
+        let sources = determine_sources()?;
+        let options = determine_recent_options()?;
+        let entries = fetch_recents(&all_sources(&flavors, &sources))?;
+        let entries = options.apply(&flavors, entries);
+        let last_opened = show_time.then(|| LastOpened::read(&flavors));
+        Ok(Loaded {
+            flavors,
+            sources,
+            entries,
+            last_opened,
+        })
+    }
+
+    /// Start loading in a worker thread, which makes Rofi reload the entries when it is done
+    ///
+    /// The thread then polls the modification times of the databases of the flavors every [WATCH_INTERVAL],
+    /// and loads again whenever VSCode changes them.
+    /// When the [Updates] are dropped, e.g. after switching the flavor, the thread stops within an interval.
+    fn load_in_background(show_time: bool, switched: Option<usize>) -> Updates {
+        let (tx, rx) = mpsc::channel();
+        let alive = Arc::new(());
+        let watcher = Arc::downgrade(&alive);
+        thread::spawn(move || loop {
+            let loaded = Self::load(show_time, switched);
+            let watched = match &loaded {
+                Ok(loaded) => watched_files(&loaded.flavors),
+                Err(_) => Vec::new(),
+            };
+            if tx.send(loaded).is_err() {
+                return;
+            }
+            // Rofi must be called from its own thread
+            glib::idle_add_once(|| unsafe { rofi::ffi::view::reload() });
+            if watched.is_empty() {
+                return;
+            }
+            let times = modification_times(&watched);
+            while modification_times(&watched) == times {
+                if watcher.strong_count() == 0 {
+                    return;
+                }
+                thread::sleep(WATCH_INTERVAL);
+            }
+            debug!("Reloading after a change of {:?}", watched);
+        });
+        Updates { rx, _alive: alive }
+    }
+
+    /// Take the latest entries loaded in the background, if any
+    ///
+    /// This replaces the displayed list, so it must only happen when Rofi fetches the entries, see [Self::entries],
+    /// or before the initial list is shown.
+    /// When `wait` is set and the entries are still being loaded at startup, block until they are ready.
+    /// On failure, the error is shown and the current entries are kept.
+    fn receive_loaded(&mut self, wait: bool) {
+        let Ok(Updates { rx, .. }) = self.updates.get_mut() else {
+            return;
+        };
+        let mut res = match wait && self.loading {
+            true => rx.recv().map_err(|_| TryRecvError::Disconnected),
+            false => rx.try_recv(),
+        };
+        // Skip to the latest update
+        while let Ok(next) = rx.try_recv() {
+            res = Ok(next);
+        }
+        let loaded = match res {
+            Ok(loaded) => loaded,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) if self.loading => {
+                Err(anyhow!("Could not load the recent items"))
+            }
+            Err(TryRecvError::Disconnected) => return,
+        };
+        self.loading = false;
+        match loaded {
+            Ok(loaded) => {
+                debug!("Loaded {} entries", loaded.entries.len());
+                self.flavors = loaded.flavors;
+                self.sources = loaded.sources;
+                self.entries = loaded.entries;
+                self.last_opened = loaded.last_opened;
+            }
+            Err(e) => {
+                error!("{:?}", e);
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// The source with the given index, see [all_sources]
+    fn source(&self, index: usize) -> Box<dyn RecentSource + '_> {
+        match self.flavors.get(index) {
+            Some(flavor) => Box::new(FlavorHistory(flavor.as_ref())),
+            None => Box::new(self.sources[index - self.flavors.len()].as_ref()),
+        }
+    }
+
+    /// Label of the entry at index `line`, tagged with its source unless it is laid out by the template
+    fn label(&self, line: usize) -> anyhow::Result<String> {
+        let (source, entry) = &self.entries[line];
+        if let Some(template) = &self.label_template {
+            return Ok(template.render(entry, self.source(*source).name())?);
+        }
+        let label = entry.label()?;
+        // The entries of the other sources are always tagged, to tell them from the history
+        let tagged = match self.flavors.get(*source) {
+            Some(_) => self.flavors.len() > 1,
+            None => true,
+        };
+        if tagged {
+            Ok(format!("{} [{}]", label, self.source(*source).name()))
+        } else {
+            Ok(label.to_string())
+        }
+    }
+
+    /// Look up the details of the entry at index `line` that are shown
+    fn details(&self, line: usize) -> EntryDetails {
+        let entry = &self.entries[line].1;
+        let repo = || {
+            let repo = recent_repo(entry)?;
+            Some(RepoStatus {
+                branch: self.show_branch.then(|| current_branch(&repo)).flatten(),
+                dirty: self.show_dirty
+                    && is_dirty(&repo)
+                        .map_err(|e| error!("{:?}", e))
+                        .unwrap_or(false),
+            })
+        };
+        let folders = || match entry {
+            Recent::Workspace { .. } => entry
+                .file_path()
+                .and_then(|p| workspace_folder_names(&p))
+                .ok(),
+            _ => None,
+        };
+        EntryDetails {
+            repo: (self.show_branch || self.show_dirty).then(repo).flatten(),
+            folders: self.show_folders.and_then(|_| folders()),
+        }
+    }
+
+    /// Details of the entry at index `line`, looked up the first time it is displayed
+    fn cached_details(&self, line: usize) -> EntryDetails {
+        let url = self.entries[line].1.url();
+        let Ok(mut cache) = self.details.lock() else {
+            return self.details(line);
+        };
+        cache
+            .entry(url.to_owned())
+            .or_insert_with(|| self.details(line))
+            .to_owned()
+    }
+
+    /// Text displayed for the entry at index `line`
+    ///
+    /// The label is shortened if it is too long, see [shorten_path].
+    /// It is marked if the entry is marked or if there are uncommitted changes, and followed by the git branch,
+    /// the folders of a multi-root workspace and the time it was last opened, when they are shown and known.
+    fn display(&self, line: usize) -> anyhow::Result<String> {
+        let mut label = self.label(line)?;
+        if let Some(max) = self.shorten {
+            label = shorten_path(&label, max);
+        }
+        let (source, entry) = &self.entries[line];
+        let details = self.cached_details(line);
+        let mut notes = Vec::new();
+        if let Some(status) = details.repo {
+            if status.dirty {
+                label = format!("{} {}", DIRTY_MARKER, label);
+            }
+            notes.extend(status.branch);
+        }
+        if self.marked.contains(entry.url()) {
+            label = format!("{} {}", MARK_MARKER, label);
+        }
+        if let Some(folders) = details.folders {
+            let count = match folders.len() {
+                1 => "1 folder".to_string(),
+                n => format!("{} folders", n),
+            };
+            match self.show_folders {
+                Some(FoldersDisplay::Names) if !folders.is_empty() => {
+                    notes.push(format!("{}: {}", count, folders.join(", ")))
+                }
+                _ => notes.push(count),
+            }
+        }
+        if let Some(time) = self
+            .last_opened
+            .as_ref()
+            .and_then(|l| l.get(*source, entry))
+        {
+            notes.push(format_relative_time(time, SystemTime::now()));
+        }
+        if notes.is_empty() {
+            Ok(label)
+        } else {
+            Ok(format!("{} ({})", label, notes.join(", ")))
+        }
+    }
+
+    /// Delete the entry at index `line` from its source, keeping it to undo the deletion
+    fn delete(&mut self, line: usize) -> anyhow::Result<Action> {
+        let (source, entry) = &self.entries[line];
+        let position = self.source(*source).delete(entry.url())?;
+        let entry = self.entries.remove(line);
+        if self.deleted.len() == UNDO_STACK_SIZE {
+            self.deleted.remove(0);
+        }
+        self.deleted.push(DeletedEntry {
+            line,
+            position,
+            entry,
+        });
+        Ok(Action::Reload)
+    }
+
+    /// Restore the most recently deleted entry, if any
+    fn undo_delete(&mut self) -> anyhow::Result<Action> {
+        let Some(deleted) = self.deleted.pop() else {
+            return Ok(Action::Reload);
+        };
+        let (source, recent) = &deleted.entry;
+        if let Some(position) = deleted.position {
+            self.source(*source).restore(recent, position)?;
+        }
+        let line = deleted.line.min(self.entries.len());
+        self.entries.insert(line, deleted.entry);
+        Ok(Action::Reload)
+    }
+
+    /// Clear the recent items of all the flavors, and of the other sources that are a history
+    fn clear(&mut self) -> anyhow::Result<Action> {
+        for source in all_sources(&self.flavors, &self.sources) {
+            source.clear()?;
+        }
+        self.entries.clear();
+        Ok(Action::Reload)
+    }
+
+    /// Move the entry at index `line` to the top of its source, and of the list
+    fn bump(&mut self, line: usize) -> anyhow::Result<Action> {
+        let (source, entry) = &self.entries[line];
+        self.source(*source).bump(entry.url())?;
+        self.entries[..=line].rotate_right(1);
+        Ok(Action::Reload)
+    }
+
+    /// Show the history of the next installed flavor, or of the selected ones after the last
+    ///
+    /// The entries are loaded again in the background, and what refers to the previous ones is dropped.
+    fn switch_flavor(&mut self) -> anyhow::Result<Action> {
+        if self.installed.is_empty() {
+            return Err(Error::FlavorNotFound.into());
+        }
+        self.switched = match self.switched {
+            None => Some(0),
+            Some(i) if i + 1 < self.installed.len() => Some(i + 1),
+            Some(_) => None,
+        };
+        self.updates = Mutex::new(Self::load_in_background(self.show_time, self.switched));
+        self.loading = true;
+        self.entries.clear();
+        self.marked.clear();
+        self.deleted.clear();
+        self.pending = None;
+        Ok(Action::Reload)
+    }
+
+    /// Open the entry at index `line`, or activate the window already showing it
+    ///
+    /// Failing to activate a window is not an error, the entry is opened anyway.
+    fn open(&mut self, line: usize) -> anyhow::Result<Action> {
+        let entry = &self.entries[line].1;
+        if let Some(backend) = &self.window_backend {
+            match focus_existing(backend.as_ref(), entry) {
+                Ok(true) => return Ok(Action::Exit),
+                Ok(false) => {}
+                Err(e) => debug!(
+                    "Could not activate a window with {}: {:?}",
+                    backend.name(),
+                    e
+                ),
+            }
+        }
+        let handoff = open_recent(self.flavor(line), entry)?;
+        self.opened(handoff)
+    }
+
+    /// Exit once an entry is opened, or else copy the URI that a client must open, see [open_recent]
+    ///
+    /// The URI is shown in the message bar, in case it cannot be copied.
+    fn opened(&mut self, handoff: Option<Url>) -> anyhow::Result<Action> {
+        let Some(url) = handoff else {
+            return Ok(Action::Exit);
+        };
+        self.notice = Some(match copy_to_clipboard(url.as_str()) {
+            Ok(()) => format!("Copied {} to the clipboard, open it from a client", url),
+            Err(e) => {
+                debug!("{:?}", e);
+                format!("Open {} from a client", url)
+            }
+        });
+        Ok(Action::Reload)
+    }
+
+    /// The flavor that opens the entry at index `line`
+    ///
+    /// This is the flavor it comes from, or the first one for the entries of the other sources.
+    fn flavor(&self, line: usize) -> &dyn EditorFlavor {
+        let flavor = self.flavors.get(self.entries[line].0);
+        flavor.unwrap_or(&self.flavors[0]).as_ref()
+    }
+
+    /// Mark the file at index `line`, or unmark it if it was marked
+    ///
+    /// Up to two files can be marked, which are then compared.
+    fn toggle_mark(&mut self, line: usize) -> anyhow::Result<Action> {
+        let entry = &self.entries[line].1;
+        let url = entry.url();
+        if let Some(i) = self.marked.iter().position(|u| u == url) {
+            self.marked.remove(i);
+        } else if !matches!(entry, Recent::File { .. }) {
+            return Err(anyhow!("Only files can be marked, to compare them"));
+        } else if self.marked.len() >= 2 {
+            return Err(anyhow!("Two files are already marked"));
+        } else {
+            self.marked.push(url.to_owned());
+        }
+        Ok(Action::Reload)
+    }
+
+    /// Compare the two marked files, with the flavor of the first one
+    fn diff_marked(&self) -> anyhow::Result<Action> {
+        let lines = self
+            .marked
+            .iter()
+            .map(|url| {
+                self.entries
+                    .iter()
+                    .position(|(_, r)| r.url() == url)
+                    .ok_or_else(|| anyhow!("The marked file {} is not listed anymore", url))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let [first, second] = lines[..] else {
+            return Err(anyhow!("Mark two files to compare them"));
+        };
+        let first_path = self.entries[first].1.file_path()?;
+        let second_path = self.entries[second].1.file_path()?;
+        self.flavor(first)
+            .diff(first_path.as_os_str(), second_path.as_os_str())?;
+        Ok(Action::Exit)
+    }
+
+    /// Open the entry at index `line` with the installed flavor with index `flavor`
+    fn open_with(&mut self, flavor: usize, line: usize) -> anyhow::Result<Action> {
+        let flavor = self.installed.get(flavor).ok_or_else(|| {
+            let names: Vec<_> = self.installed.iter().map(|f| f.name()).collect();
+            anyhow!(
+                "No installed flavor number {}, the installed ones are {}",
+                flavor + 1,
+                names.join(", ")
+            )
+        })?;
+        let handoff = open_recent(flavor.as_ref(), &self.entries[line].1)?;
+        self.opened(handoff)
+    }
+
+    /// Open the entry at index `line` with `args` instead of the default arguments of its flavor
+    fn open_with_args(&mut self, line: usize, args: Vec<String>) -> anyhow::Result<Action> {
+        let flavor = WithArgs {
+            flavor: self.flavor(line),
+            args,
+        };
+        let handoff = open_recent(&flavor, &self.entries[line].1)?;
+        self.opened(handoff)
+    }
+
+    /// Copy the path of the entry at index `line` to the clipboard, or its URI if it is not local
+    fn copy_path(&self, line: usize) -> anyhow::Result<Action> {
+        let entry = &self.entries[line].1;
+        let text = match entry.file_path() {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(_) => entry.url().to_string(),
+        };
+        copy_to_clipboard(&text)?;
+        Ok(Action::Exit)
+    }
+
+    /// Open a terminal in the folder at index `line`, or in the directory of the file or workspace
+    fn terminal(&self, line: usize) -> anyhow::Result<Action> {
+        let entry = &self.entries[line].1;
+        let path = entry.file_path()?;
+        let dir = match entry {
+            Recent::Folder { .. } => path.as_path(),
+            _ => path.parent().unwrap_or(&path),
+        };
+        open_terminal(dir)?;
+        Ok(Action::Exit)
+    }
+
+    /// Delete the entry at index `line`, after confirmation if required
+    fn request_delete(
+        &mut self,
+        line: usize,
+        pending: Option<&(PendingAction, Instant)>,
+    ) -> anyhow::Result<Action> {
+        let action = PendingAction::Delete(self.entries[line].1.url().to_owned());
+        if !self.confirm_delete || confirms(pending, &action) {
+            self.delete(line)
+        } else {
+            self.pending = Some((action, Instant::now()));
+            Ok(Action::Reload)
+        }
+    }
+
+    /// Clear the whole history, always after confirmation
+    fn request_clear(
+        &mut self,
+        pending: Option<&(PendingAction, Instant)>,
+    ) -> anyhow::Result<Action> {
+        if confirms(pending, &PendingAction::Clear) {
+            self.clear()
+        } else {
+            self.pending = Some((PendingAction::Clear, Instant::now()));
+            Ok(Action::Reload)
+        }
+    }
+
+    /// Run the action bound to `key` on the entry at index `selected`, if any
+    ///
+    /// `pending` is the action that awaited confirmation before the key was pressed.
+    fn run_key(
+        &mut self,
+        key: Key,
+        selected: Option<usize>,
+        pending: Option<&(PendingAction, Instant)>,
+    ) -> anyhow::Result<Action> {
+        let action = self
+            .keymap
+            .action(key)
+            .ok_or_else(|| anyhow!("No action is bound to {}", key))?;
+        let line = || selected.ok_or_else(|| anyhow!("No entry is selected"));
+        match action {
+            KeyAction::Open => self.open(line()?),
+            // Only folders are browsed, anything else is opened
+            KeyAction::Browse => {
+                let line = line()?;
+                match &self.entries[line].1 {
+                    entry @ Recent::Folder { .. } => {
+                        let dir = entry.file_path()?;
+                        self.browse(dir)
+                    }
+                    _ => self.open(line),
+                }
+            }
+            KeyAction::Mark => self.toggle_mark(line()?),
+            KeyAction::OpenAlt => {
+                let args = self
+                    .alt_args
+                    .clone()
+                    .ok_or_else(|| anyhow!("{} is not set", ENV_ALT_ARGS))?;
+                self.open_with_args(line()?, args)
+            }
+            KeyAction::NewWindow => self.open_with_args(line()?, vec!["--new-window".to_string()]),
+            KeyAction::CopyPath => self.copy_path(line()?),
+            KeyAction::Terminal => self.terminal(line()?),
+            KeyAction::Delete => self.request_delete(line()?, pending),
+            KeyAction::Undo => self.undo_delete(),
+            KeyAction::Clear => self.request_clear(pending),
+            KeyAction::Bump => self.bump(line()?),
+            KeyAction::SwitchFlavor => self.switch_flavor(),
+            KeyAction::OpenWith(flavor) => self.open_with(flavor, line()?),
+        }
+    }
+
+    /// Open the custom input, either a path or a location in a file
+    ///
+    /// Relative paths are taken from the browsed directory, if any, and directories are browsed.
+    fn open_input(&mut self, input: &str) -> anyhow::Result<Action> {
+        let flavor = self.flavors.first().ok_or(Error::FlavorNotFound)?;
+        let base = self
+            .browser
+            .as_ref()
+            .map(|b| b.dir().to_owned())
+            .unwrap_or_default();
+        match split_location(input) {
+            Some((path, line, column)) => {
+                let mut location = base.join(expand_path(path)?).into_os_string();
+                location.push(format!(":{}", line));
+                if let Some(column) = column {
+                    location.push(format!(":{}", column));
+                }
+                flavor.goto(&location)?;
+            }
+            None => {
+                let path = base.join(expand_path(input)?);
+                if path.is_dir() {
+                    return self.browse(path);
+                }
+                flavor.open_local_path(path.as_os_str())?;
+            }
+        }
+        Ok(Action::Exit)
+    }
+
+    /// Browse the directory `dir`, entering it if another one is being browsed
+    fn browse(&mut self, dir: PathBuf) -> anyhow::Result<Action> {
+        match &mut self.browser {
+            Some(browser) => browser.enter(dir)?,
+            None => self.browser = Some(Browser::new(dir)?),
+        }
+        self.api.set_display_name("Browse");
+        Ok(Action::Reset)
+    }
+
+    /// Go back to the previously browsed directory, or to the recent items
+    fn browse_back(&mut self) -> anyhow::Result<Action> {
+        if let Some(browser) = &mut self.browser {
+            if browser.back()? {
+                return Ok(Action::Reset);
+            }
+        }
+        self.browser = None;
+        self.api.set_display_name("Open Recent");
+        Ok(Action::Reset)
+    }
+
+    /// Name of the first key bound to `action`, to tell it in messages
+    fn key_name(&self, action: KeyAction) -> String {
+        match self.keymap.key(action) {
+            Some(key) => key.to_string(),
+            None => format!("a key bound to {}", action),
+        }
+    }
+
+    /// React to an event while browsing a directory
+    fn react_browsing(&mut self, event: Event, input: &mut rofi::String) -> anyhow::Result<Action> {
+        let Some(browser) = &self.browser else {
+            return Err(anyhow!("No directory is being browsed"));
+        };
+        match event {
+            // Pressed Escape key
+            Event::Cancel { selected: _ } => self.browse_back(),
+
+            // Selected a subdirectory, enter it
+            Event::Ok { alt: _, selected } if browser.is_subdir(selected) => {
+                let dir = browser.entries()[selected].file_path()?;
+                self.browse(dir)
+            }
+            // Selected a file or the browsed directory itself, open it
+            Event::Ok { alt: _, selected } => {
+                let path = browser.entries()[selected].file_path()?;
+                self.flavors
+                    .first()
+                    .ok_or(Error::FlavorNotFound)?
+                    .open_local_path(path.as_os_str())?;
+                Ok(Action::Exit)
+            }
+            // Entered a path, relative to the browsed directory
+            Event::CustomInput {
+                alt: _,
+                selected: _,
+            } => self.open_input(input),
+
+            // Autocomplete input from the name of the selected entry
+            Event::Complete { selected } => {
+                if let Some(line) = selected {
+                    *input = rofi::String::from(browser.name(line).as_str());
+                }
+                Ok(Action::Reset)
+            }
+
+            // Anything else is not supported
+            Event::DeleteEntry { .. } => Err(anyhow!("Deletion not supported while browsing")),
+            Event::CustomCommand { .. } => Err(anyhow!("Command not supported while browsing")),
+        }
+    }
+}
+
 impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeRecentMode<'rofi> {
     const NAME: &'static str = "vscode-recent\0";
 
@@ -58,120 +973,233 @@ impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeRecentMode<'rofi> {
     fn init(mut api: Api<'rofi>) -> Result<Self, ()> {
         // Set name
         api.set_display_name("Open Recent");
-        // Initialize vscode flavor
-        let flavor = determine_vscode_flavor().map_err(|e| eprint!("{:?}", e))?;
-        // Initialize the entries
-        let entries =
-            recently_opened_from_storage(&flavor, false).map_err(|e| eprint!("{:?}", e))?;
+        // Load vscode flavors and the entries without blocking the menu
+        // On failure, show the error instead of exiting silently
+        let show_time = config::flag(ENV_SHOW_TIME);
+        let updates = Self::load_in_background(show_time, None);
+
+        let show_branch = config::flag(ENV_SHOW_BRANCH);
+        let show_dirty = config::flag(ENV_SHOW_DIRTY);
+        let show_folders =
+            config::var(ENV_SHOW_FOLDERS).and_then(|v| match v.to_lowercase().as_str() {
+                "count" | "true" | "1" => Some(FoldersDisplay::Count),
+                "names" => Some(FoldersDisplay::Names),
+                _ => None,
+            });
 
-        let icon_config = determine_icon_config().map_err(|e| eprint!("{:?}", e))?;
+        let label_template = determine_label_template().map_err(|e| error!("{:?}", e))?;
+        let shorten = determine_shorten("recent").map_err(|e| error!("{:?}", e))?;
+        let icon_config = determine_icon_config().map_err(|e| error!("{:?}", e))?;
+
+        let installed: Vec<_> = Flavor::installed()
+            .into_iter()
+            .map(|f| with_extra_args(Box::new(f)))
+            .collect();
+        // kb-accept-alt marks files, opens with the alternate arguments or browses folders, unless bound otherwise
+        let alt_args = config::args(ENV_ALT_ARGS);
+        let accept_alt = match (config::flag(ENV_MULTI_SELECT), &alt_args) {
+            (true, _) => KeyAction::Mark,
+            (false, Some(_)) => KeyAction::OpenAlt,
+            (false, None) => KeyAction::Browse,
+        };
+        let keymap =
+            determine_keymap(accept_alt, installed.len()).map_err(|e| error!("{:?}", e))?;
 
         Ok(VSCodeRecentMode {
             api,
-            entries,
-            flavor,
+            entries: Vec::new(),
+            flavors: Vec::new(),
+            sources: Vec::new(),
+            installed,
+            switched: None,
+            show_time,
+            alt_args,
+            keymap,
+            marked: Vec::new(),
+            window_backend: config::flag(ENV_FOCUS_EXISTING)
+                .then(detect_backend)
+                .flatten(),
+            last_opened: None,
+            label_template,
+            shorten,
+            show_branch,
+            show_dirty,
+            show_folders,
+            details: Mutex::default(),
+            confirm_delete: config::flag(ENV_CONFIRM_DELETE),
+            pending: None,
+            deleted: Vec::new(),
+            error: None,
+            notice: None,
+            updates: Mutex::new(updates),
+            loading: true,
+            browser: None,
             icon_config,
         })
     }
 
-    /// Get the number of entries offered by the mode
+    /// Get the number of entries offered by the mode, as last loaded
     fn entries(&mut self) -> usize {
-        self.entries.len()
+        self.receive_loaded(false);
+        match &self.browser {
+            Some(browser) => browser.entries().len(),
+            None => self.entries.len(),
+        }
     }
 
     fn entry_content(&self, line: usize) -> rofi::String {
-        match self.entries[line].label() {
-            Ok(label) => rofi::String::from(label.as_ref()),
+        if let Some(browser) = &self.browser {
+            return rofi::String::from(browser.name(line).as_str());
+        }
+        match self.display(line) {
+            Ok(label) => rofi::String::from(label.as_str()),
             Err(e) => {
-                eprint!("{}", e);
+                error!("{}", e);
                 rofi::String::new()
             }
         }
     }
 
     fn entry_icon(&mut self, line: usize, height: u32) -> Option<cairo::Surface> {
-        let entry = &self.entries[line];
-        match self.icon_config.mode {
-            IconMode::None => None,
-            IconMode::Theme => self
-                .api
-                .query_icon(entry.icon_name(), height)
-                .wait(&mut self.api)
-                .map_err(|e| eprintln!("{}", e))
-                .ok(),
-            IconMode::Nerd => draw_nerd_icon(
-                entry.nerd_icon(),
-                &self.icon_config.font,
-                self.icon_config.color,
-                height,
-            )
-            .map_err(|e| eprintln!("{}", e))
-            .ok(),
-        }
+        let entry = match &self.browser {
+            Some(browser) => &browser.entries()[line],
+            None => &self.entries[line].1,
+        };
+        self.icon_config.recent_icon(&mut self.api, entry, height)
     }
 
     fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        // Actions apply to the loaded entries, e.g. custom input requires the flavors.
+        // Only the initial load is awaited: a reload must not replace the list that the selection indexes,
+        // it is applied when Rofi asks for the entries again.
+        if self.loading && !matches!(event, Event::Cancel { .. }) {
+            self.receive_loaded(true);
+        }
+        // Any other action cancels a pending one and dismisses the last error or notice
+        let pending = self.pending.take();
+        self.error = None;
+        self.notice = None;
         let res: anyhow::Result<Action> = match event {
+            // The entries are the contents of the browsed directory
+            event if self.browser.is_some() => self.react_browsing(event, input),
+
             // Pressed Escape key
             Event::Cancel { selected: _ } => Ok(Action::Exit),
 
+            // Selected an item when two files are marked, compare them
+            Event::Ok {
+                alt: false,
+                selected: _,
+            } if self.marked.len() == 2 => self.diff_marked(),
+            // Selected an item with kb-accept-alt, run its action
+            Event::Ok {
+                alt: true,
+                selected,
+            } => self.run_key(Key::AcceptAlt, Some(selected), pending.as_ref()),
             // Selected an item
-            Event::Ok { alt: _, selected } => self
-                .flavor
-                .open_recent(&self.entries[selected])
-                .map(|_| Action::Exit),
-            // Selected a custom input (not in list)
+            Event::Ok { alt: _, selected } => self.open(selected),
+            // Selected a custom input (not in list), a path or a location in a file
             Event::CustomInput {
                 alt: _,
                 selected: _,
-            } => {
-                let path = untildify(input);
-                self.flavor.open_local_path(path).map(|_| Action::Exit)
-            }
+            } => self.open_input(input),
 
             // Autocomplete input from selected entry
             Event::Complete { selected } => {
                 if let Some(line) = selected {
-                    if let Ok(label) = self.entries[line].label() {
+                    if let Ok(label) = self.entries[line].1.label() {
                         *input = rofi::String::from(label.as_ref());
                     }
                 }
                 Ok(Action::Reset)
             }
 
-            // Delete selected entry
-            Event::DeleteEntry { selected } => {
-                self.entries.remove(selected);
-                store_recently_opened(&self.flavor, &self.entries).map(|_| Action::Reload)
-            }
+            // Delete selected entry, after confirmation if required
+            Event::DeleteEntry { selected } => self.request_delete(selected, pending.as_ref()),
 
-            // User ran a custom command
-            Event::CustomCommand {
-                number: _,
-                selected: _,
-            } => Err(anyhow!("Command not supported")),
+            // Run the action bound to the custom command
+            Event::CustomCommand { number, selected } => {
+                self.run_key(Key::Custom(number), selected, pending.as_ref())
+            }
         };
-        // Handle errors
+        // Handle errors, showing them until the next action
         match res {
             Ok(a) => a,
             Err(e) => {
-                eprint!("{:?}", e);
-                Action::Exit
+                error!("{:?}", e);
+                self.error = Some(e);
+                Action::Reload
             }
         }
     }
 
     /// Check if the given matcher matches an entry
     fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
-        match self.entries[line].label() {
+        if let Some(browser) = &self.browser {
+            return matcher.matches(&browser.name(line));
+        }
+        match self.label(line) {
             Ok(label) => matcher.matches(&label),
             Err(_) => false,
         }
     }
+
+    /// Show the last error, the browsed directory, that the entries are loading,
+    /// ask to confirm a pending action, tell what to do with the marked files
+    /// or which flavor was switched to
+    fn message(&mut self) -> rofi::String {
+        if let Some(e) = &self.error {
+            return error_message(e);
+        }
+        if let Some(notice) = &self.notice {
+            return rofi::String::from(pango_escape(notice).as_str());
+        }
+        if let Some(browser) = &self.browser {
+            return rofi::String::from(pango_escape(&browser.title()).as_str());
+        }
+        if self.loading {
+            return rofi::String::from(LOADING_MESSAGE);
+        }
+        match self.pending.as_ref().map(|(action, _)| action) {
+            Some(PendingAction::Delete(url)) => {
+                let label = self
+                    .entries
+                    .iter()
+                    .find(|(_, r)| r.url() == url)
+                    .and_then(|(_, r)| r.label().ok())
+                    .unwrap_or_default();
+                rofi::format!(
+                    "Delete again within {} seconds to remove {} from the history",
+                    CONFIRM_TIMEOUT.as_secs(),
+                    pango_escape(&label)
+                )
+            }
+            Some(PendingAction::Clear) => rofi::format!(
+                "Press {} again within {} seconds to clear the whole history",
+                self.key_name(KeyAction::Clear),
+                CONFIRM_TIMEOUT.as_secs()
+            ),
+            None => match self.marked.len() {
+                0 => match self.switched.and_then(|i| self.installed.get(i)) {
+                    Some(flavor) => rofi::format!(
+                        "History of {}, press {} to switch flavor",
+                        pango_escape(flavor.name()),
+                        self.key_name(KeyAction::SwitchFlavor)
+                    ),
+                    None => rofi::String::new(),
+                },
+                1 => rofi::format!(
+                    "Mark another file with {} to compare them",
+                    self.key_name(KeyAction::Mark)
+                ),
+                _ => rofi::String::from("Press Enter to compare the marked files"),
+            },
+        }
+    }
 }
 
 fn determine_icon_config() -> anyhow::Result<IconConfig> {
-    let _mode = env::var(ENV_ICON_MODE)
+    let _mode = config::var(ENV_ICON_MODE)
         .map(|v| v.to_lowercase())
         .map(|icon_mode| match icon_mode.as_str() {
             "none" => IconMode::None,
@@ -181,10 +1209,10 @@ fn determine_icon_config() -> anyhow::Result<IconConfig> {
         })
         .unwrap_or_default();
 
-    let font = env::var(ENV_ICON_FONT).unwrap_or_else(|_| "monospace".to_string());
+    let font = config::var(ENV_ICON_FONT).unwrap_or_else(|| "monospace".to_string());
 
-    let color = env::var(ENV_ICON_COLOR)
-        .map_err(|_| ())
+    let color = config::var(ENV_ICON_COLOR)
+        .ok_or(())
         .and_then(|s| RGBAColor::parse(&s))
         .unwrap_or_default();
 
@@ -192,20 +1220,132 @@ fn determine_icon_config() -> anyhow::Result<IconConfig> {
         mode: _mode,
         font,
         color,
+        glyphs: Arc::default(),
+        thumbnails: config::flag(ENV_THUMBNAILS),
     })
 }
 
+/// Get the icon `name` from the theme, or the image at that path if it is absolute
+///
+/// Rofi looks up the icon in its own workers, and answers the same request for the same name.
+fn theme_icon(api: &mut Api<'_>, name: &str, height: u32) -> Option<cairo::Surface> {
+    let request = api.query_icon(name, height);
+    api.retrieve_icon(request)
+        .map_err(|e| trace!("Icon {} not ready: {}", name, e))
+        .ok()
+}
+
+/// A mode registered under the name chosen at build time
+///
+/// The name defaults to the one of the mode, and can be overridden with `ROFI_VSCODE_MODE_NAME`,
+/// so that the same mode can be loaded from several plugins, see `build.rs`.
+/// Everything else is left to the wrapped mode.
+pub struct Exported<M>(M);
+
+impl<'rofi, M: rofi_mode::Mode<'rofi>> rofi_mode::Mode<'rofi> for Exported<M> {
+    const NAME: &'static str = concat!(env!("ROFI_VSCODE_MODE_NAME"), "\0");
+
+    fn init(api: Api<'rofi>) -> Result<Self, ()> {
+        M::init(api).map(Exported)
+    }
+
+    fn entries(&mut self) -> usize {
+        self.0.entries()
+    }
+
+    fn entry_content(&self, line: usize) -> rofi::String {
+        self.0.entry_content(line)
+    }
+
+    fn entry_style(&self, line: usize) -> rofi::Style {
+        self.0.entry_style(line)
+    }
+
+    fn entry_attributes(&self, line: usize) -> rofi::Attributes {
+        self.0.entry_attributes(line)
+    }
+
+    fn entry_icon(&mut self, line: usize, height: u32) -> Option<cairo::Surface> {
+        self.0.entry_icon(line, height)
+    }
+
+    fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        self.0.react(event, input)
+    }
+
+    fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
+        self.0.matches(line, matcher)
+    }
+
+    fn completed(&self, line: usize) -> rofi::String {
+        self.0.completed(line)
+    }
+
+    fn preprocess_input(&mut self, input: &str) -> rofi::String {
+        self.0.preprocess_input(input)
+    }
+
+    fn message(&mut self) -> rofi::String {
+        self.0.message()
+    }
+}
+
+/// Exit once an entry is opened, or else copy the URI that a client must open, see [open_or_hand_off](crate::utils::open_or_hand_off)
+///
+/// Unlike the recent mode, the other modes cannot tell about it before closing.
+fn exit_or_hand_off(handoff: Option<Url>) -> anyhow::Result<Action> {
+    if let Some(url) = handoff {
+        copy_to_clipboard(url.as_str())
+            .with_context(|| format!("Could not copy {} to the clipboard", url))?;
+    }
+    Ok(Action::Exit)
+}
+
+/// Format an error and its causes for the message bar
+fn error_message(e: &anyhow::Error) -> rofi::String {
+    rofi::format!("<b>Error:</b> {}", pango_escape(&format!("{:#}", e)))
+}
+
+/// The files that change when VSCode updates the recent items of the `flavors`
+///
+/// This is the state database of each flavor, and its write-ahead log.
+fn watched_files(flavors: &[Box<dyn EditorFlavor>]) -> Vec<PathBuf> {
+    flavors
+        .iter()
+        .filter_map(|f| f.state_db())
+        .flat_map(|db| {
+            let mut wal = OsString::from(db.as_os_str());
+            wal.push("-wal");
+            [db, PathBuf::from(wal)]
+        })
+        .collect()
+}
+
+/// When each of the `paths` was last modified, if it exists
+fn modification_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Escape the characters that have a special meaning in Pango markup
+fn pango_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 fn draw_nerd_icon(
     text: &str,
     font: &str,
     color: RGBAColor,
     size: u32,
-) -> anyhow::Result<cairo::Surface> {
+) -> anyhow::Result<cairo::ImageSurface> {
     let size = i32::try_from(size)?;
 
     // Create drawing surface
     let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, size, size)?;
-    let surface = unsafe { cairo::Surface::from_raw_none(surface.to_raw_none()) };
     let cr = cairo::Context::new(&surface)?;
 
     // Set text layout
@@ -227,6 +1367,7 @@ fn draw_nerd_icon(
     cr.set_source_rgba(red, green, blue, alpha);
     pangocairo::functions::update_layout(&cr, &layout);
     pangocairo::functions::show_layout(&cr, &layout);
+    drop(cr);
 
     Ok(surface)
 }
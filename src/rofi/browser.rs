@@ -0,0 +1,135 @@
+//! Browsing the local directories from the recent mode
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context};
+
+use crate::vscode::{file_path_to_url, tildify, workspaces::Recent};
+
+/// The directories entered from the recent mode, to pick one of their files
+///
+/// The first entry is always the directory itself, so that it can be opened,
+/// followed by its subdirectories and then its files, hidden ones excluded.
+#[derive(Debug)]
+pub(super) struct Browser {
+    /// The entered directories, the current one last
+    dirs: Vec<PathBuf>,
+    /// The current directory and its contents
+    entries: Vec<Recent>,
+}
+
+impl Browser {
+    /// Start browsing from `dir`
+    pub(super) fn new(dir: PathBuf) -> anyhow::Result<Self> {
+        let mut browser = Browser {
+            dirs: Vec::new(),
+            entries: Vec::new(),
+        };
+        browser.enter(dir)?;
+        Ok(browser)
+    }
+
+    /// Enter the subdirectory `dir`
+    pub(super) fn enter(&mut self, dir: PathBuf) -> anyhow::Result<()> {
+        self.entries = list_dir(&dir)?;
+        self.dirs.push(dir);
+        Ok(())
+    }
+
+    /// Go back to the directory entered before the current one
+    ///
+    /// Returns `false` if the current directory was the first one.
+    pub(super) fn back(&mut self) -> anyhow::Result<bool> {
+        self.dirs.pop();
+        match self.dirs.last() {
+            Some(dir) => {
+                self.entries = list_dir(dir)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// The current directory
+    pub(super) fn dir(&self) -> &Path {
+        self.dirs.last().expect("the browser has no directory")
+    }
+
+    /// The current directory and its contents
+    pub(super) fn entries(&self) -> &[Recent] {
+        &self.entries
+    }
+
+    /// Whether the entry at index `line` is a subdirectory that can be entered
+    pub(super) fn is_subdir(&self, line: usize) -> bool {
+        line > 0 && matches!(self.entries[line], Recent::Folder { .. })
+    }
+
+    /// Name of the entry at index `line`, with a trailing `/` for subdirectories
+    pub(super) fn name(&self, line: usize) -> String {
+        if line == 0 {
+            return ".".to_string();
+        }
+        let name = self.entries[line]
+            .file_path()
+            .ok()
+            .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_default();
+        if self.is_subdir(line) {
+            format!("{}/", name)
+        } else {
+            name
+        }
+    }
+
+    /// The current directory, shortened for display
+    pub(super) fn title(&self) -> String {
+        tildify(self.dir())
+    }
+}
+
+/// The entry for a local file or directory
+fn local_entry(path: &Path, is_dir: bool) -> anyhow::Result<Recent> {
+    let url = file_path_to_url(path)
+        .ok_or_else(|| anyhow!("Could not convert path {:?} to URI", path))?;
+    Ok(if is_dir {
+        Recent::Folder {
+            folder_uri: url,
+            label: None,
+            remote_authority: None,
+        }
+    } else {
+        Recent::File {
+            file_uri: url,
+            label: None,
+            remote_authority: None,
+        }
+    })
+}
+
+/// The directory `dir` followed by its visible subdirectories and files, each sorted by name
+fn list_dir(dir: &Path) -> anyhow::Result<Vec<Recent>> {
+    let mut children: Vec<(bool, String, PathBuf)> = fs::read_dir(dir)
+        .with_context(|| format!("Could not read directory {:?}", dir))?
+        .filter_map(Result::ok)
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| {
+            let path = e.path();
+            // Follow symlinks, a link to a directory is browsed as well
+            (
+                !path.is_dir(),
+                e.file_name().to_string_lossy().to_lowercase(),
+                path,
+            )
+        })
+        .collect();
+    children.sort();
+    let mut entries = vec![local_entry(dir, true)?];
+    for (is_file, _, path) in children {
+        entries.push(local_entry(&path, !is_file)?);
+    }
+    Ok(entries)
+}
@@ -0,0 +1,125 @@
+//! Rofi mode to open GitHub Codespaces
+
+use super::{determine_icon_config, IconConfig};
+use crate::codespaces::{list_codespaces, Codespace, CodespaceState};
+use crate::logging::error;
+use crate::utils::determine_vscode_flavor;
+use crate::vscode::EditorFlavor;
+use anyhow::anyhow;
+use pangocairo::cairo;
+use rofi_mode::{self as rofi, Action, Api, Event, Matcher};
+
+/// Theme icon shown next to running codespaces
+const RUNNING_ICON_NAME: &str = "media-playback-start";
+/// Nerd font glyph shown next to running codespaces
+const RUNNING_NERD_ICON: &str = "\u{f04b}";
+/// Theme icon shown next to stopped codespaces
+const STOPPED_ICON_NAME: &str = "media-playback-stop";
+/// Nerd font glyph shown next to stopped codespaces
+const STOPPED_NERD_ICON: &str = "\u{f04d}";
+/// Theme icon shown next to codespaces in a transitional state
+const PENDING_ICON_NAME: &str = "view-refresh";
+/// Nerd font glyph shown next to codespaces in a transitional state
+const PENDING_NERD_ICON: &str = "\u{f021}";
+
+/// Open a GitHub Codespace in VSCode
+///
+/// Stopped codespaces are started when opening them.
+pub struct VSCodeCodespacesMode<'rofi> {
+    /// Binding to the Rofi api
+    api: Api<'rofi>,
+    /// The codespaces of the user
+    codespaces: Vec<Codespace>,
+    /// The selected VSCode flavor
+    flavor: Box<dyn EditorFlavor>,
+    /// Configuration to render icons
+    icon_config: IconConfig,
+}
+
+impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeCodespacesMode<'rofi> {
+    const NAME: &'static str = "vscode-codespaces\0";
+
+    /// Initialization
+    fn init(mut api: Api<'rofi>) -> Result<Self, ()> {
+        // Set name
+        api.set_display_name("Open Codespace");
+        // Initialize vscode flavor
+        let flavor = determine_vscode_flavor().map_err(|e| error!("{:?}", e))?;
+        // Initialize the codespaces
+        let codespaces = list_codespaces().map_err(|e| error!("{:?}", e))?;
+
+        let icon_config = determine_icon_config().map_err(|e| error!("{:?}", e))?;
+
+        Ok(VSCodeCodespacesMode {
+            api,
+            codespaces,
+            flavor,
+            icon_config,
+        })
+    }
+
+    /// Get the number of entries offered by the mode
+    fn entries(&mut self) -> usize {
+        self.codespaces.len()
+    }
+
+    fn entry_content(&self, line: usize) -> rofi::String {
+        let codespace = &self.codespaces[line];
+        if codespace.display_name.is_empty() {
+            rofi::format!("{} ({})", codespace.repository, codespace.name)
+        } else {
+            rofi::format!("{} ({})", codespace.repository, codespace.display_name)
+        }
+    }
+
+    fn entry_icon(&mut self, line: usize, height: u32) -> Option<cairo::Surface> {
+        let (name, glyph) = match self.codespaces[line].state {
+            CodespaceState::Available => (RUNNING_ICON_NAME, RUNNING_NERD_ICON),
+            CodespaceState::Shutdown => (STOPPED_ICON_NAME, STOPPED_NERD_ICON),
+            CodespaceState::Other => (PENDING_ICON_NAME, PENDING_NERD_ICON),
+        };
+        self.icon_config.icon(&mut self.api, name, glyph, height)
+    }
+
+    fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        let res: anyhow::Result<Action> = match event {
+            // Pressed Escape key
+            Event::Cancel { selected: _ } => Ok(Action::Exit),
+
+            // Selected a codespace
+            Event::Ok { alt: _, selected } => self.codespaces[selected]
+                .open(self.flavor.as_ref())
+                .map(|_| Action::Exit)
+                .map_err(Into::into),
+
+            // Autocomplete input from selected entry
+            Event::Complete { selected } => {
+                if let Some(line) = selected {
+                    *input = rofi::String::from(self.codespaces[line].repository.as_str());
+                }
+                Ok(Action::Reset)
+            }
+
+            // Anything else is not supported
+            Event::CustomInput { .. } => Err(anyhow!("Custom input not supported")),
+            Event::DeleteEntry { .. } => Err(anyhow!("Deletion not supported")),
+            Event::CustomCommand { .. } => Err(anyhow!("Command not supported")),
+        };
+        // Handle errors
+        match res {
+            Ok(a) => a,
+            Err(e) => {
+                error!("{:?}", e);
+                Action::Exit
+            }
+        }
+    }
+
+    /// Check if the given matcher matches an entry
+    fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
+        let codespace = &self.codespaces[line];
+        matcher.matches(&codespace.repository)
+            || matcher.matches(&codespace.display_name)
+            || matcher.matches(&codespace.name)
+    }
+}
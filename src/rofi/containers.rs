@@ -0,0 +1,131 @@
+//! Rofi mode to attach VSCode to running containers
+
+use super::{determine_icon_config, IconConfig};
+use crate::config;
+use crate::containers::{running_containers, working_dir, Container};
+use crate::logging::error;
+use crate::utils::determine_vscode_flavor;
+use crate::vscode::EditorFlavor;
+use anyhow::anyhow;
+use pangocairo::cairo;
+use rofi_mode::{self as rofi, Action, Api, Event, Matcher};
+
+const ENV_CONTAINER_CLI: &str = "ROFI_VSCODE_CONTAINER_CLI";
+
+/// Theme icon shown next to containers
+const CONTAINER_ICON_NAME: &str = "docker";
+/// Nerd font glyph shown next to containers
+const CONTAINER_NERD_ICON: &str = "\u{f308}";
+
+/// Open a new VSCode window attached to a running container
+///
+/// The container's working directory is opened, if it is set.
+pub struct VSCodeContainersMode<'rofi> {
+    /// Binding to the Rofi api
+    api: Api<'rofi>,
+    /// The containers that will be displayed
+    containers: Vec<Container>,
+    /// Command used to query the containers
+    cli: String,
+    /// The selected VSCode flavor
+    flavor: Box<dyn EditorFlavor>,
+    /// Configuration to render icons
+    icon_config: IconConfig,
+}
+
+impl VSCodeContainersMode<'_> {
+    /// Attach to the given container
+    fn attach(&self, container: &Container) -> anyhow::Result<Action> {
+        let path = working_dir(&self.cli, container)?;
+        self.flavor
+            .open_remote(&container.remote_authority(), path.as_deref())?;
+        Ok(Action::Exit)
+    }
+}
+
+impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeContainersMode<'rofi> {
+    const NAME: &'static str = "vscode-containers\0";
+
+    /// Initialization
+    fn init(mut api: Api<'rofi>) -> Result<Self, ()> {
+        // Set name
+        api.set_display_name("Attach to Container");
+        // Initialize vscode flavor
+        let flavor = determine_vscode_flavor().map_err(|e| error!("{:?}", e))?;
+        // Initialize the containers
+        let cli = config::var(ENV_CONTAINER_CLI).unwrap_or_else(|| "docker".to_string());
+        let containers = running_containers(&cli).map_err(|e| error!("{:?}", e))?;
+
+        let icon_config = determine_icon_config().map_err(|e| error!("{:?}", e))?;
+
+        Ok(VSCodeContainersMode {
+            api,
+            containers,
+            cli,
+            flavor,
+            icon_config,
+        })
+    }
+
+    /// Get the number of entries offered by the mode
+    fn entries(&mut self) -> usize {
+        self.containers.len()
+    }
+
+    fn entry_content(&self, line: usize) -> rofi::String {
+        let container = &self.containers[line];
+        rofi::format!("{} ({})", container.name, container.image)
+    }
+
+    fn entry_icon(&mut self, _line: usize, height: u32) -> Option<cairo::Surface> {
+        self.icon_config.icon(
+            &mut self.api,
+            CONTAINER_ICON_NAME,
+            CONTAINER_NERD_ICON,
+            height,
+        )
+    }
+
+    fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        let res: anyhow::Result<Action> = match event {
+            // Pressed Escape key
+            Event::Cancel { selected: _ } => Ok(Action::Exit),
+
+            // Selected a container
+            Event::Ok { alt: _, selected } => self.attach(&self.containers[selected]),
+
+            // Autocomplete input from selected entry
+            Event::Complete { selected } => {
+                if let Some(line) = selected {
+                    *input = rofi::String::from(self.containers[line].name.as_str());
+                }
+                Ok(Action::Reset)
+            }
+
+            // Anything else is not supported
+            Event::CustomInput {
+                alt: _,
+                selected: _,
+            } => Err(anyhow!("Custom input not supported")),
+            Event::DeleteEntry { selected: _ } => Err(anyhow!("Deletion not supported")),
+            Event::CustomCommand {
+                number: _,
+                selected: _,
+            } => Err(anyhow!("Command not supported")),
+        };
+        // Handle errors
+        match res {
+            Ok(a) => a,
+            Err(e) => {
+                error!("{:?}", e);
+                Action::Exit
+            }
+        }
+    }
+
+    /// Check if the given matcher matches an entry
+    fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
+        let container = &self.containers[line];
+        matcher.matches(&container.name) || matcher.matches(&container.image)
+    }
+}
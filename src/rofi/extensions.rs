@@ -0,0 +1,143 @@
+//! Rofi mode to manage the installed VSCode extensions
+
+use super::{determine_icon_config, IconConfig};
+use crate::logging::error;
+use crate::utils::{determine_vscode_flavor, open_url};
+use crate::vscode::{
+    extensions::{
+        installed_extensions, open_with_extension_disabled, uninstall_extension, Extension,
+    },
+    EditorFlavor,
+};
+use anyhow::anyhow;
+use pangocairo::cairo;
+use rofi_mode::{self as rofi, Action, Api, Event, Matcher};
+
+/// Theme icon shown next to extensions
+const EXTENSION_ICON_NAME: &str = "application-x-addon";
+/// Nerd font glyph shown next to extensions
+const EXTENSION_NERD_ICON: &str = "\u{f12e}";
+
+/// Manage the installed extensions
+///
+/// When an extension is selected:
+/// - <kbd>Enter</kbd> opens its page on the marketplace
+/// - <kbd>Shift</kbd>+<kbd>Del</kbd> uninstalls it
+/// - `kb-custom-1` opens a new window with the extension disabled
+pub struct VSCodeExtensionsMode<'rofi> {
+    /// Binding to the Rofi api
+    api: Api<'rofi>,
+    /// The extensions that will be displayed
+    extensions: Vec<Extension>,
+    /// The selected VSCode flavor
+    flavor: Box<dyn EditorFlavor>,
+    /// Configuration to render icons
+    icon_config: IconConfig,
+}
+
+impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeExtensionsMode<'rofi> {
+    const NAME: &'static str = "vscode-extensions\0";
+
+    /// Initialization
+    fn init(mut api: Api<'rofi>) -> Result<Self, ()> {
+        // Set name
+        api.set_display_name("Extensions");
+        // Initialize vscode flavor
+        let flavor = determine_vscode_flavor().map_err(|e| error!("{:?}", e))?;
+        // Initialize the extensions
+        let extensions = installed_extensions(flavor.as_ref()).map_err(|e| error!("{:?}", e))?;
+
+        let icon_config = determine_icon_config().map_err(|e| error!("{:?}", e))?;
+
+        Ok(VSCodeExtensionsMode {
+            api,
+            extensions,
+            flavor,
+            icon_config,
+        })
+    }
+
+    /// Get the number of entries offered by the mode
+    fn entries(&mut self) -> usize {
+        self.extensions.len()
+    }
+
+    fn entry_content(&self, line: usize) -> rofi::String {
+        let extension = &self.extensions[line];
+        match &extension.version {
+            Some(version) => rofi::format!("{} ({})", extension.id, version),
+            None => rofi::String::from(extension.id.as_str()),
+        }
+    }
+
+    fn entry_icon(&mut self, _line: usize, height: u32) -> Option<cairo::Surface> {
+        self.icon_config.icon(
+            &mut self.api,
+            EXTENSION_ICON_NAME,
+            EXTENSION_NERD_ICON,
+            height,
+        )
+    }
+
+    fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        let res: anyhow::Result<Action> = match event {
+            // Pressed Escape key
+            Event::Cancel { selected: _ } => Ok(Action::Exit),
+
+            // Selected an extension, open its page
+            Event::Ok { alt: _, selected } => {
+                let url = self.extensions[selected].page_url(self.flavor.as_ref());
+                open_url(&url).map(|_| Action::Exit).map_err(Into::into)
+            }
+
+            // Autocomplete input from selected entry
+            Event::Complete { selected } => {
+                if let Some(line) = selected {
+                    *input = rofi::String::from(self.extensions[line].id.as_str());
+                }
+                Ok(Action::Reset)
+            }
+
+            // Uninstall selected extension
+            Event::DeleteEntry { selected } => {
+                uninstall_extension(self.flavor.as_ref(), &self.extensions[selected])
+                    .map(|_| {
+                        self.extensions.remove(selected);
+                        Action::Reload
+                    })
+                    .map_err(Into::into)
+            }
+
+            // Open a window with the selected extension disabled
+            Event::CustomCommand {
+                number: 0,
+                selected: Some(selected),
+            } => open_with_extension_disabled(self.flavor.as_ref(), &self.extensions[selected])
+                .map(|_| Action::Exit)
+                .map_err(Into::into),
+
+            // Anything else is not supported
+            Event::CustomCommand {
+                number: _,
+                selected: _,
+            } => Err(anyhow!("Command not supported")),
+            Event::CustomInput {
+                alt: _,
+                selected: _,
+            } => Err(anyhow!("Custom input not supported")),
+        };
+        // Handle errors
+        match res {
+            Ok(a) => a,
+            Err(e) => {
+                error!("{:?}", e);
+                Action::Exit
+            }
+        }
+    }
+
+    /// Check if the given matcher matches an entry
+    fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
+        matcher.matches(&self.extensions[line].id)
+    }
+}
@@ -0,0 +1,201 @@
+//! Rofi mode to open the files recently edited in a workspace
+
+use super::{determine_icon_config, exit_or_hand_off, pango_escape, IconConfig};
+use crate::logging::error;
+use crate::utils::{determine_shorten, determine_vscode_flavor, open_or_hand_off, shorten_path};
+use crate::vscode::{
+    workspace_storage::storage_for_recent,
+    workspaces::{recently_opened_from_storage, Recent},
+    EditorFlavor,
+};
+use anyhow::anyhow;
+use pangocairo::cairo;
+use rofi_mode::{self as rofi, Action, Api, Event, Matcher};
+
+/// Open a file recently edited in a workspace
+///
+/// The global history only contains a handful of standalone files,
+/// while each workspace keeps track of the editors opened in it.
+///
+/// The mode works in two steps:
+/// 1. the recent workspaces and folders are listed, and the selected one is chosen;
+/// 2. the files recently opened in it are listed, and the selected one is opened.
+///    Pressing <kbd>Escape</kbd> goes back to the workspaces.
+pub struct VSCodeFilesMode<'rofi> {
+    /// Binding to the Rofi api
+    api: Api<'rofi>,
+    /// The recent workspaces and folders
+    recents: Vec<Recent>,
+    /// The files recently opened in the chosen workspace
+    files: Vec<Recent>,
+    /// Index of the workspace chosen in the first step, if we are in the second one
+    chosen: Option<usize>,
+    /// The selected VSCode flavor
+    flavor: Box<dyn EditorFlavor>,
+    /// Length beyond which the labels are shortened, if any
+    shorten: Option<usize>,
+    /// Configuration to render icons
+    icon_config: IconConfig,
+}
+
+impl VSCodeFilesMode<'_> {
+    /// Proceed to the second step with the workspace at index `line`
+    fn choose_workspace(&mut self, line: usize) -> anyhow::Result<Action> {
+        self.files = match storage_for_recent(self.flavor.as_ref(), &self.recents[line])? {
+            Some(storage) => storage.editor_history()?,
+            None => Vec::new(),
+        };
+        self.chosen = Some(line);
+        self.api.set_display_name("Open File");
+        Ok(Action::Reset)
+    }
+
+    /// Go back to the list of workspaces
+    fn back_to_workspaces(&mut self) -> Action {
+        self.files.clear();
+        self.chosen = None;
+        self.api.set_display_name("Choose Workspace");
+        Action::Reset
+    }
+
+    /// The entries of the current step
+    fn current(&self) -> &[Recent] {
+        match self.chosen {
+            None => &self.recents,
+            Some(_) => &self.files,
+        }
+    }
+
+    /// Open a file, going to it directly if it is local
+    fn open_file(&self, file: &Recent) -> anyhow::Result<Action> {
+        match file.file_path() {
+            Ok(path) => {
+                self.flavor.goto(path.as_os_str())?;
+                Ok(Action::Exit)
+            }
+            Err(_) => exit_or_hand_off(open_or_hand_off(self.flavor.as_ref(), file)?),
+        }
+    }
+}
+
+impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeFilesMode<'rofi> {
+    const NAME: &'static str = "vscode-files\0";
+
+    /// Initialization
+    fn init(mut api: Api<'rofi>) -> Result<Self, ()> {
+        // Set name
+        api.set_display_name("Choose Workspace");
+        // Initialize vscode flavor
+        let flavor = determine_vscode_flavor().map_err(|e| error!("{:?}", e))?;
+        // Initialize the entries, only workspaces and folders have their own storage
+        let recents = recently_opened_from_storage(flavor.as_ref(), false)
+            .map_err(|e| error!("{:?}", e))?
+            .into_iter()
+            .filter(|r| {
+                !matches!(
+                    r,
+                    Recent::File {
+                        file_uri: _,
+                        label: _,
+                        remote_authority: _,
+                    }
+                )
+            })
+            .collect();
+
+        let shorten = determine_shorten("files").map_err(|e| error!("{:?}", e))?;
+        let icon_config = determine_icon_config().map_err(|e| error!("{:?}", e))?;
+
+        Ok(VSCodeFilesMode {
+            api,
+            recents,
+            files: Vec::new(),
+            chosen: None,
+            flavor,
+            shorten,
+            icon_config,
+        })
+    }
+
+    /// Get the number of entries offered by the mode
+    fn entries(&mut self) -> usize {
+        self.current().len()
+    }
+
+    fn entry_content(&self, line: usize) -> rofi::String {
+        match self.current()[line].label() {
+            Ok(label) => match self.shorten {
+                Some(max) => rofi::String::from(shorten_path(&label, max).as_str()),
+                None => rofi::String::from(label.as_ref()),
+            },
+            Err(e) => {
+                error!("{}", e);
+                rofi::String::new()
+            }
+        }
+    }
+
+    fn entry_icon(&mut self, line: usize, height: u32) -> Option<cairo::Surface> {
+        let entry = match self.chosen {
+            None => &self.recents[line],
+            Some(_) => &self.files[line],
+        };
+        self.icon_config.recent_icon(&mut self.api, entry, height)
+    }
+
+    fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        let res: anyhow::Result<Action> = match (self.chosen, event) {
+            // Pressed Escape key while choosing the workspace
+            (None, Event::Cancel { selected: _ }) => Ok(Action::Exit),
+            // Pressed Escape key while choosing the file
+            (Some(_), Event::Cancel { selected: _ }) => Ok(self.back_to_workspaces()),
+
+            // Selected a workspace, list its files
+            (None, Event::Ok { alt: _, selected }) => self.choose_workspace(selected),
+            // Selected a file, open it
+            (Some(_), Event::Ok { alt: _, selected }) => self.open_file(&self.files[selected]),
+
+            // Autocomplete input from selected entry
+            (_, Event::Complete { selected }) => {
+                if let Some(line) = selected {
+                    if let Ok(label) = self.current()[line].label() {
+                        *input = rofi::String::from(label.as_ref());
+                    }
+                }
+                Ok(Action::Reset)
+            }
+
+            // Anything else is not supported
+            (_, Event::CustomInput { .. }) => Err(anyhow!("Custom input not supported")),
+            (_, Event::DeleteEntry { .. }) => Err(anyhow!("Deletion not supported")),
+            (_, Event::CustomCommand { .. }) => Err(anyhow!("Command not supported")),
+        };
+        // Handle errors
+        match res {
+            Ok(a) => a,
+            Err(e) => {
+                error!("{:?}", e);
+                Action::Exit
+            }
+        }
+    }
+
+    /// Check if the given matcher matches an entry
+    fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
+        match self.current()[line].label() {
+            Ok(label) => matcher.matches(&label),
+            Err(_) => false,
+        }
+    }
+
+    /// Tell the user when no files were opened in the chosen workspace
+    fn message(&mut self) -> rofi::String {
+        match self.chosen {
+            Some(line) if self.files.is_empty() => {
+                let label = self.recents[line].label().unwrap_or_default();
+                rofi::format!("No recent files in {}", pango_escape(&label))
+            }
+            _ => rofi::String::new(),
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Rofi mode to open VSCode with one of its profiles
+
+use super::{determine_icon_config, IconConfig};
+use crate::logging::error;
+use crate::utils::determine_vscode_flavor;
+use crate::vscode::{
+    profiles::{profiles_from_storage, Profile},
+    workspaces::{recently_opened_from_storage, Recent},
+    EditorFlavor,
+};
+use anyhow::anyhow;
+use pangocairo::cairo;
+use rofi_mode::{self as rofi, Action, Api, Event, Matcher};
+
+/// Theme icon shown next to profiles
+const PROFILE_ICON_NAME: &str = "avatar-default";
+/// Nerd font glyph shown next to profiles
+const PROFILE_NERD_ICON: &str = "\u{f007}";
+
+/// Open a new VSCode window with the chosen profile
+///
+/// The mode works in two steps:
+/// 1. the profiles are listed: pressing <kbd>Enter</kbd> opens an empty window with the selected profile,
+///    while <kbd>Shift</kbd>+<kbd>Enter</kbd> proceeds to the second step;
+/// 2. the recent items are listed, and the selected one is opened with the profile chosen before.
+///    Pressing <kbd>Escape</kbd> goes back to the profiles.
+pub struct VSCodeProfilesMode<'rofi> {
+    /// Binding to the Rofi api
+    api: Api<'rofi>,
+    /// The profiles of the flavor
+    profiles: Vec<Profile>,
+    /// The recent items, loaded when entering the second step
+    recents: Vec<Recent>,
+    /// Index of the profile chosen in the first step, if we are in the second one
+    chosen: Option<usize>,
+    /// The selected VSCode flavor
+    flavor: Box<dyn EditorFlavor>,
+    /// Configuration to render icons
+    icon_config: IconConfig,
+}
+
+impl VSCodeProfilesMode<'_> {
+    /// Proceed to the second step with the profile at index `line`
+    fn choose_profile(&mut self, line: usize) -> anyhow::Result<Action> {
+        self.recents = recently_opened_from_storage(self.flavor.as_ref(), false)?;
+        self.chosen = Some(line);
+        self.api
+            .set_display_name(format!("Open with {}", self.profiles[line].name));
+        Ok(Action::Reset)
+    }
+
+    /// Go back to the list of profiles
+    fn back_to_profiles(&mut self) -> Action {
+        self.recents.clear();
+        self.chosen = None;
+        self.api.set_display_name("Open Profile");
+        Action::Reset
+    }
+
+    /// Label of the entry at index `line` in the current step
+    fn label(&self, line: usize) -> anyhow::Result<String> {
+        match self.chosen {
+            None => Ok(self.profiles[line].name.clone()),
+            Some(_) => Ok(self.recents[line].label()?.to_string()),
+        }
+    }
+}
+
+impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeProfilesMode<'rofi> {
+    const NAME: &'static str = "vscode-profiles\0";
+
+    /// Initialization
+    fn init(mut api: Api<'rofi>) -> Result<Self, ()> {
+        // Set name
+        api.set_display_name("Open Profile");
+        // Initialize vscode flavor
+        let flavor = determine_vscode_flavor().map_err(|e| error!("{:?}", e))?;
+        // Initialize the profiles
+        let profiles = profiles_from_storage(flavor.as_ref()).map_err(|e| error!("{:?}", e))?;
+
+        let icon_config = determine_icon_config().map_err(|e| error!("{:?}", e))?;
+
+        Ok(VSCodeProfilesMode {
+            api,
+            profiles,
+            recents: Vec::new(),
+            chosen: None,
+            flavor,
+            icon_config,
+        })
+    }
+
+    /// Get the number of entries offered by the mode
+    fn entries(&mut self) -> usize {
+        match self.chosen {
+            None => self.profiles.len(),
+            Some(_) => self.recents.len(),
+        }
+    }
+
+    fn entry_content(&self, line: usize) -> rofi::String {
+        match self.label(line) {
+            Ok(label) => rofi::String::from(label.as_str()),
+            Err(e) => {
+                error!("{}", e);
+                rofi::String::new()
+            }
+        }
+    }
+
+    fn entry_icon(&mut self, line: usize, height: u32) -> Option<cairo::Surface> {
+        match self.chosen {
+            None => {
+                self.icon_config
+                    .icon(&mut self.api, PROFILE_ICON_NAME, PROFILE_NERD_ICON, height)
+            }
+            Some(_) => {
+                let entry = &self.recents[line];
+                self.icon_config.recent_icon(&mut self.api, entry, height)
+            }
+        }
+    }
+
+    fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        let res: anyhow::Result<Action> = match (self.chosen, event) {
+            // Pressed Escape key while choosing the profile
+            (None, Event::Cancel { selected: _ }) => Ok(Action::Exit),
+            // Pressed Escape key while choosing the recent item
+            (Some(_), Event::Cancel { selected: _ }) => Ok(self.back_to_profiles()),
+
+            // Selected a profile, open an empty window
+            (
+                None,
+                Event::Ok {
+                    alt: false,
+                    selected,
+                },
+            ) => self
+                .flavor
+                .open_profile(&self.profiles[selected], None)
+                .map(|_| Action::Exit)
+                .map_err(Into::into),
+            // Selected a profile with the alt binding, choose a recent item
+            (
+                None,
+                Event::Ok {
+                    alt: true,
+                    selected,
+                },
+            ) => self.choose_profile(selected),
+            // Selected a recent item, open it with the chosen profile
+            (Some(profile), Event::Ok { alt: _, selected }) => self
+                .flavor
+                .open_profile(&self.profiles[profile], Some(&self.recents[selected]))
+                .map(|_| Action::Exit)
+                .map_err(Into::into),
+
+            // Autocomplete input from selected entry
+            (_, Event::Complete { selected }) => {
+                if let Some(line) = selected {
+                    if let Ok(label) = self.label(line) {
+                        *input = rofi::String::from(label.as_str());
+                    }
+                }
+                Ok(Action::Reset)
+            }
+
+            // Anything else is not supported
+            (_, Event::CustomInput { .. }) => Err(anyhow!("Custom input not supported")),
+            (_, Event::DeleteEntry { .. }) => Err(anyhow!("Deletion not supported")),
+            (_, Event::CustomCommand { .. }) => Err(anyhow!("Command not supported")),
+        };
+        // Handle errors
+        match res {
+            Ok(a) => a,
+            Err(e) => {
+                error!("{:?}", e);
+                Action::Exit
+            }
+        }
+    }
+
+    /// Check if the given matcher matches an entry
+    fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
+        match self.label(line) {
+            Ok(label) => matcher.matches(&label),
+            Err(_) => false,
+        }
+    }
+}
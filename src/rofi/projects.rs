@@ -0,0 +1,126 @@
+//! Rofi mode to open the projects saved with the Project Manager extension
+
+use super::{determine_icon_config, exit_or_hand_off, IconConfig};
+use crate::logging::error;
+use crate::utils::{determine_vscode_flavor, open_or_hand_off};
+use crate::vscode::{
+    projects::{projects_from_storage, Project},
+    workspaces::Recent,
+    EditorFlavor,
+};
+use anyhow::anyhow;
+use pangocairo::cairo;
+use rofi_mode::{self as rofi, Action, Api, Event, Matcher};
+
+/// Open a project saved with the Project Manager extension
+///
+/// Each project is shown with its tags, which can also be used to filter the list.
+pub struct VSCodeProjectsMode<'rofi> {
+    /// Binding to the Rofi api
+    api: Api<'rofi>,
+    /// The saved projects, each with the recent item used to open it
+    projects: Vec<(Project, Recent)>,
+    /// The selected VSCode flavor
+    flavor: Box<dyn EditorFlavor>,
+    /// Configuration to render icons
+    icon_config: IconConfig,
+}
+
+impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeProjectsMode<'rofi> {
+    const NAME: &'static str = "vscode-projects\0";
+
+    /// Initialization
+    fn init(mut api: Api<'rofi>) -> Result<Self, ()> {
+        // Set name
+        api.set_display_name("Open Project");
+        // Initialize vscode flavor
+        let flavor = determine_vscode_flavor().map_err(|e| error!("{:?}", e))?;
+        // Initialize the entries, skipping the projects that cannot be opened
+        let projects = projects_from_storage(flavor.as_ref())
+            .map_err(|e| error!("{:?}", e))?
+            .into_iter()
+            .filter_map(|p| match p.to_recent() {
+                Ok(recent) => Some((p, recent)),
+                Err(e) => {
+                    error!("{:?}", e);
+                    None
+                }
+            })
+            .collect();
+
+        let icon_config = determine_icon_config().map_err(|e| error!("{:?}", e))?;
+
+        Ok(VSCodeProjectsMode {
+            api,
+            projects,
+            flavor,
+            icon_config,
+        })
+    }
+
+    /// Get the number of entries offered by the mode
+    fn entries(&mut self) -> usize {
+        self.projects.len()
+    }
+
+    fn entry_content(&self, line: usize) -> rofi::String {
+        let (project, _) = &self.projects[line];
+        if project.tags.is_empty() {
+            rofi::String::from(project.name.as_str())
+        } else {
+            rofi::format!("{} [{}]", project.name, project.tags.join(", "))
+        }
+    }
+
+    fn entry_icon(&mut self, line: usize, height: u32) -> Option<cairo::Surface> {
+        let (_, recent) = &self.projects[line];
+        self.icon_config.icon(
+            &mut self.api,
+            recent.icon_name(),
+            recent.nerd_icon(),
+            height,
+        )
+    }
+
+    fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        let res: anyhow::Result<Action> = match event {
+            // Pressed Escape key
+            Event::Cancel { selected: _ } => Ok(Action::Exit),
+
+            // Selected a project
+            Event::Ok { alt: _, selected } => {
+                let (_, recent) = &self.projects[selected];
+                open_or_hand_off(self.flavor.as_ref(), recent)
+                    .map_err(Into::into)
+                    .and_then(exit_or_hand_off)
+            }
+
+            // Autocomplete input from selected entry
+            Event::Complete { selected } => {
+                if let Some(line) = selected {
+                    *input = rofi::String::from(self.projects[line].0.name.as_str());
+                }
+                Ok(Action::Reset)
+            }
+
+            // Anything else is not supported
+            Event::CustomInput { .. } => Err(anyhow!("Custom input not supported")),
+            Event::DeleteEntry { .. } => Err(anyhow!("Deletion not supported")),
+            Event::CustomCommand { .. } => Err(anyhow!("Command not supported")),
+        };
+        // Handle errors
+        match res {
+            Ok(a) => a,
+            Err(e) => {
+                error!("{:?}", e);
+                Action::Exit
+            }
+        }
+    }
+
+    /// Check if the given matcher matches the name or one of the tags of a project
+    fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
+        let (project, _) = &self.projects[line];
+        matcher.matches(&project.name) || project.tags.iter().any(|t| matcher.matches(t))
+    }
+}
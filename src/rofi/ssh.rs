@@ -0,0 +1,222 @@
+//! Rofi mode to connect VSCode to the hosts in the SSH configuration, and to the machines reachable over tunnels
+
+use std::collections::HashMap;
+
+use super::{determine_icon_config, IconConfig};
+use crate::config;
+use crate::logging::{debug, error};
+use crate::ssh::{hosts_from_config, SshHost};
+use crate::tunnels::{list_tunnels, Tunnel};
+use crate::utils::determine_vscode_flavor;
+use crate::vscode::{workspaces::recently_opened_from_storage, EditorFlavor};
+use anyhow::anyhow;
+use pangocairo::cairo;
+use rofi_mode::{self as rofi, Action, Api, Event, Matcher};
+
+const ENV_SSH_PATHS: &str = "ROFI_VSCODE_SSH_PATHS";
+
+/// Theme icon shown next to hosts
+const HOST_ICON_NAME: &str = "network-server";
+/// Nerd font glyph shown next to hosts
+const HOST_NERD_ICON: &str = "\u{f233}";
+/// Theme icon shown next to tunnels
+const TUNNEL_ICON_NAME: &str = "network-vpn";
+/// Nerd font glyph shown next to tunnels
+const TUNNEL_NERD_ICON: &str = "\u{f0ac}";
+
+/// An entry of the mode
+enum Remote<'a> {
+    Host(&'a SshHost),
+    Tunnel(&'a Tunnel),
+}
+
+impl Remote<'_> {
+    /// Name of the host or tunnel
+    fn name(&self) -> &str {
+        match self {
+            Remote::Host(host) => &host.alias,
+            Remote::Tunnel(tunnel) => &tunnel.name,
+        }
+    }
+}
+
+/// Open a new VSCode window connected to an SSH host
+///
+/// This complements the recent items, since it also lists the hosts that were never connected to.
+/// The tunnels of this machine and of the recent items are listed after the hosts.
+pub struct VSCodeSshMode<'rofi> {
+    /// Binding to the Rofi api
+    api: Api<'rofi>,
+    /// The hosts that will be displayed
+    hosts: Vec<SshHost>,
+    /// The tunnels that will be displayed after the hosts
+    tunnels: Vec<Tunnel>,
+    /// Remote path to open by default, by host alias or tunnel name
+    default_paths: HashMap<String, String>,
+    /// The selected VSCode flavor
+    flavor: Box<dyn EditorFlavor>,
+    /// Configuration to render icons
+    icon_config: IconConfig,
+}
+
+impl VSCodeSshMode<'_> {
+    /// The host or tunnel shown at `line`
+    fn remote(&self, line: usize) -> Remote<'_> {
+        match self.hosts.get(line) {
+            Some(host) => Remote::Host(host),
+            None => Remote::Tunnel(&self.tunnels[line - self.hosts.len()]),
+        }
+    }
+
+    /// Connect to the host or tunnel shown at `line`, opening its default path if any
+    fn open_line(&self, line: usize) -> anyhow::Result<Action> {
+        match self.remote(line) {
+            Remote::Host(host) => self.open_host(&host.alias),
+            Remote::Tunnel(tunnel) => {
+                let path = self.default_paths.get(&tunnel.name).map(String::as_str);
+                tunnel.open(self.flavor.as_ref(), path)?;
+                Ok(Action::Exit)
+            }
+        }
+    }
+
+    /// Connect to the host with the given alias, opening its default path if any
+    fn open_host(&self, alias: &str) -> anyhow::Result<Action> {
+        let host = SshHost {
+            alias: alias.to_string(),
+            hostname: None,
+            user: None,
+        };
+        let path = self.default_paths.get(alias).map(String::as_str);
+        self.flavor.open_remote(&host.remote_authority(), path)?;
+        Ok(Action::Exit)
+    }
+}
+
+impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeSshMode<'rofi> {
+    const NAME: &'static str = "vscode-ssh\0";
+
+    /// Initialization
+    fn init(mut api: Api<'rofi>) -> Result<Self, ()> {
+        // Set name
+        api.set_display_name("Open SSH Host");
+        // Initialize vscode flavor
+        let flavor = determine_vscode_flavor().map_err(|e| error!("{:?}", e))?;
+        // Initialize the hosts
+        let hosts = hosts_from_config().map_err(|e| error!("{:?}", e))?;
+        // The tunnels are optional, as the mode is mostly about SSH
+        let recents = recently_opened_from_storage(flavor.as_ref(), false).unwrap_or_else(|e| {
+            debug!("{:?}", e);
+            Vec::new()
+        });
+        let tunnels = list_tunnels(flavor.as_ref(), &recents);
+        let default_paths = config::var(ENV_SSH_PATHS)
+            .map(|v| parse_default_paths(&v))
+            .unwrap_or_default();
+
+        let icon_config = determine_icon_config().map_err(|e| error!("{:?}", e))?;
+
+        Ok(VSCodeSshMode {
+            api,
+            hosts,
+            tunnels,
+            default_paths,
+            flavor,
+            icon_config,
+        })
+    }
+
+    /// Get the number of entries offered by the mode
+    fn entries(&mut self) -> usize {
+        self.hosts.len() + self.tunnels.len()
+    }
+
+    fn entry_content(&self, line: usize) -> rofi::String {
+        let host = match self.remote(line) {
+            Remote::Host(host) => host,
+            Remote::Tunnel(tunnel) => {
+                return match tunnel.connected {
+                    Some(true) => rofi::format!("{} (tunnel, connected)", tunnel.name),
+                    Some(false) => rofi::format!("{} (tunnel, disconnected)", tunnel.name),
+                    None => rofi::format!("{} (tunnel)", tunnel.name),
+                }
+            }
+        };
+        match (&host.user, &host.hostname) {
+            (Some(user), Some(hostname)) => {
+                rofi::format!("{} ({}@{})", host.alias, user, hostname)
+            }
+            (None, Some(hostname)) => rofi::format!("{} ({})", host.alias, hostname),
+            (_, None) => rofi::String::from(host.alias.as_str()),
+        }
+    }
+
+    fn entry_icon(&mut self, line: usize, height: u32) -> Option<cairo::Surface> {
+        let (name, glyph) = match self.remote(line) {
+            Remote::Host(_) => (HOST_ICON_NAME, HOST_NERD_ICON),
+            Remote::Tunnel(_) => (TUNNEL_ICON_NAME, TUNNEL_NERD_ICON),
+        };
+        self.icon_config.icon(&mut self.api, name, glyph, height)
+    }
+
+    fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        let res: anyhow::Result<Action> = match event {
+            // Pressed Escape key
+            Event::Cancel { selected: _ } => Ok(Action::Exit),
+
+            // Selected a host or tunnel
+            Event::Ok { alt: _, selected } => self.open_line(selected),
+            // Selected a custom input (not in list), treat it as a host
+            Event::CustomInput {
+                alt: _,
+                selected: _,
+            } => self.open_host(input.trim()),
+
+            // Autocomplete input from selected entry
+            Event::Complete { selected } => {
+                if let Some(line) = selected {
+                    *input = rofi::String::from(self.remote(line).name());
+                }
+                Ok(Action::Reset)
+            }
+
+            // Hosts can only be removed from the SSH configuration, and tunnels from their machine
+            Event::DeleteEntry { selected: _ } => Err(anyhow!("Deletion not supported")),
+
+            // User ran a custom command
+            Event::CustomCommand {
+                number: _,
+                selected: _,
+            } => Err(anyhow!("Command not supported")),
+        };
+        // Handle errors
+        match res {
+            Ok(a) => a,
+            Err(e) => {
+                error!("{:?}", e);
+                Action::Exit
+            }
+        }
+    }
+
+    /// Check if the given matcher matches an entry
+    fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
+        let host = match self.remote(line) {
+            Remote::Host(host) => host,
+            Remote::Tunnel(tunnel) => return matcher.matches(&tunnel.name),
+        };
+        matcher.matches(&host.alias)
+            || host
+                .hostname
+                .as_deref()
+                .is_some_and(|hostname| matcher.matches(hostname))
+    }
+}
+
+/// Parse the default paths from a string of the form `host=path,host=path`
+fn parse_default_paths(s: &str) -> HashMap<String, String> {
+    s.split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(host, path)| (host.trim().to_string(), path.trim().to_string()))
+        .collect()
+}
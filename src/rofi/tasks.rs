@@ -0,0 +1,196 @@
+//! Rofi mode to run the tasks of a recent workspace or folder
+
+use super::{determine_icon_config, pango_escape, IconConfig};
+use crate::config;
+use crate::logging::error;
+use crate::utils::determine_vscode_flavor;
+use crate::vscode::{
+    tasks::{recent_tasks, Task},
+    workspaces::{recently_opened_from_storage, Recent},
+};
+use anyhow::anyhow;
+use pangocairo::cairo;
+use rofi_mode::{self as rofi, Action, Api, Event, Matcher};
+
+const ENV_TASK_TERMINAL: &str = "ROFI_VSCODE_TASK_TERMINAL";
+
+/// Theme icon shown next to tasks
+const TASK_ICON_NAME: &str = "system-run";
+/// Nerd font glyph shown next to tasks
+const TASK_NERD_ICON: &str = "\u{f013}";
+
+/// Run a task without opening VSCode
+///
+/// The mode works in two steps:
+/// 1. the recent local workspaces and folders are listed, and the selected one is chosen;
+/// 2. its tasks are listed, and the selected one is run.
+///    Pressing <kbd>Escape</kbd> goes back to the workspaces.
+pub struct VSCodeTasksMode<'rofi> {
+    /// Binding to the Rofi api
+    api: Api<'rofi>,
+    /// The recent workspaces and folders
+    recents: Vec<Recent>,
+    /// The tasks of the chosen workspace
+    tasks: Vec<Task>,
+    /// Index of the workspace chosen in the first step, if we are in the second one
+    chosen: Option<usize>,
+    /// Terminal command to run the tasks into, if any
+    terminal: Option<String>,
+    /// Configuration to render icons
+    icon_config: IconConfig,
+}
+
+impl VSCodeTasksMode<'_> {
+    /// Proceed to the second step with the workspace at index `line`
+    fn choose_workspace(&mut self, line: usize) -> anyhow::Result<Action> {
+        self.tasks = recent_tasks(&self.recents[line])?;
+        self.chosen = Some(line);
+        self.api.set_display_name("Run Task");
+        Ok(Action::Reset)
+    }
+
+    /// Go back to the list of workspaces
+    fn back_to_workspaces(&mut self) -> Action {
+        self.tasks.clear();
+        self.chosen = None;
+        self.api.set_display_name("Choose Workspace");
+        Action::Reset
+    }
+
+    /// Label of the entry at index `line` in the current step
+    fn label(&self, line: usize) -> anyhow::Result<String> {
+        match self.chosen {
+            None => Ok(self.recents[line].label()?.to_string()),
+            Some(_) => Ok(self.tasks[line].label.clone()),
+        }
+    }
+}
+
+impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeTasksMode<'rofi> {
+    const NAME: &'static str = "vscode-tasks\0";
+
+    /// Initialization
+    fn init(mut api: Api<'rofi>) -> Result<Self, ()> {
+        // Set name
+        api.set_display_name("Choose Workspace");
+        // Initialize vscode flavor
+        let flavor = determine_vscode_flavor().map_err(|e| error!("{:?}", e))?;
+        // Initialize the entries, only workspaces and folders can have tasks
+        let recents = recently_opened_from_storage(flavor.as_ref(), true)
+            .map_err(|e| error!("{:?}", e))?
+            .into_iter()
+            .filter(|r| {
+                !matches!(
+                    r,
+                    Recent::File {
+                        file_uri: _,
+                        label: _,
+                        remote_authority: _,
+                    }
+                )
+            })
+            .collect();
+        let terminal = config::var(ENV_TASK_TERMINAL);
+
+        let icon_config = determine_icon_config().map_err(|e| error!("{:?}", e))?;
+
+        Ok(VSCodeTasksMode {
+            api,
+            recents,
+            tasks: Vec::new(),
+            chosen: None,
+            terminal,
+            icon_config,
+        })
+    }
+
+    /// Get the number of entries offered by the mode
+    fn entries(&mut self) -> usize {
+        match self.chosen {
+            None => self.recents.len(),
+            Some(_) => self.tasks.len(),
+        }
+    }
+
+    fn entry_content(&self, line: usize) -> rofi::String {
+        match self.label(line) {
+            Ok(label) => rofi::String::from(label.as_str()),
+            Err(e) => {
+                error!("{}", e);
+                rofi::String::new()
+            }
+        }
+    }
+
+    fn entry_icon(&mut self, line: usize, height: u32) -> Option<cairo::Surface> {
+        match self.chosen {
+            None => {
+                let entry = &self.recents[line];
+                self.icon_config
+                    .icon(&mut self.api, entry.icon_name(), entry.nerd_icon(), height)
+            }
+            Some(_) => self
+                .icon_config
+                .icon(&mut self.api, TASK_ICON_NAME, TASK_NERD_ICON, height),
+        }
+    }
+
+    fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        let res: anyhow::Result<Action> = match (self.chosen, event) {
+            // Pressed Escape key while choosing the workspace
+            (None, Event::Cancel { selected: _ }) => Ok(Action::Exit),
+            // Pressed Escape key while choosing the task
+            (Some(_), Event::Cancel { selected: _ }) => Ok(self.back_to_workspaces()),
+
+            // Selected a workspace, list its tasks
+            (None, Event::Ok { alt: _, selected }) => self.choose_workspace(selected),
+            // Selected a task, run it
+            (Some(_), Event::Ok { alt: _, selected }) => self.tasks[selected]
+                .run(self.terminal.as_deref())
+                .map(|_| Action::Exit)
+                .map_err(Into::into),
+
+            // Autocomplete input from selected entry
+            (_, Event::Complete { selected }) => {
+                if let Some(line) = selected {
+                    if let Ok(label) = self.label(line) {
+                        *input = rofi::String::from(label.as_str());
+                    }
+                }
+                Ok(Action::Reset)
+            }
+
+            // Anything else is not supported
+            (_, Event::CustomInput { .. }) => Err(anyhow!("Custom input not supported")),
+            (_, Event::DeleteEntry { .. }) => Err(anyhow!("Deletion not supported")),
+            (_, Event::CustomCommand { .. }) => Err(anyhow!("Command not supported")),
+        };
+        // Handle errors
+        match res {
+            Ok(a) => a,
+            Err(e) => {
+                error!("{:?}", e);
+                Action::Exit
+            }
+        }
+    }
+
+    /// Check if the given matcher matches an entry
+    fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
+        match self.label(line) {
+            Ok(label) => matcher.matches(&label),
+            Err(_) => false,
+        }
+    }
+
+    /// Tell the user when the chosen workspace has no tasks
+    fn message(&mut self) -> rofi::String {
+        match self.chosen {
+            Some(line) if self.tasks.is_empty() => {
+                let label = self.recents[line].label().unwrap_or_default();
+                rofi::format!("No tasks found in {}", pango_escape(&label))
+            }
+            _ => rofi::String::new(),
+        }
+    }
+}
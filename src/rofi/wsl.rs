@@ -0,0 +1,114 @@
+//! Rofi mode to open VSCode in the installed WSL distributions
+
+use super::{determine_icon_config, IconConfig};
+use crate::logging::error;
+use crate::utils::determine_vscode_flavor;
+use crate::vscode::EditorFlavor;
+use crate::wsl::{interop_available, list_distros, remote_authority, WSL_ICON_NAME, WSL_NERD_ICON};
+use anyhow::anyhow;
+use pangocairo::cairo;
+use rofi_mode::{self as rofi, Action, Api, Event, Matcher};
+
+/// Open a new VSCode window connected to a WSL distribution
+///
+/// This complements the recent items, since it also lists the distributions that were never connected to.
+/// It is available only on systems with WSL interoperability.
+pub struct VSCodeWslMode<'rofi> {
+    /// Binding to the Rofi api
+    api: Api<'rofi>,
+    /// The installed distributions
+    distros: Vec<String>,
+    /// The selected VSCode flavor
+    flavor: Box<dyn EditorFlavor>,
+    /// Configuration to render icons
+    icon_config: IconConfig,
+}
+
+impl VSCodeWslMode<'_> {
+    /// Connect to the given distribution
+    fn open_distro(&self, distro: &str) -> anyhow::Result<Action> {
+        self.flavor.open_remote(&remote_authority(distro), None)?;
+        Ok(Action::Exit)
+    }
+}
+
+impl<'rofi> rofi_mode::Mode<'rofi> for VSCodeWslMode<'rofi> {
+    const NAME: &'static str = "vscode-wsl\0";
+
+    /// Initialization
+    fn init(mut api: Api<'rofi>) -> Result<Self, ()> {
+        // Set name
+        api.set_display_name("Open WSL Distribution");
+        // Initialize vscode flavor
+        let flavor = determine_vscode_flavor().map_err(|e| error!("{:?}", e))?;
+        // Initialize the distributions
+        if !interop_available() {
+            error!("{:?}", anyhow!("WSL interoperability is not available"));
+            return Err(());
+        }
+        let distros = list_distros().map_err(|e| error!("{:?}", e))?;
+
+        let icon_config = determine_icon_config().map_err(|e| error!("{:?}", e))?;
+
+        Ok(VSCodeWslMode {
+            api,
+            distros,
+            flavor,
+            icon_config,
+        })
+    }
+
+    /// Get the number of entries offered by the mode
+    fn entries(&mut self) -> usize {
+        self.distros.len()
+    }
+
+    fn entry_content(&self, line: usize) -> rofi::String {
+        rofi::String::from(self.distros[line].as_str())
+    }
+
+    fn entry_icon(&mut self, _line: usize, height: u32) -> Option<cairo::Surface> {
+        self.icon_config
+            .icon(&mut self.api, WSL_ICON_NAME, WSL_NERD_ICON, height)
+    }
+
+    fn react(&mut self, event: Event, input: &mut rofi::String) -> Action {
+        let res: anyhow::Result<Action> = match event {
+            // Pressed Escape key
+            Event::Cancel { selected: _ } => Ok(Action::Exit),
+
+            // Selected a distribution
+            Event::Ok { alt: _, selected } => self.open_distro(&self.distros[selected]),
+            // Selected a custom input (not in list), treat it as a distribution
+            Event::CustomInput {
+                alt: _,
+                selected: _,
+            } => self.open_distro(input.trim()),
+
+            // Autocomplete input from selected entry
+            Event::Complete { selected } => {
+                if let Some(line) = selected {
+                    *input = rofi::String::from(self.distros[line].as_str());
+                }
+                Ok(Action::Reset)
+            }
+
+            // Anything else is not supported
+            Event::DeleteEntry { .. } => Err(anyhow!("Deletion not supported")),
+            Event::CustomCommand { .. } => Err(anyhow!("Command not supported")),
+        };
+        // Handle errors
+        match res {
+            Ok(a) => a,
+            Err(e) => {
+                error!("{:?}", e);
+                Action::Exit
+            }
+        }
+    }
+
+    /// Check if the given matcher matches an entry
+    fn matches(&self, line: usize, matcher: Matcher<'_>) -> bool {
+        matcher.matches(&self.distros[line])
+    }
+}
@@ -0,0 +1,216 @@
+//! Providers of recent items
+//!
+//! The history of each VSCode flavor is a [RecentSource], and other providers can be listed alongside it.
+//! The items of all the sources are merged into a single list with [fetch_recents],
+//! tagging each item with the index of the source it comes from.
+//!
+//! The other sources are:
+//! - [xbel::RecentlyUsedFiles], the source code files recently used with any application
+//! - [zoxide::ZoxideDirectories], the directories most visited from the shell
+//! - [repos::Repositories], the repositories cloned with ghq or under a root directory
+
+use std::{collections::HashSet, fmt, path::PathBuf};
+
+use url::Url;
+
+use crate::error::{other, Result};
+use crate::vscode::{
+    workspaces::{
+        bump_recently_opened, clear_recently_opened, merge_recents, recently_opened_from_storage,
+        remove_recently_opened, restore_recently_opened, Recent,
+    },
+    EditorFlavor,
+};
+
+pub mod repos;
+pub mod xbel;
+pub mod zoxide;
+
+/// A provider of recent items
+///
+/// Only fetching the items is required. Sources that are not a history, e.g. a scanned directory,
+/// refuse deletions and are left untouched when the history is cleared.
+pub trait RecentSource: fmt::Debug + Send + Sync {
+    /// Name of the source, shown next to its items when there are many sources
+    fn name(&self) -> &str;
+
+    /// Get the items of the source, the most relevant first
+    fn fetch(&self) -> Result<Vec<Recent>>;
+
+    /// Remove the item with the given URL from the source
+    ///
+    /// Returns the position that the item had in the source, if it was found.
+    fn delete(&self, _url: &Url) -> Result<Option<usize>> {
+        Err(other!("Items cannot be deleted from {}", self.name()))
+    }
+
+    /// Put back a deleted item at the `position` it had in the source
+    fn restore(&self, _recent: &Recent, _position: usize) -> Result<()> {
+        Err(other!("Items cannot be restored into {}", self.name()))
+    }
+
+    /// Remove all the items from the source, if it is a history
+    fn clear(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Move the item with the given URL to the top of the source, as if it was just opened
+    fn bump(&self, _url: &Url) -> Result<()> {
+        Err(other!("Items cannot be moved in {}", self.name()))
+    }
+}
+
+impl<S: RecentSource + ?Sized> RecentSource for &S {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn fetch(&self) -> Result<Vec<Recent>> {
+        (**self).fetch()
+    }
+
+    fn delete(&self, url: &Url) -> Result<Option<usize>> {
+        (**self).delete(url)
+    }
+
+    fn restore(&self, recent: &Recent, position: usize) -> Result<()> {
+        (**self).restore(recent, position)
+    }
+
+    fn clear(&self) -> Result<()> {
+        (**self).clear()
+    }
+
+    fn bump(&self, url: &Url) -> Result<()> {
+        (**self).bump(url)
+    }
+}
+
+/// The recent items stored by a flavor
+#[derive(Debug, Clone, Copy)]
+pub struct FlavorHistory<'a>(pub &'a dyn EditorFlavor);
+
+impl RecentSource for FlavorHistory<'_> {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn fetch(&self) -> Result<Vec<Recent>> {
+        recently_opened_from_storage(self.0, false)
+    }
+
+    fn delete(&self, url: &Url) -> Result<Option<usize>> {
+        remove_recently_opened(self.0, url)
+    }
+
+    fn restore(&self, recent: &Recent, position: usize) -> Result<()> {
+        restore_recently_opened(self.0, recent, position)
+    }
+
+    fn clear(&self) -> Result<()> {
+        clear_recently_opened(self.0)
+    }
+
+    fn bump(&self, url: &Url) -> Result<()> {
+        match bump_recently_opened(self.0, url)? {
+            true => Ok(()),
+            false => Err(other!(
+                "{} is not in the recent items of {}",
+                url,
+                self.name()
+            )),
+        }
+    }
+}
+
+/// The histories of the `flavors`, followed by the `others` sources
+///
+/// The index of each flavor is thus the index of its history.
+pub fn all_sources<'a>(
+    flavors: &'a [Box<dyn EditorFlavor>],
+    others: &'a [Box<dyn RecentSource>],
+) -> Vec<Box<dyn RecentSource + 'a>> {
+    let histories = flavors
+        .iter()
+        .map(|f| Box::new(FlavorHistory(f.as_ref())) as Box<dyn RecentSource + 'a>);
+    let others = others
+        .iter()
+        .map(|s| Box::new(s.as_ref()) as Box<dyn RecentSource + 'a>);
+    histories.chain(others).collect()
+}
+
+/// What an item points to, its path if it is local or else its URL
+///
+/// Local items of different sources may have differently encoded URLs for the same path.
+fn target(recent: &Recent) -> Result<PathBuf, Url> {
+    recent.file_path().map_err(|_| recent.url().to_owned())
+}
+
+/// Fetch and merge the items of all the `sources`, see [merge_recents]
+///
+/// Each item is tagged with the index of its source, and items already provided by a previous source are skipped,
+/// so that the histories of the flavors take precedence over the other sources.
+pub fn fetch_recents(sources: &[Box<dyn RecentSource + '_>]) -> Result<Vec<(usize, Recent)>> {
+    let mut seen = HashSet::new();
+    let mut lists = Vec::new();
+    for source in sources {
+        let list = source
+            .fetch()
+            .map_err(|e| e.context(format!("Could not fetch the items of {}", source.name())))?;
+        let targets: Vec<_> = list.iter().map(target).collect();
+        lists.push(
+            list.into_iter()
+                .zip(&targets)
+                .filter(|(_, t)| !seen.contains(*t))
+                .map(|(r, _)| r)
+                .collect(),
+        );
+        seen.extend(targets);
+    }
+    Ok(merge_recents(lists))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Result;
+    use crate::vscode::workspaces::Recent;
+
+    use super::{fetch_recents, RecentSource};
+
+    #[derive(Debug)]
+    struct Fixed(&'static str, &'static [&'static str]);
+
+    impl RecentSource for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn fetch(&self) -> Result<Vec<Recent>> {
+            self.1
+                .iter()
+                .map(|p| Recent::from_target(p, None))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn merge_sources() {
+        let sources: [Box<dyn RecentSource>; 2] = [
+            Box::new(Fixed("first", &["file:///a", "file:///b"])),
+            Box::new(Fixed("second", &["file:///b/", "file:///c"])),
+        ];
+        let merged: Vec<(usize, String)> = fetch_recents(&sources)
+            .expect("could not fetch")
+            .into_iter()
+            .map(|(i, r)| (i, r.url().path().to_string()))
+            .collect();
+        assert_eq!(
+            merged,
+            vec![(0, "/a".into()), (1, "/c".into()), (0, "/b".into())]
+        );
+
+        let url = "file:///a".parse().unwrap();
+        assert!(sources[0].delete(&url).is_err());
+        assert!(sources[0].clear().is_ok());
+    }
+}
@@ -0,0 +1,146 @@
+//! Repositories cloned on the machine
+//!
+//! The repositories are listed by [ghq](https://github.com/x-motemen/ghq) with `ghq list --full-path`,
+//! or found by scanning a root directory laid out like `GOPATH/src` or the ghq root,
+//! e.g. `~/src/github.com/dunder/paper`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::SystemTime,
+};
+
+use crate::error::{other, Context, Result};
+
+use super::RecentSource;
+use crate::{
+    git::git_dir,
+    vscode::workspaces::{Recent, RecentKind},
+};
+
+/// How deep repositories are looked for under the root, e.g. `host/owner/repo`
+const MAX_DEPTH: usize = 3;
+
+/// The repositories listed by ghq or found under a root directory
+#[derive(Debug, Clone, Default)]
+pub struct Repositories {
+    /// Directory to scan for repositories, instead of asking ghq
+    root: Option<PathBuf>,
+}
+
+impl Repositories {
+    /// List the repositories with ghq
+    pub fn ghq() -> Self {
+        Self { root: None }
+    }
+
+    /// Find the repositories under `root`
+    pub fn under(root: PathBuf) -> Self {
+        Self { root: Some(root) }
+    }
+}
+
+impl RecentSource for Repositories {
+    fn name(&self) -> &str {
+        "repos"
+    }
+
+    /// Get the repositories as folders, the most recently cloned or updated first
+    ///
+    /// # Errors
+    /// The call fails if `ghq` cannot be executed, or if the root cannot be read.
+    fn fetch(&self) -> Result<Vec<Recent>> {
+        let mut repos = match &self.root {
+            Some(root) => {
+                let mut repos = Vec::new();
+                scan(root, MAX_DEPTH, &mut repos)
+                    .with_context(|| format!("Could not scan {:?} for repositories", root))?;
+                repos
+            }
+            None => ghq_list()?,
+        };
+        // The git directory is touched by fetches and commits
+        repos.sort_by_cached_key(|p| std::cmp::Reverse(last_modified(p)));
+        Ok(repos
+            .into_iter()
+            .filter_map(|p| {
+                Recent::from_target(&p.to_string_lossy(), Some(RecentKind::Folder)).ok()
+            })
+            .collect())
+    }
+}
+
+/// List the full paths of the repositories managed by ghq
+fn ghq_list() -> Result<Vec<PathBuf>> {
+    let output = Command::new("ghq")
+        .args(["list", "--full-path"])
+        .output()
+        .context("Could not execute ghq, is it installed?")?;
+    if !output.status.success() {
+        return Err(other!(
+            "Could not list ghq repositories: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Collect the repositories in `dir` and its subdirectories, up to `depth` levels down
+///
+/// The subdirectories of a repository and the hidden directories are not scanned.
+fn scan(dir: &Path, depth: usize, repos: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if depth == 0 {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if hidden || !path.is_dir() {
+            continue;
+        }
+        if git_dir(&path).is_some() {
+            repos.push(path);
+        } else {
+            // Unreadable subdirectories are skipped rather than failing the whole scan
+            let _ = scan(&path, depth - 1, repos);
+        }
+    }
+    Ok(())
+}
+
+/// When the git directory of the repository `repo` was last modified
+fn last_modified(repo: &Path) -> Option<SystemTime> {
+    git_dir(repo)?.metadata().ok()?.modified().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::{scan, MAX_DEPTH};
+
+    #[test]
+    fn scan_root() {
+        let root = env::temp_dir().join(format!("rofi-vscode-mode-repos-{}", process::id()));
+        let repo = root.join("github.com").join("dunder").join("paper");
+        let nested = repo.join("vendor").join("lib");
+        let too_deep = root.join("a").join("b").join("c").join("d");
+        for dir in [&repo, &nested, &too_deep] {
+            fs::create_dir_all(dir.join(".git")).expect("could not create directory");
+        }
+
+        let mut repos = Vec::new();
+        scan(&root, MAX_DEPTH, &mut repos).expect("could not scan");
+        assert_eq!(repos, vec![repo]);
+
+        fs::remove_dir_all(&root).expect("could not clean up");
+    }
+}
@@ -0,0 +1,222 @@
+//! Files recently used with any application
+//!
+//! Desktop applications record the files they open in `$XDG_DATA_HOME/recently-used.xbel`,
+//! following the [Desktop Bookmark Specification](https://www.freedesktop.org/wiki/Specifications/desktop-bookmark-spec/).
+//! Only the few elements needed here are read, without a full XML parser.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use url::Url;
+
+use super::RecentSource;
+use crate::error::{Context, Result};
+use crate::vscode::{
+    file_url_to_path,
+    workspaces::{Recent, RecentKind},
+};
+
+/// MIME types of source code that do not start with `text/`
+const CODE_MIME_TYPES: &[&str] = &[
+    "application/json",
+    "application/javascript",
+    "application/toml",
+    "application/xml",
+    "application/sql",
+    "application/x-shellscript",
+    "application/x-yaml",
+    "application/x-php",
+    "application/x-perl",
+    "application/x-ruby",
+    "application/x-desktop",
+];
+
+/// Extensions of source code files, for the ones recorded with a generic MIME type
+const CODE_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cpp", "cs", "css", "go", "h", "hpp", "hs", "html", "java", "js", "json", "jsx",
+    "kt", "lua", "md", "nix", "php", "py", "rb", "rs", "scss", "sh", "sql", "swift", "toml", "ts",
+    "tsx", "txt", "vue", "xml", "yaml", "yml", "zig",
+];
+
+/// A file recorded in the bookmark file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bookmark {
+    /// URI of the file
+    href: String,
+    /// MIME type of the file, if recorded
+    mime_type: Option<String>,
+    /// When the file was last used, as an ISO 8601 timestamp
+    time: String,
+}
+
+/// The source code files recently used with any application
+#[derive(Debug, Clone)]
+pub struct RecentlyUsedFiles {
+    /// Path of the bookmark file
+    path: PathBuf,
+}
+
+impl RecentlyUsedFiles {
+    /// Read the files from the bookmark file at `path`
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Path of the bookmark file shared by desktop applications, which may not exist
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|d| d.join("recently-used.xbel"))
+    }
+}
+
+impl RecentSource for RecentlyUsedFiles {
+    fn name(&self) -> &str {
+        "recently used"
+    }
+
+    /// Get the existing source code files, the most recently used first
+    ///
+    /// A missing bookmark file has no files.
+    fn fetch(&self) -> Result<Vec<Recent>> {
+        let xml = match fs::read_to_string(&self.path) {
+            Ok(xml) => xml,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Could not read {:?}", self.path)),
+        };
+        let mut bookmarks = parse_bookmarks(&xml);
+        bookmarks.sort_by(|a, b| b.time.cmp(&a.time));
+        Ok(bookmarks
+            .into_iter()
+            .filter_map(|b| {
+                let url = Url::parse(&b.href).ok()?;
+                let path = file_url_to_path(&url)?;
+                if !path.is_file() || !is_code(b.mime_type.as_deref(), &path) {
+                    return None;
+                }
+                Recent::from_target(url.as_str(), Some(RecentKind::File)).ok()
+            })
+            .collect())
+    }
+}
+
+/// Whether the file at `path` with the given MIME type looks like source code
+fn is_code(mime_type: Option<&str>, path: &Path) -> bool {
+    let code_mime_type =
+        mime_type.is_some_and(|m| m.starts_with("text/") || CODE_MIME_TYPES.contains(&m));
+    let code_extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| CODE_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    code_mime_type || code_extension
+}
+
+/// Find the bookmarks in the contents of a bookmark file
+///
+/// Malformed bookmarks are skipped.
+fn parse_bookmarks(xml: &str) -> Vec<Bookmark> {
+    let mut bookmarks = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find("<bookmark ") {
+        rest = &rest[start..];
+        let Some(tag_end) = rest.find('>') else {
+            break;
+        };
+        let tag = &rest[..tag_end];
+        // The body holds the metadata, unless the element is empty
+        let body = match tag.ends_with('/') {
+            true => "",
+            false => &rest[tag_end..rest.find("</bookmark>").unwrap_or(rest.len())],
+        };
+        rest = &rest[tag_end..];
+
+        let Some(href) = attribute(tag, "href") else {
+            continue;
+        };
+        let time = ["visited", "modified", "added"]
+            .iter()
+            .filter_map(|a| attribute(tag, a))
+            .max()
+            .unwrap_or_default();
+        let mime_type = body
+            .find("mime-type ")
+            .and_then(|i| attribute(&body[i..], "type"));
+        bookmarks.push(Bookmark {
+            href,
+            mime_type,
+            time,
+        });
+    }
+    bookmarks
+}
+
+/// Value of the attribute `name` in the start tag `tag`, with its entities replaced
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let prefix = format!(" {}=\"", name);
+    let start = tag.find(&prefix)? + prefix.len();
+    let len = tag[start..].find('"')?;
+    Some(unescape(&tag[start..start + len]))
+}
+
+/// Replace the predefined XML entities
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{is_code, parse_bookmarks, Bookmark};
+
+    #[test]
+    fn parse_xbel() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0" xmlns:bookmark="http://www.freedesktop.org/standards/desktop-bookmarks" xmlns:mime="http://www.freedesktop.org/standards/shared-mime-info">
+  <bookmark href="file:///home/dwight/beets/harvest.py" added="2024-03-01T08:00:00.000000Z" modified="2024-03-02T08:00:00.000000Z" visited="2024-03-01T08:00:00.000000Z">
+    <info>
+      <metadata owner="http://freedesktop.org">
+        <mime:mime-type type="text/x-python"/>
+        <bookmark:applications>
+          <bookmark:application name="gedit" exec="&apos;gedit %u&apos;" modified="2024-03-02T08:00:00.000000Z" count="2"/>
+        </bookmark:applications>
+      </metadata>
+    </info>
+  </bookmark>
+  <bookmark href="file:///home/dwight/Salt%20&amp;%20Pepper.pdf" added="2024-02-01T08:00:00.000000Z"/>
+</xbel>"#;
+        assert_eq!(
+            parse_bookmarks(xml),
+            vec![
+                Bookmark {
+                    href: "file:///home/dwight/beets/harvest.py".to_string(),
+                    mime_type: Some("text/x-python".to_string()),
+                    time: "2024-03-02T08:00:00.000000Z".to_string(),
+                },
+                Bookmark {
+                    href: "file:///home/dwight/Salt%20&%20Pepper.pdf".to_string(),
+                    mime_type: None,
+                    time: "2024-02-01T08:00:00.000000Z".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn code_files() {
+        assert!(is_code(Some("text/x-python"), Path::new("harvest.py")));
+        assert!(is_code(Some("application/json"), Path::new("beets.json")));
+        assert!(is_code(
+            Some("application/octet-stream"),
+            Path::new("main.rs")
+        ));
+        assert!(is_code(None, Path::new("Cargo.TOML")));
+        assert!(!is_code(Some("application/pdf"), Path::new("paper.pdf")));
+        assert!(!is_code(None, Path::new("photo")));
+    }
+}
@@ -0,0 +1,78 @@
+//! Directories ranked by [zoxide](https://github.com/ajeetdsouza/zoxide)
+//!
+//! zoxide ranks the directories by how frequently and recently they are visited from the shell.
+//! Its database is private to it, so the ranking is read from `zoxide query --list`.
+
+use std::{path::PathBuf, process::Command};
+
+use crate::error::{other, Context, Result};
+
+use super::RecentSource;
+use crate::vscode::workspaces::{Recent, RecentKind};
+
+/// Number of the highest-ranked directories that are shown
+const MAX_DIRECTORIES: usize = 50;
+
+/// The highest-ranked directories of zoxide
+#[derive(Debug, Clone, Default)]
+pub struct ZoxideDirectories;
+
+impl RecentSource for ZoxideDirectories {
+    fn name(&self) -> &str {
+        "zoxide"
+    }
+
+    /// Get the highest-ranked existing directories, as folders
+    ///
+    /// # Errors
+    /// The call fails if `zoxide` cannot be executed.
+    fn fetch(&self) -> Result<Vec<Recent>> {
+        let output = Command::new("zoxide")
+            .args(["query", "--list"])
+            .output()
+            .context("Could not execute zoxide, is it installed?")?;
+        if !output.status.success() {
+            return Err(other!(
+                "Could not list zoxide directories: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(parse_list(&String::from_utf8_lossy(&output.stdout))
+            .into_iter()
+            .filter(|p| p.is_dir())
+            .take(MAX_DIRECTORIES)
+            .filter_map(|p| {
+                Recent::from_target(&p.to_string_lossy(), Some(RecentKind::Folder)).ok()
+            })
+            .collect())
+    }
+}
+
+/// Parse the directories listed by `zoxide query --list`, one per line with the highest-ranked first
+fn parse_list(output: &str) -> Vec<PathBuf> {
+    output
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::parse_list;
+
+    #[test]
+    fn parse_query_list() {
+        let output = "/home/dwight/beet-farm\n/home/dwight/bed and breakfast\n\n";
+        assert_eq!(
+            parse_list(output),
+            vec![
+                PathBuf::from("/home/dwight/beet-farm"),
+                PathBuf::from("/home/dwight/bed and breakfast"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,281 @@
+//! SSH configuration utilities
+//!
+//! The hosts declared in the user's `~/.ssh/config` are the ones that VSCode's
+//! [Remote - SSH](https://code.visualstudio.com/docs/remote/ssh) extension offers to connect to.
+
+use super::utils::matches_wildcard;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::error::{other, Context, Result};
+
+/// Maximum nesting of `Include` directives, as in OpenSSH
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A host declared in the SSH configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshHost {
+    /// Alias of the host, as given in the `Host` directive
+    pub alias: String,
+    /// Real host name to connect to, if different from the alias
+    pub hostname: Option<String>,
+    /// User to log in as
+    pub user: Option<String>,
+}
+
+impl SshHost {
+    fn new(alias: &str) -> Self {
+        SshHost {
+            alias: alias.to_string(),
+            hostname: None,
+            user: None,
+        }
+    }
+
+    /// The remote authority used by VSCode to connect to this host
+    pub fn remote_authority(&self) -> String {
+        format!("ssh-remote+{}", self.alias)
+    }
+}
+
+/// Path of the user's SSH directory
+fn ssh_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh"))
+}
+
+/// Get the hosts declared in the user's `~/.ssh/config`
+///
+/// Hosts declared with patterns (e.g. `Host *.example.com`) are not included,
+/// since there is no way to connect to them without knowing the actual name.
+///
+/// # Errors
+/// The call fails if the home directory cannot be determined or the configuration cannot be read.
+pub fn hosts_from_config() -> Result<Vec<SshHost>> {
+    let ssh_dir = ssh_dir().ok_or_else(|| other!("Could not determine the home directory"))?;
+    let mut hosts = Vec::new();
+    parse_config_file(&ssh_dir.join("config"), &ssh_dir, &mut hosts, 0)?;
+    Ok(hosts)
+}
+
+/// Parse the configuration file at `path`, appending the hosts to `hosts`
+fn parse_config_file(
+    path: &Path,
+    ssh_dir: &Path,
+    hosts: &mut Vec<SshHost>,
+    depth: usize,
+) -> Result<()> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Could not read SSH configuration {:?}", path))?;
+    parse_config(&contents, ssh_dir, hosts, depth)
+}
+
+/// Parse the contents of a configuration file, appending the hosts to `hosts`
+///
+/// Files referenced by `Include` directives are parsed recursively,
+/// resolving relative paths against `ssh_dir`.
+/// A host declared again, in another block or file, is listed once;
+/// as in OpenSSH, the first value given to each setting wins.
+fn parse_config(
+    contents: &str,
+    ssh_dir: &Path,
+    hosts: &mut Vec<SshHost>,
+    depth: usize,
+) -> Result<()> {
+    // Indices of the hosts declared by the current `Host` block
+    let mut block: Vec<usize> = Vec::new();
+
+    for line in contents.lines() {
+        let Some((keyword, args)) = split_directive(line) else {
+            continue;
+        };
+        match keyword.to_lowercase().as_str() {
+            "host" => {
+                block.clear();
+                for alias in args
+                    .split_whitespace()
+                    .filter(|p| !p.contains(['*', '?', '!']))
+                {
+                    let index = match hosts.iter().position(|h| h.alias == alias) {
+                        Some(index) => index,
+                        None => {
+                            hosts.push(SshHost::new(alias));
+                            hosts.len() - 1
+                        }
+                    };
+                    if !block.contains(&index) {
+                        block.push(index);
+                    }
+                }
+            }
+            "match" => block.clear(),
+            "hostname" => {
+                for &i in &block {
+                    hosts[i].hostname.get_or_insert_with(|| args.to_string());
+                }
+            }
+            "user" => {
+                for &i in &block {
+                    hosts[i].user.get_or_insert_with(|| args.to_string());
+                }
+            }
+            "include" if depth < MAX_INCLUDE_DEPTH => {
+                for pattern in args.split_whitespace() {
+                    for path in expand_include(pattern, ssh_dir) {
+                        // Missing or unreadable included files are ignored, as OpenSSH does
+                        let _ = parse_config_file(&path, ssh_dir, hosts, depth + 1);
+                    }
+                }
+                // An included file may end in the middle of a block
+                block.clear();
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Split a configuration line into keyword and arguments
+///
+/// Returns `None` for empty lines and comments.
+fn split_directive(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (keyword, args) = line
+        .split_once(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or((line, ""));
+    let args = args.trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+    Some((keyword, args.trim_matches('"')))
+}
+
+/// Resolve the files matched by an `Include` pattern
+///
+/// Wildcards are supported only in the file name.
+fn expand_include(pattern: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = match pattern.strip_prefix("~/") {
+        Some(rel) => match dirs::home_dir() {
+            Some(home) => home.join(rel),
+            None => return Vec::new(),
+        },
+        None => ssh_dir.join(pattern),
+    };
+
+    let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Vec::new();
+    };
+    if !file_name.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| matches_wildcard(file_name, n))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{parse_config, SshHost};
+
+    #[test]
+    fn parse_hosts() {
+        let config = "
+# Paper company
+Host dunder-mifflin scranton
+    HostName 10.0.0.1
+    User michael
+
+Host *.sabre.com !corporate
+    User jo
+
+Host=vance-refrigeration
+    HostName = vance.example.com
+
+Match host stamford
+    User karen
+";
+        let mut hosts = Vec::new();
+        parse_config(config, Path::new("/nonexistent"), &mut hosts, 0).expect("could not parse");
+
+        assert_eq!(
+            hosts,
+            vec![
+                SshHost {
+                    alias: "dunder-mifflin".to_string(),
+                    hostname: Some("10.0.0.1".to_string()),
+                    user: Some("michael".to_string()),
+                },
+                SshHost {
+                    alias: "scranton".to_string(),
+                    hostname: Some("10.0.0.1".to_string()),
+                    user: Some("michael".to_string()),
+                },
+                SshHost {
+                    alias: "vance-refrigeration".to_string(),
+                    hostname: Some("vance.example.com".to_string()),
+                    user: None,
+                },
+            ]
+        );
+        assert_eq!(hosts[0].remote_authority(), "ssh-remote+dunder-mifflin");
+    }
+
+    #[test]
+    fn merge_repeated_hosts() {
+        let dir = std::env::temp_dir().join(format!("rofi-vscode-mode-ssh-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("could not create directory");
+        std::fs::write(
+            dir.join("office"),
+            "Host scranton\n    HostName 10.0.0.2\n    User dwight\n",
+        )
+        .expect("could not write included file");
+        let config = "
+Host scranton scranton
+    User michael
+
+Include office
+
+Host stamford scranton
+    HostName 10.0.0.3
+";
+        let mut hosts = Vec::new();
+        parse_config(config, &dir, &mut hosts, 0).expect("could not parse");
+
+        assert_eq!(
+            hosts,
+            vec![
+                SshHost {
+                    alias: "scranton".to_string(),
+                    hostname: Some("10.0.0.2".to_string()),
+                    user: Some("michael".to_string()),
+                },
+                SshHost {
+                    alias: "stamford".to_string(),
+                    hostname: Some("10.0.0.3".to_string()),
+                    user: None,
+                },
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).expect("could not remove directory");
+    }
+}
@@ -0,0 +1,308 @@
+//! Usage statistics
+//!
+//! Every item opened through the plugin or the command line is recorded with the time and the flavor,
+//! one JSON object per line in `$XDG_STATE_HOME/rofi-vscode-mode/usage.jsonl`.
+//! Unlike the history of VSCode, which only keeps the order of the items, this tells how often each one is used.
+//! The records are summed up by [UsageStats], and the oldest ones are dropped when the file grows too large.
+//! They back the `most-used` sort order, which ranks the items by how often and how recently they were opened.
+//!
+//! Recording is disabled with `ROFI_VSCODE_NO_USAGE=true`.
+
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::config;
+use crate::error::{other, Context, Result};
+use crate::logging::debug;
+use crate::vscode::workspaces::Recent;
+
+const ENV_NO_USAGE: &str = "ROFI_VSCODE_NO_USAGE";
+
+/// Name of the file holding the records, inside the state directory
+const USAGE_FILE: &str = "usage.jsonl";
+/// Size of the file beyond which the oldest half of the records are dropped
+const MAX_USAGE_FILE_SIZE: u64 = 1024 * 1024;
+/// Age, in seconds, at which an opening weighs half as much as one made now
+const HALF_LIFE: f64 = 14.0 * 24.0 * 60.0 * 60.0;
+
+/// The current time, in seconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// An item opened at some time
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+    /// When the item was opened, in seconds since the Unix epoch
+    pub time: u64,
+    /// URL of the item
+    pub url: Url,
+    /// Name of the flavor that opened it
+    pub flavor: String,
+}
+
+impl Usage {
+    /// The opening of `recent` with the flavor named `flavor`, now
+    pub fn now(recent: &Recent, flavor: &str) -> Self {
+        Usage {
+            time: now(),
+            url: recent.url().to_owned(),
+            flavor: flavor.to_string(),
+        }
+    }
+}
+
+/// How an item was used
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemStats {
+    /// When the item was opened, in seconds since the Unix epoch, from the oldest
+    pub times: Vec<u64>,
+}
+
+impl ItemStats {
+    /// How many times the item was opened
+    pub fn count(&self) -> usize {
+        self.times.len()
+    }
+
+    /// When the item was last opened, in seconds since the Unix epoch
+    pub fn last_used(&self) -> Option<u64> {
+        self.times.iter().max().copied()
+    }
+
+    /// Score of the item at time `now`, each opening weighing half as much every two weeks
+    pub fn frecency(&self, now: u64) -> f64 {
+        self.times
+            .iter()
+            .map(|&t| 0.5_f64.powf(now.saturating_sub(t) as f64 / HALF_LIFE))
+            .sum()
+    }
+}
+
+/// The usage of every recorded item, by URL
+#[derive(Debug, Clone, Default)]
+pub struct UsageStats(HashMap<Url, ItemStats>);
+
+impl UsageStats {
+    /// Sum up the records
+    pub fn from_usages(usages: impl IntoIterator<Item = Usage>) -> Self {
+        let mut stats: HashMap<Url, ItemStats> = HashMap::new();
+        for usage in usages {
+            stats.entry(usage.url).or_default().times.push(usage.time);
+        }
+        for item in stats.values_mut() {
+            item.times.sort_unstable();
+        }
+        UsageStats(stats)
+    }
+
+    /// Read and sum up the records of the usage file
+    ///
+    /// # Errors
+    /// The call fails if the file exists but cannot be read.
+    pub fn read() -> Result<Self> {
+        match usage_file() {
+            Some(path) => read_usage_file(&path).map(Self::from_usages),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// How the item with the given URL was used, if it was opened at all
+    pub fn get(&self, url: &Url) -> Option<&ItemStats> {
+        self.0.get(url)
+    }
+
+    /// Sort the entries from the most used now, see [ItemStats::frecency]
+    ///
+    /// Entries that were never opened come last, in their original order.
+    pub fn rank<T>(&self, entries: &mut [(T, Recent)]) {
+        self.rank_at(entries, now())
+    }
+
+    /// Sort the entries from the most used at time `now`
+    fn rank_at<T>(&self, entries: &mut [(T, Recent)], now: u64) {
+        let score = |r: &Recent| self.get(r.url()).map_or(0.0, |s| s.frecency(now));
+        entries.sort_by(|(_, a), (_, b)| score(b).total_cmp(&score(a)));
+    }
+
+    /// The recorded items, from the most opened one
+    pub fn most_used(&self) -> Vec<(&Url, &ItemStats)> {
+        let mut items: Vec<_> = self.0.iter().collect();
+        items.sort_by(|(_, a), (_, b)| {
+            b.count()
+                .cmp(&a.count())
+                .then(b.last_used().cmp(&a.last_used()))
+        });
+        items
+    }
+}
+
+/// Path of the usage file, if the state directory can be determined
+pub fn usage_file() -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("rofi-vscode-mode").join(USAGE_FILE))
+}
+
+/// Record that `recent` was opened with the flavor named `flavor`, unless recording is disabled
+///
+/// # Errors
+/// The call fails if the usage file cannot be written.
+pub fn record_open(recent: &Recent, flavor: &str) -> Result<()> {
+    if config::flag(ENV_NO_USAGE) {
+        return Ok(());
+    }
+    let path =
+        usage_file().ok_or_else(|| other!("Could not find a directory to record the usage"))?;
+    append_usage(&path, &Usage::now(recent, flavor))
+}
+
+/// Append a record to the usage file at `path`, trimming it if it has grown too large
+fn append_usage(path: &Path, usage: &Usage) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Could not create {:?}", dir))?;
+    }
+    if fs::metadata(path).is_ok_and(|m| m.len() > MAX_USAGE_FILE_SIZE) {
+        trim_usage_file(path)?;
+    }
+    let mut line = serde_json::to_string(usage)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Could not record the usage in {:?}", path))
+}
+
+/// Drop the oldest half of the records of the usage file at `path`
+fn trim_usage_file(path: &Path) -> Result<()> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Could not read the usage file {:?}", path))?;
+    let lines: Vec<&str> = contents.lines().collect();
+    let kept = lines[lines.len() / 2..].join("\n") + "\n";
+    debug!("Dropping {} records from {:?}", lines.len() / 2, path);
+    fs::write(path, kept).with_context(|| format!("Could not trim the usage file {:?}", path))
+}
+
+/// Read the records of the usage file at `path`, which may not exist
+///
+/// Lines that cannot be parsed are skipped.
+fn read_usage_file(path: &Path) -> Result<Vec<Usage>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Could not read the usage file {:?}", path))
+        }
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use serde_json::json;
+    use url::Url;
+
+    use super::{append_usage, read_usage_file, Usage, UsageStats, HALF_LIFE};
+    use crate::vscode::workspaces::Recent;
+
+    #[test]
+    fn record_usage() {
+        let path = env::temp_dir().join(format!("rofi-vscode-mode-usage-{}.jsonl", process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(read_usage_file(&path).unwrap(), Vec::new());
+
+        let usage = |time: u64, url: &str| Usage {
+            time,
+            url: Url::parse(url).unwrap(),
+            flavor: "VSCodium".to_string(),
+        };
+        for u in [
+            usage(100, "file:///srv/beet-farm"),
+            usage(200, "file:///srv/paper"),
+            usage(300, "file:///srv/beet-farm"),
+        ] {
+            append_usage(&path, &u).unwrap();
+        }
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "not a record\n").unwrap();
+
+        let usages = read_usage_file(&path).unwrap();
+        assert_eq!(usages.len(), 3);
+        let stats = UsageStats::from_usages(usages);
+        let farm = stats
+            .get(&Url::parse("file:///srv/beet-farm").unwrap())
+            .unwrap();
+        assert_eq!(farm.count(), 2);
+        assert_eq!(farm.last_used(), Some(300));
+        let most_used: Vec<&str> = stats.most_used().iter().map(|(u, _)| u.path()).collect();
+        assert_eq!(most_used, vec!["/srv/beet-farm", "/srv/paper"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rank_most_used() {
+        let week = HALF_LIFE as u64 / 2;
+        let now = 100 * week;
+        let usage = |time: u64, url: &str| Usage {
+            time,
+            url: Url::parse(url).unwrap(),
+            flavor: "Code".to_string(),
+        };
+        // Opened three times last year, twice this week and once today
+        let stats = UsageStats::from_usages([
+            usage(now - 52 * week, "file:///srv/paper"),
+            usage(now - 52 * week, "file:///srv/paper"),
+            usage(now - 51 * week, "file:///srv/paper"),
+            usage(now - week / 2, "file:///srv/beet-farm"),
+            usage(now - week / 3, "file:///srv/beet-farm"),
+            usage(now, "file:///srv/dunder"),
+        ]);
+        assert_eq!(
+            stats
+                .get(&Url::parse("file:///srv/dunder").unwrap())
+                .unwrap()
+                .frecency(now),
+            1.0
+        );
+
+        let recents: Vec<Recent> = serde_json::from_value(json!([
+            { "folderUri": "file:///srv/paper" },
+            { "folderUri": "file:///srv/scranton" },
+            { "folderUri": "file:///srv/dunder" },
+            { "folderUri": "file:///srv/stamford" },
+            { "folderUri": "file:///srv/beet-farm" },
+        ]))
+        .unwrap();
+        let mut entries: Vec<(usize, Recent)> = recents.into_iter().map(|r| (0, r)).collect();
+        stats.rank_at(&mut entries, now);
+        let paths: Vec<&str> = entries.iter().map(|(_, r)| r.url().path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/srv/beet-farm",
+                "/srv/dunder",
+                "/srv/paper",
+                "/srv/scranton",
+                "/srv/stamford"
+            ]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Remote Tunnels utilities
+//!
+//! With [Remote Tunnels](https://code.visualstudio.com/docs/remote/tunnels), a machine running `code tunnel`
+//! can be reached from anywhere under the name it registered, as the remote authority `tunnel+{name}`.
+//! The tunnels are found from `code tunnel status`, which reports the one of this machine,
+//! and from the recent items that were opened through a tunnel.
+
+use serde_json::Value;
+
+use crate::error::{other, Context, Result};
+use crate::logging::debug;
+use crate::vscode::{workspaces::Recent, EditorFlavor};
+
+/// Kind of the remote authorities of the tunnels
+const TUNNEL_KIND: &str = "tunnel";
+
+/// A machine reachable through a tunnel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tunnel {
+    /// Name under which the machine is registered
+    pub name: String,
+    /// Whether the tunnel is connected, known only for the tunnel of this machine
+    pub connected: Option<bool>,
+}
+
+impl Tunnel {
+    fn new(name: &str) -> Self {
+        Tunnel {
+            name: name.to_string(),
+            connected: None,
+        }
+    }
+
+    /// The remote authority used by VSCode to connect through this tunnel
+    pub fn remote_authority(&self) -> String {
+        format!("{}+{}", TUNNEL_KIND, self.name)
+    }
+
+    /// The URI opening `path` on the machine, e.g. `vscode-remote://tunnel+devbox/srv/beet-farm`
+    pub fn url(&self, path: &str) -> String {
+        format!(
+            "vscode-remote://{}/{}",
+            self.remote_authority(),
+            path.trim_start_matches('/')
+        )
+    }
+
+    /// Open a new window connected to the machine, opening `path` on it if given
+    ///
+    /// # Errors
+    /// The call fails if [EditorFlavor::cmd()] cannot be executed.
+    pub fn open(&self, flavor: &dyn EditorFlavor, path: Option<&str>) -> Result<()> {
+        match path {
+            Some(path) => flavor
+                .open_command()
+                .arg("--folder-uri")
+                .arg(self.url(path))
+                .spawn()
+                .map(|_| ())
+                .with_context(|| format!("Could not execute {}", flavor.cmd())),
+            None => flavor.open_remote(&self.remote_authority(), None),
+        }
+    }
+}
+
+/// Parse the output of `code tunnel status`
+///
+/// It is JSON with the `tunnel` of this machine, which is null when none was started.
+fn parse_tunnel_status(output: &str) -> Result<Option<Tunnel>> {
+    let status: Value =
+        serde_json::from_str(output).context("Could not parse the tunnel status")?;
+    let Some(name) = status.pointer("/tunnel/name").and_then(Value::as_str) else {
+        return Ok(None);
+    };
+    let connected = status
+        .pointer("/tunnel/tunnel")
+        .and_then(Value::as_str)
+        .is_some_and(|s| s.eq_ignore_ascii_case("connected"));
+    Ok(Some(Tunnel {
+        name: name.to_string(),
+        connected: Some(connected),
+    }))
+}
+
+/// Get the tunnel of this machine, if one was started
+///
+/// # Errors
+/// The call fails if [EditorFlavor::cmd()] cannot be executed, reports an error or prints an unexpected output,
+/// e.g. for flavors without tunnels.
+pub fn tunnel_status(flavor: &dyn EditorFlavor) -> Result<Option<Tunnel>> {
+    let output = flavor
+        .command()
+        .args(["tunnel", "status"])
+        .output()
+        .with_context(|| format!("Could not execute {}", flavor.cmd()))?;
+    if !output.status.success() {
+        return Err(other!(
+            "Could not get the tunnel status: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    parse_tunnel_status(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Get the tunnels that the recent items were opened through, each once and by recency
+pub fn tunnels_from_recent(recents: &[Recent]) -> Vec<Tunnel> {
+    let mut tunnels: Vec<Tunnel> = Vec::new();
+    let names = recents
+        .iter()
+        .filter_map(Recent::remote)
+        .filter_map(|a| a.strip_prefix(TUNNEL_KIND)?.strip_prefix('+'));
+    for name in names {
+        if !tunnels.iter().any(|t| t.name == name) {
+            tunnels.push(Tunnel::new(name));
+        }
+    }
+    tunnels
+}
+
+/// Get the tunnel of this machine, if any, followed by the ones of the recent items
+///
+/// Unlike [tunnel_status], the call does not fail if the tunnel status cannot be obtained.
+pub fn list_tunnels(flavor: &dyn EditorFlavor, recents: &[Recent]) -> Vec<Tunnel> {
+    let mut tunnels: Vec<Tunnel> = match tunnel_status(flavor) {
+        Ok(status) => status.into_iter().collect(),
+        Err(e) => {
+            debug!("{:?}", e);
+            Vec::new()
+        }
+    };
+    for tunnel in tunnels_from_recent(recents) {
+        if !tunnels.iter().any(|t| t.name == tunnel.name) {
+            tunnels.push(tunnel);
+        }
+    }
+    tunnels
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{parse_tunnel_status, tunnels_from_recent, Tunnel};
+    use crate::vscode::workspaces::Recent;
+
+    #[test]
+    fn parse_status() {
+        let output = r#"{"tunnel":{"name":"beet-farm","started_at":"2026-10-15T08:00:00Z","tunnel":"Connected"},"service_installed":false}"#;
+        let tunnel = parse_tunnel_status(output).unwrap().unwrap();
+        assert_eq!(tunnel.name, "beet-farm");
+        assert_eq!(tunnel.connected, Some(true));
+        assert_eq!(tunnel.remote_authority(), "tunnel+beet-farm");
+        assert_eq!(
+            tunnel.url("/srv/app"),
+            "vscode-remote://tunnel+beet-farm/srv/app"
+        );
+
+        let output = r#"{"tunnel":null,"service_installed":false}"#;
+        assert_eq!(parse_tunnel_status(output).unwrap(), None);
+        assert!(parse_tunnel_status("not json").is_err());
+    }
+
+    #[test]
+    fn tunnels_of_recent_items() {
+        let recents: Vec<Recent> = serde_json::from_value(json!([
+            { "folderUri": "vscode-remote://tunnel+scranton/home/michael", "remoteAuthority": "tunnel+scranton" },
+            { "folderUri": "vscode-remote://ssh-remote+devbox/srv", "remoteAuthority": "ssh-remote+devbox" },
+            { "fileUri": "vscode-remote://tunnel+scranton/etc/hosts", "remoteAuthority": "tunnel+scranton" },
+            { "folderUri": "file:///srv/beet-farm" },
+        ]))
+        .unwrap();
+        assert_eq!(tunnels_from_recent(&recents), vec![Tunnel::new("scranton")]);
+    }
+}
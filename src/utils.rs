@@ -1,25 +1,1149 @@
 //! Common utilities
 
 const ENV_FLAVOR: &str = "ROFI_VSCODE_FLAVOR";
+const ENV_CMD: &str = "ROFI_VSCODE_CMD";
+const ENV_CONFIG_DIR: &str = "ROFI_VSCODE_CONFIG_DIR";
+const ENV_USER_DATA_DIR: &str = "ROFI_VSCODE_USER_DATA_DIR";
+const ENV_DB: &str = "ROFI_VSCODE_DB";
+const ENV_ARGS: &str = "ROFI_VSCODE_ARGS";
+const ENV_DEDUP: &str = "ROFI_VSCODE_DEDUP";
+const ENV_MAX_ENTRIES: &str = "ROFI_VSCODE_MAX_ENTRIES";
+const ENV_WITHIN: &str = "ROFI_VSCODE_WITHIN";
+const ENV_GROUP: &str = "ROFI_VSCODE_GROUP";
+const ENV_KINDS: &str = "ROFI_VSCODE_KINDS";
+const ENV_SORT: &str = "ROFI_VSCODE_SORT";
+const ENV_REMOTE: &str = "ROFI_VSCODE_REMOTE";
+const ENV_HIDE_VIRTUAL: &str = "ROFI_VSCODE_HIDE_VIRTUAL";
+const ENV_LOCAL_ONLY: &str = "ROFI_VSCODE_LOCAL_ONLY";
+const ENV_RESOLVE_SYMLINKS: &str = "ROFI_VSCODE_RESOLVE_SYMLINKS";
+const ENV_SOURCES: &str = "ROFI_VSCODE_SOURCES";
+const ENV_LABEL: &str = "ROFI_VSCODE_LABEL";
+const ENV_SHORTEN: &str = "ROFI_VSCODE_SHORTEN";
+const ENV_KEYS: &str = "ROFI_VSCODE_KEYS";
+const ENV_PROFILE: &str = "ROFI_VSCODE_PROFILE";
+const ENV_TERMINAL: &str = "ROFI_VSCODE_TERMINAL";
+const ENV_BROWSER_FALLBACK: &str = "ROFI_VSCODE_BROWSER_FALLBACK";
+const ENV_NOTIFY: &str = "ROFI_VSCODE_NOTIFY";
 
-use super::vscode::Flavor;
-use anyhow::anyhow;
-use std::{env, str::FromStr};
+/// Replaces the path components left out of shortened labels
+const ELLIPSIS: &str = "...";
+const ENV_REPOS_ROOT: &str = "ROFI_VSCODE_REPOS_ROOT";
+
+/// Value of `ROFI_VSCODE_FLAVOR` to use every installed flavor
+const ALL_FLAVORS: &str = "all";
+/// Value of `ROFI_VSCODE_PROFILE` to merge the recent items of every profile
+const ALL_PROFILES: &str = "all";
+
+/// Flavor the plugin was bound to at build time, which takes precedence over `ROFI_VSCODE_FLAVOR`
+const MODE_FLAVOR: Option<&str> = option_env!("ROFI_VSCODE_MODE_FLAVOR");
+
+use super::config;
+use super::error::{other, Context, Error, Result};
+use super::keys::{KeyAction, Keymap};
+use super::logging::{debug, span, warning};
+use super::notify::{notify, NotifyLevel, Urgency};
+use super::remote::web_fallback;
+use super::sources::{
+    repos::Repositories, xbel::RecentlyUsedFiles, zoxide::ZoxideDirectories, RecentSource,
+};
+use super::stats::record_open;
+use super::vscode::{
+    extensions::installed_extensions,
+    label::LabelTemplate,
+    profiles::{profile_state_db, profiles_from_storage},
+    server::Server,
+    untildify,
+    workspaces::{DedupPreference, Recent, RecentKind, RecentOptions, RecentSort, RemotePlacement},
+    CustomFlavor, EditorFlavor, Flavor, InProfile, Marketplace, WithArgs, WithUserDataDir,
+};
+use std::{
+    env, fs,
+    io::Write,
+    path::{Component, Path, PathBuf},
+    process::{Command, Stdio},
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+use url::Url;
+
+/// The flavor set at build time with `ROFI_VSCODE_MODE_FLAVOR`, or else the `ROFI_VSCODE_FLAVOR` setting
+fn flavor_setting() -> Option<String> {
+    match MODE_FLAVOR {
+        Some(flavor) if !flavor.is_empty() => Some(flavor.to_string()),
+        _ => config::var(ENV_FLAVOR),
+    }
+}
 
 /// Determine the VSCode flavor
 ///
-/// First it looks up the environment variable `ROFI_VSCODE_FLAVOR`.
-/// If it is not set, it tries to auto-detect the flavor
+/// First it looks up the `ROFI_VSCODE_FLAVOR` setting, unless a flavor was set at build time.
+/// If it is not set, or it is `all`, it tries to auto-detect the flavor,
+/// and then a [Server] installed by Remote SSH, e.g. in `~/.vscode-server/`.
+///
+/// The command, the configuration directory and the state database can be overridden with the
+/// `ROFI_VSCODE_CMD`, `ROFI_VSCODE_CONFIG_DIR` and `ROFI_VSCODE_DB` settings, resulting in a [CustomFlavor].
+/// The ones that are not set are taken from the flavor determined as above.
+/// The `ROFI_VSCODE_USER_DATA_DIR` setting also overrides the configuration directory,
+/// and is passed to the flavor as `--user-data-dir`, see [with_user_data_dir].
+///
+/// The flavor is passed the arguments of the `ROFI_VSCODE_ARGS` setting, see [with_extra_args].
+///
+/// # Errors
+/// The function fails if the setting contains an unrecognized value,
+/// or if the setting is not given and a suitable flavor cannot be detected.
+pub fn determine_vscode_flavor() -> Result<Box<dyn EditorFlavor>> {
+    determine_base_flavor().map(with_extra_args)
+}
+
+/// Determine the VSCode flavor as [determine_vscode_flavor], without extra arguments
+fn determine_base_flavor() -> Result<Box<dyn EditorFlavor>> {
+    let _span = span!("Determining the VSCode flavor");
+    let base = match flavor_setting() {
+        Some(val) if !val.eq_ignore_ascii_case(ALL_FLAVORS) => Some(Flavor::from_str(&val)?),
+        _ => Flavor::detect().copied(),
+    };
+    debug!("Base flavor: {:?}", base);
+    let not_found = || Error::FlavorNotFound;
+
+    let config_dir = config::var(ENV_CONFIG_DIR).or_else(|| config::var(ENV_USER_DATA_DIR));
+    let flavor: Box<dyn EditorFlavor> =
+        match (config::var(ENV_CMD), config_dir, config::var(ENV_DB)) {
+            // On a headless host, fall back to the state of a VSCode Server
+            (None, None, None) => match base {
+                Some(f) => Box::new(f),
+                None => Box::new(Server::detect().ok_or_else(not_found)?),
+            },
+            (cmd, config_dir, state_db) => {
+                let cmd = cmd
+                    .map(|c| untildify(&c).to_string_lossy().into_owned())
+                    .or_else(|| base.map(|f| f.cmd().to_string()))
+                    .ok_or_else(not_found)?;
+                let config_dir = config_dir
+                    .map(|d| untildify(&d))
+                    .or_else(|| base.and_then(|f| f.config_dir()));
+                let state_db = state_db.map(|d| untildify(&d));
+                if config_dir.is_none() && state_db.is_none() {
+                    return Err(not_found());
+                }
+                let marketplace = base
+                    .map(|f| f.marketplace())
+                    .unwrap_or(Marketplace::OpenVsx);
+                let flavor = CustomFlavor {
+                    cmd,
+                    config_dir,
+                    state_db,
+                    marketplace,
+                };
+                debug!("Custom flavor: {:?}", flavor);
+                Box::new(flavor)
+            }
+        };
+    Ok(with_user_data_dir(flavor))
+}
+
+/// Run `flavor` with the user data directory of the `ROFI_VSCODE_USER_DATA_DIR` setting, see [WithUserDataDir]
+///
+/// The flavor is returned as is if the setting is not given.
+pub fn with_user_data_dir(flavor: Box<dyn EditorFlavor>) -> Box<dyn EditorFlavor> {
+    match config::var(ENV_USER_DATA_DIR).filter(|d| !d.is_empty()) {
+        Some(dir) => {
+            let dir = untildify(&dir);
+            debug!("User data directory: {:?}", dir);
+            Box::new(WithUserDataDir { flavor, dir })
+        }
+        None => flavor,
+    }
+}
+
+/// Determine the VSCode flavors whose recent items are shown
+///
+/// If the `ROFI_VSCODE_FLAVOR` setting is `all`, every installed flavor is returned,
+/// otherwise the one given by [determine_vscode_flavor].
+/// The recent items of their profiles are then added or selected with the `ROFI_VSCODE_PROFILE` setting, see [InProfile].
+///
+/// # Errors
+/// The function fails if no flavor can be determined.
+pub fn determine_vscode_flavors() -> Result<Vec<Box<dyn EditorFlavor>>> {
+    let flavors = match flavor_setting() {
+        Some(val) if val.eq_ignore_ascii_case(ALL_FLAVORS) => {
+            let flavors: Vec<Box<dyn EditorFlavor>> = Flavor::installed()
+                .into_iter()
+                .map(|f| with_extra_args(Box::new(f)))
+                .collect();
+            if flavors.is_empty() {
+                Err(Error::FlavorNotFound)
+            } else {
+                Ok(flavors)
+            }
+        }
+        _ => determine_vscode_flavor().map(|f| vec![f]),
+    }?;
+    with_profiles(flavors)
+}
+
+/// Add or select the recent items of the profiles of the `flavors`, see [InProfile]
+///
+/// It looks up the `ROFI_VSCODE_PROFILE` setting: if it is `all`, the recent items of every profile
+/// follow the ones of each flavor, otherwise only the ones of the profile with this name are shown.
+/// Profiles that have no state database yet are skipped.
+/// If it is not set, only the recent items of the default profile are shown.
+///
+/// # Errors
+/// The function fails if no flavor has the selected profile.
+fn with_profiles(flavors: Vec<Box<dyn EditorFlavor>>) -> Result<Vec<Box<dyn EditorFlavor>>> {
+    let Some(setting) = config::var(ENV_PROFILE).filter(|v| !v.trim().is_empty()) else {
+        return Ok(flavors);
+    };
+    let all = setting.trim().eq_ignore_ascii_case(ALL_PROFILES);
+    let mut result: Vec<Box<dyn EditorFlavor>> = Vec::new();
+    for flavor in flavors {
+        let profiles = profiles_from_storage(flavor.as_ref()).unwrap_or_else(|e| {
+            debug!("No profiles for {}: {:?}", flavor.name(), e);
+            Vec::new()
+        });
+        let start = result.len();
+        for profile in profiles {
+            if !all && profile.name != setting.trim() {
+                continue;
+            }
+            let Some(config_dir) = flavor.config_dir() else {
+                break;
+            };
+            if !profile_state_db(&config_dir, &profile).exists() {
+                debug!("No state database for profile {}", profile.name);
+                continue;
+            }
+            let base = CustomFlavor {
+                cmd: flavor.cmd().to_string(),
+                config_dir: Some(config_dir),
+                state_db: None,
+                marketplace: flavor.marketplace(),
+            };
+            let name = flavor.name().to_string();
+            result.push(Box::new(InProfile::new(
+                with_extra_args(with_user_data_dir(Box::new(base))),
+                profile,
+                &name,
+            )));
+        }
+        // The default profile comes before the other ones
+        if all {
+            result.insert(start, flavor);
+        }
+    }
+    if result.is_empty() {
+        return Err(other!(
+            "No flavor has a profile named \"{}\"",
+            setting.trim()
+        ));
+    }
+    Ok(result)
+}
+
+/// Pass the arguments of the `ROFI_VSCODE_ARGS` setting to `flavor`, whenever it opens something
+///
+/// The flavor is returned as is if the setting is not given.
+pub fn with_extra_args(flavor: Box<dyn EditorFlavor>) -> Box<dyn EditorFlavor> {
+    match config::args(ENV_ARGS) {
+        Some(args) if !args.is_empty() => {
+            debug!("Extra arguments: {:?}", args);
+            Box::new(WithArgs { flavor, args })
+        }
+        _ => flavor,
+    }
+}
+
+/// Determine how entries pointing to the same directory are collapsed
+///
+/// It looks up the `ROFI_VSCODE_DEDUP` setting, which can be `workspace` or `folder`.
+/// If it is not set, the entries are not collapsed.
+///
+/// # Errors
+/// The function fails if the setting contains an unrecognized value.
+pub fn determine_dedup_preference() -> Result<Option<DedupPreference>> {
+    config::var(ENV_DEDUP)
+        .map(|val| DedupPreference::from_str(&val))
+        .transpose()
+}
+
+/// Determine the maximum number of recent items to show
+///
+/// It looks up the `ROFI_VSCODE_MAX_ENTRIES` setting. If it is not set, all the items are shown.
+///
+/// # Errors
+/// The function fails if the setting is not a non-negative number.
+pub fn determine_max_entries() -> Result<Option<usize>> {
+    config::var(ENV_MAX_ENTRIES)
+        .map(|val| {
+            val.trim()
+                .parse()
+                .with_context(|| format!("Invalid maximum number of entries {}", val))
+        })
+        .transpose()
+}
+
+/// Determine the length beyond which the labels of the given `mode` are shortened, see [shorten_path]
+///
+/// It looks up the `ROFI_VSCODE_SHORTEN_{MODE}` setting, e.g. `ROFI_VSCODE_SHORTEN_FILES` for `files`,
+/// then the `ROFI_VSCODE_SHORTEN` one. If neither is set, labels are shown in full.
+///
+/// # Errors
+/// The function fails if the setting is not a non-negative number.
+pub fn determine_shorten(mode: &str) -> Result<Option<usize>> {
+    config::var(&format!("{}_{}", ENV_SHORTEN, mode.to_uppercase()))
+        .or_else(|| config::var(ENV_SHORTEN))
+        .map(|val| {
+            val.trim()
+                .parse()
+                .with_context(|| format!("Invalid maximum label length {}", val))
+        })
+        .transpose()
+}
+
+/// The path of `path` relative to the `base` directory, e.g. `projects/app` under the home directory
+///
+/// The base itself is `.`, and paths outside of it are returned as they are.
+pub fn relative_path(path: &Path, base: &Path) -> String {
+    match path.strip_prefix(base) {
+        Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+        Ok(rel) => rel.to_string_lossy().into_owned(),
+        Err(_) => path.to_string_lossy().into_owned(),
+    }
+}
+
+/// Shorten `label` to about `max` characters by leaving out the middle components of its path,
+/// e.g. `~/work/.../service/api`
+///
+/// The first and the last components are always kept, then as many of the last and the first ones as fit,
+/// alternately. Labels that are short enough, or without components to leave out, are returned as is.
+pub fn shorten_path(label: &str, max: usize) -> String {
+    let len = |s: &str| s.chars().count();
+    if len(label) <= max {
+        return label.to_string();
+    }
+    let components: Vec<&str> = label.split('/').collect();
+    if components.len() <= 3 {
+        return label.to_string();
+    }
+    // Number of components kept at the start and at the end
+    let (mut head, mut tail) = (1, 1);
+    let mut width = len(components[0]) + len(ELLIPSIS) + len(components[components.len() - 1]) + 2;
+    loop {
+        let from_tail = tail <= head;
+        let next = if from_tail {
+            components[components.len() - 1 - tail]
+        } else {
+            components[head]
+        };
+        if head + tail + 1 >= components.len() || width + len(next) + 1 > max {
+            break;
+        }
+        width += len(next) + 1;
+        if from_tail {
+            tail += 1;
+        } else {
+            head += 1;
+        }
+    }
+    if head + tail >= components.len() {
+        return label.to_string();
+    }
+    let mut shortened = components[..head].to_vec();
+    shortened.push(ELLIPSIS);
+    shortened.extend(&components[components.len() - tail..]);
+    shortened.join("/")
+}
+
+/// Determine the directory that the recent items must be located in
+///
+/// It looks up the `ROFI_VSCODE_WITHIN` setting. If it is not set, items are shown wherever they are.
+pub fn determine_within() -> Option<PathBuf> {
+    config::var(ENV_WITHIN).map(|dir| untildify(&dir))
+}
+
+/// Determine the order of the groups of recent items
+///
+/// It looks up the `ROFI_VSCODE_GROUP` setting, a comma-separated list of kinds,
+/// e.g. `workspaces,folders,files`. If it is not set, the items are not grouped.
+///
+/// # Errors
+/// The function fails if the setting contains an unrecognized kind.
+pub fn determine_group_order() -> Result<Vec<RecentKind>> {
+    kinds_var(ENV_GROUP)
+}
+
+/// Determine which launches are notified, see [crate::notify]
+///
+/// It looks up the `ROFI_VSCODE_NOTIFY` setting, which can be `never` (default), `errors` or `all`.
+/// An unrecognized value is reported and ignored, so that it does not prevent opening the items.
+pub fn determine_notify() -> NotifyLevel {
+    config::var(ENV_NOTIFY)
+        .map(|val| NotifyLevel::from_str(&val))
+        .unwrap_or(Ok(NotifyLevel::default()))
+        .unwrap_or_else(|e| {
+            warning!("{:?}", e);
+            NotifyLevel::default()
+        })
+}
+
+/// Determine how the recent items are sorted
+///
+/// It looks up the `ROFI_VSCODE_SORT` setting, which can be `recency` (default), `last-opened` or `most-used`.
+///
+/// # Errors
+/// The function fails if the setting contains an unrecognized value.
+pub fn determine_sort() -> Result<RecentSort> {
+    config::var(ENV_SORT)
+        .map(|val| RecentSort::from_str(&val))
+        .unwrap_or(Ok(RecentSort::default()))
+}
+
+/// Determine where the remote and virtual items are placed
+///
+/// It looks up the `ROFI_VSCODE_REMOTE` setting, which can be `mixed` (default), `last` or `hidden`.
+/// The `ROFI_VSCODE_LOCAL_ONLY` flag is a shorthand for `hidden`, and takes precedence.
+///
+/// # Errors
+/// The function fails if the setting contains an unrecognized value.
+pub fn determine_remote_placement() -> Result<RemotePlacement> {
+    if config::flag(ENV_LOCAL_ONLY) {
+        return Ok(RemotePlacement::Hidden);
+    }
+    config::var(ENV_REMOTE)
+        .map(|val| RemotePlacement::from_str(&val))
+        .unwrap_or(Ok(RemotePlacement::default()))
+}
+
+/// Determine the action run by each keybinding of the recent mode
+///
+/// The default [Keymap] has `accept_alt` for `kb-accept-alt` and opens entries with the `flavors` installed ones.
+/// It is changed by the `ROFI_VSCODE_KEYS` setting, a comma-separated list of `key=action` pairs,
+/// e.g. `kb-custom-1=copy-path,kb-accept-alt=new-window`.
+///
+/// # Errors
+/// The function fails if the setting binds an unrecognized key or action.
+pub fn determine_keymap(accept_alt: KeyAction, flavors: usize) -> Result<Keymap> {
+    let mut keymap = Keymap::new(accept_alt, flavors);
+    if let Some(pairs) = config::var(ENV_KEYS) {
+        keymap
+            .bind(&pairs)
+            .with_context(|| format!("Invalid {}", ENV_KEYS))?;
+    }
+    Ok(keymap)
+}
+
+/// Determine the kinds of recent items to show
+///
+/// It looks up the `ROFI_VSCODE_KINDS` setting, a comma-separated list of kinds,
+/// e.g. `workspaces,folders`. If it is not set, items of every kind are shown.
+///
+/// # Errors
+/// The function fails if the setting contains an unrecognized kind.
+pub fn determine_kinds() -> Result<Vec<RecentKind>> {
+    kinds_var(ENV_KINDS)
+}
+
+/// Parse the setting `name` as a comma-separated list of kinds, empty if it is not set
+fn kinds_var(name: &str) -> Result<Vec<RecentKind>> {
+    config::var(name)
+        .map(|val| {
+            val.split(',')
+                .filter(|k| !k.trim().is_empty())
+                .map(RecentKind::from_str)
+                .collect()
+        })
+        .unwrap_or_else(|| Ok(Vec::new()))
+}
+
+/// Determine how the list of recent items is filtered and ordered
+///
+/// The `ROFI_VSCODE_RESOLVE_SYMLINKS` setting enables resolving the symlinks in the paths of the items.
+/// See also [determine_kinds], [determine_within], [determine_dedup_preference], [determine_sort], [determine_group_order], [determine_remote_placement] and [determine_max_entries].
+///
+/// # Errors
+/// The function fails if any of the settings contains an invalid value.
+pub fn determine_recent_options() -> Result<RecentOptions> {
+    Ok(RecentOptions {
+        kinds: determine_kinds()?,
+        resolve_symlinks: config::flag(ENV_RESOLVE_SYMLINKS),
+        within: determine_within(),
+        dedup: determine_dedup_preference()?,
+        sort: determine_sort()?,
+        group: determine_group_order()?,
+        remote: determine_remote_placement()?,
+        hide_virtual: config::flag(ENV_HIDE_VIRTUAL),
+        query: None,
+        max_entries: determine_max_entries()?,
+    })
+}
+
+/// Determine the sources of recent items shown next to the histories of the flavors
+///
+/// It looks up the `ROFI_VSCODE_SOURCES` setting, a comma-separated list of sources:
+/// - `xbel`, the source code files recently used with any application, see [RecentlyUsedFiles]
+/// - `zoxide`, the directories most visited from the shell, see [ZoxideDirectories]
+/// - `repos`, the repositories found under the `ROFI_VSCODE_REPOS_ROOT` directory if it is set,
+///   or else listed by ghq, see [Repositories]
+///
+/// If it is not set, only the histories are shown.
+///
+/// # Errors
+/// The function fails if the setting contains an unrecognized source.
+pub fn determine_sources() -> Result<Vec<Box<dyn RecentSource>>> {
+    let Some(val) = config::var(ENV_SOURCES) else {
+        return Ok(Vec::new());
+    };
+    let mut sources: Vec<Box<dyn RecentSource>> = Vec::new();
+    for name in val.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match name.to_lowercase().as_str() {
+            "xbel" => {
+                if let Some(path) = RecentlyUsedFiles::default_path() {
+                    sources.push(Box::new(RecentlyUsedFiles::new(path)));
+                }
+            }
+            "zoxide" => sources.push(Box::new(ZoxideDirectories)),
+            "repos" => sources.push(Box::new(match config::var(ENV_REPOS_ROOT) {
+                Some(root) => Repositories::under(untildify(&root)),
+                None => Repositories::ghq(),
+            })),
+            _ => return Err(other!("Unrecognized source {}", name)),
+        }
+    }
+    Ok(sources)
+}
+
+/// Determine how the labels of the recent items are laid out
+///
+/// It looks up the `ROFI_VSCODE_LABEL` setting, e.g. `{basename}  {dir}  {remote}`, see [LabelTemplate].
+/// If it is not set, the usual labels are shown.
 ///
 /// # Errors
-/// The function fails if the env. variable contains an unrecognized value,
-/// or if the variable is not set and a suitable flavor cannot be detected.
-pub fn determine_vscode_flavor() -> anyhow::Result<Flavor> {
-    if let Ok(val) = env::var(ENV_FLAVOR) {
-        Flavor::from_str(&val)
+/// The function fails if the setting is not a valid template.
+pub fn determine_label_template() -> Result<Option<LabelTemplate>> {
+    config::var(ENV_LABEL)
+        .map(|val| LabelTemplate::from_str(&val))
+        .transpose()
+}
+
+/// Split a location of the form `{path}:{line}[:{column}]`, as printed by compilers
+///
+/// Returns the path, the line and the column if any, or `None` if `input` does not end with a line number.
+pub fn split_location(input: &str) -> Option<(&str, u32, Option<u32>)> {
+    // Compilers often follow the location with a colon and a message
+    let input = input.trim().trim_end_matches(':');
+    let (rest, last) = input.rsplit_once(':')?;
+    let last = last.parse().ok()?;
+    let location = match rest.rsplit_once(':') {
+        Some((path, line)) => match line.parse() {
+            Ok(line) => (path, line, Some(last)),
+            Err(_) => (rest, last, None),
+        },
+        None => (rest, last, None),
+    };
+    Some(location).filter(|(path, _, _)| !path.is_empty())
+}
+
+/// Expand a path typed by the user, as a shell would
+///
+/// Expands the `$NAME` and `${NAME}` environment variables, the `~` and `~user` prefixes,
+/// and the `*` and `?` wildcards, see [matches_wildcard].
+///
+/// # Errors
+/// The call fails if a variable is not set, if the user is unknown,
+/// or if the wildcards do not match exactly one path.
+pub fn expand_path(input: &str) -> Result<PathBuf> {
+    let path = expand_home(&expand_vars(input)?)?;
+    if path.to_string_lossy().contains(['*', '?']) {
+        expand_wildcards(&path)
     } else {
-        Flavor::detect()
-            .ok_or_else(|| anyhow!("Could not find any suitable VSCode flavor"))
-            .copied()
+        Ok(path)
+    }
+}
+
+/// Expand the `$NAME` and `${NAME}` environment variables in `input`
+fn expand_vars(input: &str) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = input;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let (name, len) = match rest.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced
+                    .find('}')
+                    .ok_or_else(|| other!("Unterminated variable in {}", input))?;
+                (&braced[..end], end + 2)
+            }
+            None => {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                (&rest[..end], end)
+            }
+        };
+        if name.is_empty() {
+            // A lone `$` is kept as is
+            expanded.push('$');
+            continue;
+        }
+        let value = env::var(name)
+            .ok()
+            .with_context(|| format!("Environment variable {} is not set", name))?;
+        expanded.push_str(&value);
+        rest = &rest[len..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Expand the `~` and `~user` prefixes of `path`, see [untildify]
+///
+/// The home directory of other users is looked up in `/etc/passwd`.
+fn expand_home(path: &str) -> Result<PathBuf> {
+    let Some(user) = path
+        .strip_prefix('~')
+        .map(|rest| rest.split('/').next().unwrap_or_default())
+        .filter(|user| !user.is_empty())
+    else {
+        return Ok(untildify(path));
+    };
+    let passwd = fs::read_to_string("/etc/passwd").context("Could not read /etc/passwd")?;
+    let home = passwd
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.len() > 5 && fields[0] == user)
+        .map(|fields| PathBuf::from(fields[5]))
+        .ok_or_else(|| other!("Unknown user {}", user))?;
+    let suffix = &path[1 + user.len()..];
+    Ok(home.join(suffix.trim_start_matches('/')))
+}
+
+/// The only path matching the wildcards in `pattern`
+///
+/// Like in a shell, wildcards do not match hidden files unless the pattern starts with a dot.
+fn expand_wildcards(pattern: &Path) -> Result<PathBuf> {
+    let mut matches = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let Component::Normal(name) = component else {
+            matches.iter_mut().for_each(|m| m.push(component));
+            continue;
+        };
+        let name = name.to_string_lossy();
+        if !name.contains(['*', '?']) {
+            matches.iter_mut().for_each(|m| m.push(&*name));
+            continue;
+        }
+        matches = matches
+            .iter()
+            .flat_map(|dir| {
+                let read_from = if dir.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    dir
+                };
+                fs::read_dir(read_from)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Result::ok)
+                    .map(|e| e.file_name().to_string_lossy().into_owned())
+                    .filter(|entry| !entry.starts_with('.') || name.starts_with('.'))
+                    .filter(|entry| matches_wildcard(&name, entry))
+                    .map(|entry| dir.join(entry))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+    }
+    match matches.len() {
+        1 => Ok(matches.remove(0)),
+        0 => Err(other!("No path matches {:?}", pattern)),
+        n => Err(other!("{} paths match {:?}", n, pattern)),
+    }
+}
+
+/// Format how long ago `time` was with respect to `now`, e.g. `2 d ago`
+pub fn format_relative_time(time: SystemTime, now: SystemTime) -> String {
+    let secs = now.duration_since(time).unwrap_or(Duration::ZERO).as_secs();
+    let (value, unit) = match secs {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (secs / 60, "min"),
+        3600..=86_399 => (secs / 3600, "h"),
+        86_400..=604_799 => (secs / 86_400, "d"),
+        604_800..=2_591_999 => (secs / 604_800, "w"),
+        2_592_000..=31_535_999 => (secs / 2_592_000, "mo"),
+        _ => (secs / 31_536_000, "y"),
+    };
+    format!("{} {} ago", value, unit)
+}
+
+/// Open a URL with the default application, e.g. the web browser
+///
+/// # Errors
+/// The call fails if `xdg-open` cannot be executed.
+pub fn open_url(url: &str) -> Result<()> {
+    Command::new("xdg-open")
+        .arg(url)
+        .spawn()
+        .map(|_| ())
+        .with_context(|| format!("Could not open {}", url))
+}
+
+/// Open a recent item with `flavor`, or in the browser if the `ROFI_VSCODE_BROWSER_FALLBACK` setting is enabled
+///
+/// The item is opened in VSCode for the Web when it has a [web page](web_fallback),
+/// and either the flavor lacks the extension required to open it or the flavor cannot be run.
+/// Either way, the opening is recorded in the [usage statistics](crate::stats),
+/// and notified according to the `ROFI_VSCODE_NOTIFY` setting.
+///
+/// If the flavor cannot open the item from this machine, the URI that a client must open is returned instead,
+/// see [EditorFlavor::handoff_url].
+///
+/// # Errors
+/// The call fails if the item cannot be opened either way.
+pub fn open_recent(flavor: &dyn EditorFlavor, recent: &Recent) -> Result<Option<Url>> {
+    if let Some(url) = flavor.handoff_url(recent)? {
+        return Ok(Some(url));
+    }
+    let level = determine_notify();
+    let label = recent
+        .label()
+        .map(|l| l.into_owned())
+        .unwrap_or_else(|_| recent.url().to_string());
+    let res = open_recent_or_browse(flavor, recent);
+    let notified = match &res {
+        Ok(()) if level == NotifyLevel::All => notify(
+            Urgency::Normal,
+            &format!("Opening {} in {}…", label, flavor.name()),
+            "",
+        ),
+        Err(e) if level >= NotifyLevel::Errors => notify(
+            Urgency::Critical,
+            &format!("Could not open {}", label),
+            &format!("{:#}", e),
+        ),
+        _ => Ok(()),
+    };
+    if let Err(e) = notified {
+        debug!("{:?}", e);
+    }
+    res?;
+    if let Err(e) = record_open(recent, flavor.name()) {
+        warning!("{:?}", e);
+    }
+    Ok(None)
+}
+
+/// Open a recent item with `flavor`, or else return the URI that a client must open, see [EditorFlavor::handoff_url]
+///
+/// Unlike [open_recent], the item is opened as is.
+///
+/// # Errors
+/// The call fails if the item cannot be opened.
+pub fn open_or_hand_off(flavor: &dyn EditorFlavor, recent: &Recent) -> Result<Option<Url>> {
+    match flavor.handoff_url(recent)? {
+        Some(url) => Ok(Some(url)),
+        None => flavor.open_recent(recent).map(|_| None),
+    }
+}
+
+/// Open a recent item as [open_recent] does, without recording it
+fn open_recent_or_browse(flavor: &dyn EditorFlavor, recent: &Recent) -> Result<()> {
+    let fallback = match web_fallback(recent.url()) {
+        Some(fallback) if config::flag(ENV_BROWSER_FALLBACK) => fallback,
+        _ => return flavor.open_recent(recent),
+    };
+    // Opening without the extension shows an error in VSCode, which cannot be detected from here
+    let has_extension = installed_extensions(flavor).map(|extensions| {
+        extensions
+            .iter()
+            .any(|e| e.id.eq_ignore_ascii_case(fallback.extension))
+    });
+    match has_extension {
+        Ok(true) => match flavor.open_recent(recent) {
+            Ok(()) => return Ok(()),
+            Err(e) => warning!("{:?}", e),
+        },
+        Ok(false) => debug!("{} lacks {}", flavor.name(), fallback.extension),
+        Err(e) => warning!("{:?}", e),
+    }
+    open_url(&fallback.url)
+}
+
+/// Copy `text` to the clipboard, with `wl-copy` on Wayland or else `xclip` or `xsel`
+///
+/// # Errors
+/// The call fails if none of them can be executed.
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+    let commands: &[&[&str]] = match wayland {
+        true => &[&["wl-copy"]],
+        false => &[
+            &["xclip", "-selection", "clipboard"],
+            &["xsel", "--clipboard", "--input"],
+        ],
+    };
+    let argv = commands
+        .iter()
+        .find(|argv| which::which(argv[0]).is_ok())
+        .ok_or_else(|| other!("Could not find {} to copy to the clipboard", commands[0][0]))?;
+    let mut child = Command::new(argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Could not execute {}", argv[0]))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .with_context(|| format!("Could not write to {}", argv[0]))?;
+    }
+    Ok(())
+}
+
+/// Open a terminal in the directory `dir`
+///
+/// The terminal is the command of the `ROFI_VSCODE_TERMINAL` setting, e.g. `alacritty`,
+/// or else of the `TERMINAL` environment variable, or `x-terminal-emulator`.
+///
+/// # Errors
+/// The call fails if the terminal cannot be executed.
+pub fn open_terminal(dir: &Path) -> Result<()> {
+    let argv = config::args(ENV_TERMINAL)
+        .or_else(|| env::var("TERMINAL").ok().map(|t| vec![t]))
+        .filter(|argv| !argv.is_empty())
+        .unwrap_or_else(|| vec!["x-terminal-emulator".to_string()]);
+    Command::new(&argv[0])
+        .args(&argv[1..])
+        .current_dir(dir)
+        .spawn()
+        .map(|_| ())
+        .with_context(|| format!("Could not open a terminal with {}", argv[0]))
+}
+
+/// Check whether `text` matches the wildcard `pattern`
+///
+/// The pattern may contain `*`, matching any sequence of characters,
+/// and `?`, matching exactly one character.
+pub fn matches_wildcard(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and of the text when it was met
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` consume one more character
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Score how well `text` matches the fuzzy `pattern`, higher is better
+///
+/// The characters of the pattern must appear in the text in the same order, ignoring case.
+/// Matches are rewarded when they are consecutive or at the start of a word, and penalized by the gaps between them.
+/// Returns `None` if the text does not match.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    const MATCH: i64 = 16;
+    const CONSECUTIVE: i64 = 16;
+    const WORD_START: i64 = 12;
+    const GAP: i64 = 1;
+
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let text: Vec<char> = text.chars().collect();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    // Best score with the current pattern character matched at each position of the text
+    let mut scores: Vec<Option<i64>> = vec![None; text.len()];
+    for (i, &p) in pattern.iter().enumerate() {
+        let mut next = vec![None; text.len()];
+        // Best score of the previous pattern character matched before the current position, minus the gap
+        let mut best_before: Option<i64> = None;
+        for (j, &t) in text.iter().enumerate() {
+            if i > 0 && j > 0 {
+                best_before = best_before.map(|s| s - GAP).max(scores[j - 1]);
+            }
+            if !t.to_lowercase().eq([p]) {
+                continue;
+            }
+            let word_start = j == 0 || "/-_. ".contains(text[j - 1]);
+            let bonus = MATCH + if word_start { WORD_START } else { 0 };
+            next[j] = if i == 0 {
+                Some(bonus)
+            } else {
+                let consecutive = (j > 0).then(|| scores[j - 1]).flatten();
+                consecutive
+                    .map(|s| s + CONSECUTIVE)
+                    .max(best_before)
+                    .map(|s| s + bonus)
+            };
+        }
+        scores = next;
+    }
+    scores.into_iter().flatten().max()
+}
+
+/// Escape `s` as a string value of a desktop entry
+pub fn escape_desktop_value(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r")
+}
+
+/// Escape `s` as a field of tab-separated values, with the same backslash escapes as [escape_desktop_value]
+pub fn escape_tsv_field(s: &str) -> String {
+    escape_desktop_value(s)
+}
+
+/// Quote `s` as a field of comma-separated values, if it contains a separator, a quote or a line break
+///
+/// See [RFC 4180](https://www.rfc-editor.org/rfc/rfc4180).
+pub fn quote_csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Quote `arg` as an argument of the `Exec` key of a desktop entry
+///
+/// Besides quoting, `%` is doubled so that it is not taken as a field code,
+/// see the [specification](https://specifications.freedesktop.org/desktop-entry-spec/latest/exec-variables.html).
+pub fn quote_desktop_exec_arg(arg: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in arg.chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    escape_desktop_value(&quoted)
+}
+
+/// Compute the MD5 digest of `data` as a lowercase hex string
+///
+/// VSCode uses it to derive the identifiers of workspaces, it is not meant for anything security related.
+pub fn md5_hex(data: &[u8]) -> String {
+    const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+    let k: Vec<u32> = (0..64)
+        .map(|i| ((i as f64 + 1.0).sin().abs() * 4294967296.0) as u32)
+        .collect();
+
+    // Pad to a multiple of 64 bytes, ending with the length in bits
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks(64) {
+        let words: Vec<u32> = chunk
+            .chunks(4)
+            .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(k[i])
+                .wrapping_add(words[g])
+                .rotate_left(SHIFTS[(i / 16) * 4 + i % 4]);
+            (a, d, c) = (d, c, b);
+            b = b.wrapping_add(rotated);
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+    state
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::Path,
+        time::{Duration, SystemTime},
+    };
+
+    use super::{
+        escape_tsv_field, expand_path, format_relative_time, fuzzy_score, matches_wildcard,
+        md5_hex, quote_csv_field, quote_desktop_exec_arg, relative_path, shorten_path,
+        split_location,
+    };
+
+    #[test]
+    fn tabular_fields() {
+        assert_eq!(escape_tsv_field("beet\tfarm\n"), "beet\\tfarm\\n");
+        assert_eq!(quote_csv_field("beet farm"), "beet farm");
+        assert_eq!(quote_csv_field("beets, bears"), "\"beets, bears\"");
+        assert_eq!(quote_csv_field("\"Battlestar\""), "\"\"\"Battlestar\"\"\"");
+    }
+
+    #[test]
+    fn relative_paths() {
+        let base = Path::new("/home/dwight");
+        assert_eq!(
+            relative_path(Path::new("/home/dwight/beet-farm/barn"), base),
+            "beet-farm/barn"
+        );
+        assert_eq!(relative_path(Path::new("/home/dwight"), base), ".");
+        assert_eq!(
+            relative_path(Path::new("/home/dwight2"), base),
+            "/home/dwight2"
+        );
+        assert_eq!(relative_path(Path::new("/srv/beets"), base), "/srv/beets");
+    }
+
+    #[test]
+    fn shorten_paths() {
+        let label = "~/work/monorepo/packages/backend/service/api";
+        assert_eq!(shorten_path(label, 100), label);
+        assert_eq!(shorten_path(label, 24), "~/work/.../service/api");
+        assert_eq!(shorten_path(label, 10), "~/.../api");
+        assert_eq!(shorten_path("/srv/beets", 5), "/srv/beets");
+        assert_eq!(
+            shorten_path("~/a/b/c/d/e/f [SSH: devbox]", 20),
+            "~/.../f [SSH: devbox]"
+        );
+    }
+
+    #[test]
+    fn expand_paths() {
+        let root = env!("CARGO_MANIFEST_DIR");
+        let home = dirs::home_dir().expect("expected a home directory");
+        assert_eq!(
+            expand_path("$CARGO_MANIFEST_DIR/src").unwrap(),
+            Path::new(root).join("src")
+        );
+        assert_eq!(
+            expand_path("${CARGO_MANIFEST_DIR}/src/util?.rs").unwrap(),
+            Path::new(root).join("src").join("utils.rs")
+        );
+        assert_eq!(expand_path("~/notes").unwrap(), home.join("notes"));
+        assert_eq!(expand_path("/cost$").unwrap(), Path::new("/cost$"));
+        assert!(expand_path("$ROFI_VSCODE_SURELY_UNSET/src").is_err());
+        assert!(expand_path("~surely-not-a-user/src").is_err());
+        // Many matches
+        assert!(expand_path(&format!("{}/src/*.rs", root)).is_err());
+        // No matches
+        assert!(expand_path(&format!("{}/src/*.beet", root)).is_err());
+    }
+
+    #[test]
+    fn locations() {
+        assert_eq!(
+            split_location("src/main.rs:42:7"),
+            Some(("src/main.rs", 42, Some(7)))
+        );
+        assert_eq!(
+            split_location("~/beets/harvest.py:3: "),
+            Some(("~/beets/harvest.py", 3, None))
+        );
+        assert_eq!(
+            split_location(r"C:\beets\main.rs:1:2"),
+            Some((r"C:\beets\main.rs", 1, Some(2)))
+        );
+        assert_eq!(split_location("/home/dwight/beet-farm"), None);
+        assert_eq!(split_location(":42"), None);
+        assert_eq!(split_location("notes:draft"), None);
+    }
+
+    #[test]
+    fn desktop_exec_quoting() {
+        assert_eq!(quote_desktop_exec_arg("open"), r#""open""#);
+        assert_eq!(
+            quote_desktop_exec_arg("file:///home/dwight/beet%20farm"),
+            r#""file:///home/dwight/beet%%20farm""#
+        );
+        assert_eq!(quote_desktop_exec_arg("$HOME"), r#""\\$HOME""#);
+    }
+
+    #[test]
+    fn md5_digest() {
+        assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(
+            md5_hex(b"The quick brown fox jumps over the lazy dog"),
+            "9e107d9d372bb6826bd81d3542a419d6"
+        );
+        assert_eq!(md5_hex(&[b'a'; 100]), "36a92cc94a9e0fa21f625f8bfb007adf");
+    }
+
+    #[test]
+    fn fuzzy_matching() {
+        assert!(fuzzy_score("bf", "~/beet-farm").is_some());
+        assert!(fuzzy_score("BF", "~/beet-farm").is_some());
+        assert!(fuzzy_score("fb", "~/beet-farm").is_none());
+        assert_eq!(fuzzy_score("", "~/beet-farm"), Some(0));
+        // Word starts and consecutive characters rank higher
+        assert!(fuzzy_score("bf", "~/beet-farm") > fuzzy_score("bf", "~/abcdef"));
+        assert!(fuzzy_score("farm", "~/beet-farm") > fuzzy_score("farm", "~/far-away/m"));
+    }
+
+    #[test]
+    fn relative_time() {
+        let now = SystemTime::now();
+        let ago = |secs| format_relative_time(now - Duration::from_secs(secs), now);
+        assert_eq!(ago(5), "just now");
+        assert_eq!(ago(150), "2 min ago");
+        assert_eq!(ago(3 * 3600), "3 h ago");
+        assert_eq!(ago(2 * 86_400 + 60), "2 d ago");
+        assert_eq!(ago(15 * 86_400), "2 w ago");
+        assert_eq!(ago(400 * 86_400), "1 y ago");
+        assert_eq!(
+            format_relative_time(now + Duration::from_secs(60), now),
+            "just now"
+        );
+    }
+
+    #[test]
+    fn wildcard_matching() {
+        assert!(matches_wildcard("*.conf", "work.conf"));
+        assert!(matches_wildcard("config-?", "config-1"));
+        assert!(matches_wildcard("*", ""));
+        assert!(matches_wildcard("a*b*c", "a-b-b-c"));
+        assert!(!matches_wildcard("*.conf", "work.config"));
+        assert!(!matches_wildcard("config-?", "config-10"));
+        assert!(!matches_wildcard("a*b", "a-c"));
     }
 }
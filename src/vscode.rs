@@ -2,25 +2,252 @@
 
 use std::{
     borrow::Cow,
+    env,
     ffi::OsStr,
+    fmt, fs, io,
     path::{Path, PathBuf},
-    process::Command,
+    process::{self, Command},
     str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        OnceLock,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use anyhow::{anyhow, Context, Result};
-use rusqlite::{Connection, OpenFlags};
+use rusqlite::{Connection, ErrorCode, OpenFlags};
+use url::Url;
 use which::which;
 
-use self::workspaces::Recent;
+use self::{profiles::Profile, workspaces::Recent};
+use crate::error::{other, Context, Error, Result};
+use crate::logging::{debug, span, trace, warning};
+use crate::notify::{watched, NotifyLevel};
+use crate::utils::determine_notify;
+
+pub mod extensions;
+pub mod icons;
+pub mod jsonc;
+pub mod label;
+pub mod profiles;
+pub mod projects;
+pub mod server;
+mod storage_json;
+pub mod tasks;
+pub mod workspace_storage;
 
 #[allow(dead_code)]
 const SCHEME_FILE: &str = "file";
 #[allow(dead_code)]
 const SCHEME_REMOTE: &str = "vscode-remote";
-#[allow(dead_code)]
 const SCHEME_VIRTUAL: &str = "vscode-vfs";
 
+/// Number of attempts to read a busy database, before reading a copy of it
+const DB_READ_ATTEMPTS: u32 = 4;
+/// Delay before retrying to read a busy database, doubled at each attempt
+const DB_RETRY_DELAY: Duration = Duration::from_millis(50);
+/// Maximum time to wait for a busy database to be written
+const DB_WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+const FLATPAK_CMD: &str = "flatpak";
+const SNAP_BIN_DIR: &str = "/snap/bin";
+/// Directory of the command line launcher inside a macOS application bundle
+const APP_BUNDLE_BIN_DIR: &str = "Contents/Resources/app/bin";
+/// Set by portable installations to their data directory, e.g. in the integrated terminal
+const ENV_VSCODE_PORTABLE: &str = "VSCODE_PORTABLE";
+/// Names of the data directory of portable installations, next to the executable or to the macOS application bundle
+const PORTABLE_DATA_DIRS: &[&str] = &["data", "code-portable-data"];
+/// How many parents of the executable are looked for the data directory of a portable installation,
+/// since the command line launcher is in `bin/` or deep inside the application bundle
+const PORTABLE_MAX_DEPTH: usize = 6;
+
+/// Where the extensions of a flavor are published
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Marketplace {
+    /// The [Visual Studio Marketplace](https://marketplace.visualstudio.com), for proprietary builds
+    VisualStudio,
+    /// The [Open VSX Registry](https://open-vsx.org), for open source builds
+    OpenVsx,
+}
+
+/// An editor sharing VSCode's command line interface and configuration layout
+///
+/// This includes VSCode itself and its forks, which keep the recent items in the same
+/// `User/globalStorage/state.vscdb` database.
+/// Implementors only tell how to run the editor and where its configuration is stored,
+/// while the operations on the editor are provided.
+pub trait EditorFlavor: fmt::Debug + Send + Sync {
+    /// The command to run the flavor
+    fn cmd(&self) -> &str;
+
+    /// Path to the configuration directory of the flavor, if it exists
+    fn config_dir(&self) -> Option<PathBuf>;
+
+    /// Marketplace where the extensions of the flavor are published
+    fn marketplace(&self) -> Marketplace {
+        Marketplace::OpenVsx
+    }
+
+    /// Path to the database holding the global state of the flavor, including the recent items
+    fn state_db(&self) -> Option<PathBuf> {
+        self.config_dir().map(|d| state_db_path(&d))
+    }
+
+    /// Name shown to the user to tell flavors apart
+    fn name(&self) -> &str {
+        self.cmd()
+    }
+
+    /// Tells whether the flavor is installed
+    ///
+    /// This requires both an executable in `$PATH` and a configuration directory.
+    fn is_installed(&self) -> bool {
+        which(self.cmd()).is_ok() && self.config_dir().is_some()
+    }
+
+    /// Creates a command that runs the flavor, to which arguments can be added
+    fn command(&self) -> Command {
+        Command::new(resolve_cmd(self.cmd()))
+    }
+
+    /// Creates a command that opens something with the flavor, see [WithArgs]
+    fn open_command(&self) -> Command {
+        self.command()
+    }
+
+    /// The URI that a client must open instead, if the flavor cannot open `recent` from this machine
+    ///
+    /// This is the case of a [server::Server] whose command line is only available in the terminals of its clients.
+    ///
+    /// # Errors
+    /// The call fails if the URI cannot be built.
+    fn handoff_url(&self, _recent: &Recent) -> Result<Option<Url>> {
+        Ok(None)
+    }
+
+    /// Opens a recent item
+    ///
+    /// It will execute a command to open the given item
+    ///
+    /// # Errors
+    /// Opening the item may fail if [Self::cmd()] is not found in `PATH`.
+    /// Currently, we support the `file://`, `vscode-remote://` and `vscode-vfs://` schemes.
+    fn open_recent(&self, recent: &Recent) -> Result<()> {
+        let mut cmd = self.open_command();
+        push_recent_args(&mut cmd, recent);
+        spawn(&mut cmd).with_context(|| format!("Could not open entry with {}", self.cmd()))
+    }
+
+    /// Opens a new window with the given profile
+    ///
+    /// If a `recent` item is given, it will be opened in the new window.
+    ///
+    /// # Errors
+    /// Opening the window may fail if [Self::cmd()] is not found in `PATH`.
+    fn open_profile(&self, profile: &Profile, recent: Option<&Recent>) -> Result<()> {
+        let mut cmd = self.open_command();
+        cmd.arg("--profile").arg(&profile.name);
+        match recent {
+            Some(recent) => push_recent_args(&mut cmd, recent),
+            None => {
+                cmd.arg("--new-window");
+            }
+        }
+        spawn(&mut cmd).with_context(|| {
+            format!(
+                "Could not open profile \"{}\" with {}",
+                profile.name,
+                self.cmd()
+            )
+        })
+    }
+
+    /// Opens a new window connected to a remote host
+    ///
+    /// The `authority` identifies the remote, e.g. `ssh-remote+{host}`.
+    /// If a `path` is given, it will be opened on the remote.
+    ///
+    /// # Errors
+    /// Opening the window may fail if [Self::cmd()] is not found in `PATH`.
+    fn open_remote(&self, authority: &str, path: Option<&str>) -> Result<()> {
+        let mut cmd = self.open_command();
+        cmd.arg("--remote").arg(authority);
+        match path {
+            Some(path) => {
+                cmd.arg(path);
+            }
+            None => {
+                cmd.arg("--new-window");
+            }
+        }
+        spawn(&mut cmd)
+            .with_context(|| format!("Could not open remote {} with {}", authority, self.cmd()))
+    }
+
+    /// Opens a file at the given location
+    ///
+    /// The `location` has the form `{path}[:{line}[:{column}]]`.
+    ///
+    /// # Errors
+    /// Opening the file may fail if [Self::cmd()] is not found in `PATH`.
+    fn goto(&self, location: &OsStr) -> Result<()> {
+        spawn(self.open_command().arg("--goto").arg(location))
+            .with_context(|| "Could not execute VSCode")
+    }
+
+    /// Opens a diff editor comparing the files `first` and `second`
+    ///
+    /// # Errors
+    /// Opening the editor may fail if [Self::cmd()] is not found in `PATH`.
+    fn diff(&self, first: &OsStr, second: &OsStr) -> Result<()> {
+        spawn(self.open_command().arg("--diff").arg(first).arg(second))
+            .with_context(|| format!("Could not compare files with {}", self.cmd()))
+    }
+
+    /// Opens the given path
+    ///
+    /// # Errors
+    /// Opening the item may fail if [Self::cmd()] is not found in `PATH` or if the command fails for some other reason.
+    fn open_local_path(&self, path: &OsStr) -> Result<()> {
+        spawn(self.open_command().arg(path)).with_context(|| "Could not execute VSCode")
+    }
+}
+
+/// How a flavor is installed
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Installation {
+    /// Installed with the system's package manager or by hand, with the command in `$PATH`
+    Native,
+    /// Installed with [Flatpak](https://flatpak.org) as the application with the given identifier
+    ///
+    /// Its configuration is kept inside the sandbox, in `~/.var/app/{id}/config/`,
+    /// and it is launched with `flatpak run {id}`.
+    Flatpak(&'static str),
+    /// Installed with [Snap](https://snapcraft.io) as the package with the given name
+    ///
+    /// It is launched with `/snap/bin/{name}`, and its configuration may be kept in `~/snap/{name}/current/.config/`.
+    Snap(&'static str),
+    /// Installed on macOS as the application bundle with the given name, in `/Applications/` or `~/Applications/`
+    ///
+    /// It is launched with the command inside the bundle, which is in `$PATH` only after running
+    /// _Shell Command: Install 'code' command in PATH_.
+    AppBundle(&'static str),
+    /// Extracted from an archive and run in [portable mode](https://code.visualstudio.com/docs/editor/portable),
+    /// with its command in `$PATH`
+    ///
+    /// Its configuration is kept in the `data/user-data/` directory next to the executable,
+    /// or in `code-portable-data/user-data/` next to the application bundle on macOS.
+    Portable,
+}
+
+/// What was found about the installation of a [Flavor]
+#[derive(Debug)]
+struct Detected {
+    installation: Option<Installation>,
+    portable_data_dir: Option<PathBuf>,
+}
+
 /// One of the possible VSCode flavors
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum Flavor {
@@ -28,106 +255,595 @@ pub enum Flavor {
     CodeInsiders,
     CodeOSS,
     VSCodium,
+    Cursor,
+    Windsurf,
 }
 
+/// Flavors in order of preference for detection
+const FLAVOR_CANDIDATES: &[Flavor] = &[
+    Flavor::VSCodium,
+    Flavor::CodeOSS,
+    Flavor::CodeInsiders,
+    Flavor::Code,
+    Flavor::Cursor,
+    Flavor::Windsurf,
+];
+
 impl Flavor {
-    //// The command to run the flavor
-    pub fn cmd(&self) -> &str {
-        match self {
-            Self::Code => "code",
-            Self::CodeInsiders => "code-insiders",
-            Self::CodeOSS => "code-oss", // also provides `code`
-            Self::VSCodium => "codium",  // also provides `vscodium`
-        }
+    /// Tries to detect the preferred flavor
+    ///
+    /// It returns the first flavor that [is installed](EditorFlavor::is_installed).
+    pub fn detect() -> Option<&'static Self> {
+        let _span = span!("Detecting the installed flavor");
+        FLAVOR_CANDIDATES.iter().find(|d| d.is_installed())
     }
 
-    /// Path to the configuration directory of the flavor, if it exists
-    pub fn config_dir(&self) -> Option<PathBuf> {
-        let subdir = match self {
+    /// All the flavors that [are installed](EditorFlavor::is_installed), in order of preference
+    pub fn installed() -> Vec<Self> {
+        let _span = span!("Detecting the installed flavors");
+        FLAVOR_CANDIDATES
+            .iter()
+            .filter(|d| d.is_installed())
+            .copied()
+            .collect()
+    }
+
+    /// Name of the flavor's directory inside the configuration directory
+    fn config_subdir(&self) -> &'static str {
+        match self {
             Self::Code => "Code",
             Self::CodeInsiders => "Code - Insiders",
             Self::CodeOSS => "Code - OSS",
             Self::VSCodium => "VSCodium",
-        };
-        dirs::config_dir()
-            .map(|mut p| {
-                p.push(subdir);
-                p
-            })
-            .filter(|p| p.exists())
+            Self::Cursor => "Cursor",
+            Self::Windsurf => "Windsurf",
+        }
     }
 
-    /// Tries to detect the preferred flavor
+    /// Identifier of the flavor's application on [Flathub](https://flathub.org), if any
+    pub fn flatpak_id(&self) -> Option<&'static str> {
+        match self {
+            Self::Code => Some("com.visualstudio.code"),
+            Self::CodeInsiders => Some("com.visualstudio.code.insiders"),
+            Self::CodeOSS => Some("com.visualstudio.code-oss"),
+            Self::VSCodium => Some("com.vscodium.codium"),
+            Self::Cursor | Self::Windsurf => None,
+        }
+    }
+
+    /// Name of the flavor's package on the [Snap Store](https://snapcraft.io), if any
+    pub fn snap_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Code => Some("code"),
+            Self::CodeInsiders => Some("code-insiders"),
+            Self::VSCodium => Some("codium"),
+            Self::CodeOSS | Self::Cursor | Self::Windsurf => None,
+        }
+    }
+
+    /// Name of the flavor's application bundle on macOS
+    pub fn app_bundle_name(&self) -> &'static str {
+        match self {
+            Self::Code => "Visual Studio Code",
+            Self::CodeInsiders => "Visual Studio Code - Insiders",
+            Self::CodeOSS => "Code - OSS",
+            Self::VSCodium => "VSCodium",
+            Self::Cursor => "Cursor",
+            Self::Windsurf => "Windsurf",
+        }
+    }
+
+    /// Command inside the flavor's application bundle in `apps_dir`
+    fn app_bundle_cmd(&self, apps_dir: &Path) -> PathBuf {
+        apps_dir
+            .join(format!("{}.app", self.app_bundle_name()))
+            .join(APP_BUNDLE_BIN_DIR)
+            .join(self.cmd())
+    }
+
+    /// Command inside the flavor's application bundle, if it is installed for the system or for the user
+    fn installed_app_bundle_cmd(&self) -> Option<PathBuf> {
+        let user_apps = dirs::home_dir().map(|h| h.join("Applications"));
+        [Some(PathBuf::from("/Applications")), user_apps]
+            .into_iter()
+            .flatten()
+            .map(|dir| self.app_bundle_cmd(&dir))
+            .find(|cmd| cmd.exists())
+    }
+
+    /// Configuration directory of a native installation
     ///
-    /// It returns the first flavor for which it can find both:
-    /// - an executable in `$PATH`
-    /// - a configuration directory
-    pub fn detect() -> Option<&'static Self> {
-        let candidates = &[
-            Self::VSCodium,
-            Self::CodeOSS,
-            Self::CodeInsiders,
-            Self::Code,
-        ];
-        candidates
-            .iter()
-            .find(|d| which(d.cmd()).ok().and(d.config_dir()).is_some())
+    /// It is inside `~/.config/` on Linux and `~/Library/Application Support/` on macOS.
+    fn native_config_dir(&self) -> Option<PathBuf> {
+        dirs::config_dir().map(|p| p.join(self.config_subdir()))
     }
 
-    /// Opens a recent item
+    /// Configuration directory of a Flatpak installation, inside the application's sandbox in `home`
+    fn flatpak_config_dir(&self, home: &Path) -> Option<PathBuf> {
+        self.flatpak_id().map(|id| {
+            home.join(".var")
+                .join("app")
+                .join(id)
+                .join("config")
+                .join(self.config_subdir())
+        })
+    }
+
+    /// Configuration directory of a Snap installation, inside the package's data in `home`
+    fn snap_config_dir(&self, home: &Path) -> Option<PathBuf> {
+        self.snap_name().map(|name| {
+            home.join("snap")
+                .join(name)
+                .join("current")
+                .join(".config")
+                .join(self.config_subdir())
+        })
+    }
+
+    /// Data directory of a portable installation, see [Installation::Portable]
+    fn portable_data_dir(&self) -> Option<PathBuf> {
+        self.detected().portable_data_dir.clone()
+    }
+
+    /// Look for the data directory of a portable installation
     ///
-    /// It will execute a command to open the given item
+    /// It is looked up next to the executable in `$PATH`, following symlinks,
+    /// or else taken from `VSCODE_PORTABLE` when the executable belongs to the installation holding it.
+    fn find_portable_data_dir(&self) -> Option<PathBuf> {
+        let exe = which(self.cmd()).ok()?;
+        let exe = fs::canonicalize(&exe).unwrap_or(exe);
+        portable_data_dir_of(&exe).or_else(|| {
+            env::var_os(ENV_VSCODE_PORTABLE)
+                .and_then(|data| portable_data_dir_from_env(&exe, Path::new(&data)))
+        })
+    }
+
+    /// Tells how the flavor is installed, if it is
     ///
-    /// # Errors
-    /// Opening the item may fail if [self.cmd()] is not found in `PATH`.
-    /// Currently, we support the `file://`, `vscode-remote://` and `vscode-vfs://` schemes.
-    pub fn open_recent(&self, recent: &Recent) -> anyhow::Result<()> {
-        let mut cmd = Command::new(self.cmd());
+    /// Portable installations are preferred, since their configuration is used whatever else is installed,
+    /// and then native ones.
+    pub fn installation(&self) -> Option<Installation> {
+        self.detected().installation
+    }
 
-        let url = recent.url().to_string();
-        match recent {
-            Recent::Workspace {
-                workspace: _,
-                label: _,
-                remote_authority: _,
-            } => {
-                cmd.arg("--file-uri").arg(url);
+    /// The installation of the flavor, detected the first time it is needed
+    fn detected(&self) -> &'static Detected {
+        static DETECTED: [OnceLock<Detected>; FLAVOR_CANDIDATES.len()] =
+            [const { OnceLock::new() }; FLAVOR_CANDIDATES.len()];
+        DETECTED[*self as usize].get_or_init(|| {
+            let portable_data_dir = self.find_portable_data_dir();
+            let installation = self.detect_installation(portable_data_dir.is_some());
+            trace!("Installation of {}: {:?}", self.name(), installation);
+            Detected {
+                installation,
+                portable_data_dir,
             }
-            Recent::Folder {
-                folder_uri: _,
-                label: _,
-                remote_authority: _,
-            } => {
-                cmd.arg("--folder-uri").arg(url);
+        })
+    }
+
+    /// Look for the installation of the flavor, see [Self::installation]
+    fn detect_installation(&self, portable: bool) -> Option<Installation> {
+        let exists = |dir: Option<PathBuf>| dir.is_some_and(|d| d.exists());
+        if portable {
+            return Some(Installation::Portable);
+        }
+        if which(self.cmd()).is_ok() && exists(self.native_config_dir()) {
+            return Some(Installation::Native);
+        }
+        if cfg!(target_os = "macos")
+            && self.installed_app_bundle_cmd().is_some()
+            && exists(self.native_config_dir())
+        {
+            return Some(Installation::AppBundle(self.app_bundle_name()));
+        }
+        if let Some(id) = self.flatpak_id() {
+            let config_dir = dirs::home_dir().and_then(|h| self.flatpak_config_dir(&h));
+            if which(FLATPAK_CMD).is_ok() && exists(config_dir) {
+                return Some(Installation::Flatpak(id));
             }
-            Recent::File {
-                file_uri: _,
-                label: _,
-                remote_authority: _,
-            } => {
-                cmd.arg("--file-uri").arg(url);
+        }
+        if let Some(name) = self.snap_name() {
+            // Classic snaps use the same configuration directory as native installations
+            let config_dir = dirs::home_dir()
+                .and_then(|h| self.snap_config_dir(&h))
+                .filter(|d| d.exists())
+                .or_else(|| self.native_config_dir());
+            if Path::new(SNAP_BIN_DIR).join(name).exists() && exists(config_dir) {
+                return Some(Installation::Snap(name));
             }
         }
-        cmd.spawn()
-            .map(|_| ())
-            .with_context(|| format!("Could not open entry with {}", self.cmd()))
+        None
     }
+}
 
-    /// Opens the given path
-    ///
-    /// # Errors
-    /// Opening the item may fail if [self.cmd()] is not found in `PATH` or if the command fails for some other reason.
-    pub fn open_local_path<S: AsRef<OsStr>>(&self, path: S) -> Result<()> {
-        Command::new(self.cmd())
-            .arg(path)
-            .spawn()
-            .map(|_| ())
-            .with_context(|| "Could not execute VSCode")
+impl EditorFlavor for Flavor {
+    fn cmd(&self) -> &str {
+        match self {
+            Self::Code => "code",
+            Self::CodeInsiders => "code-insiders",
+            Self::CodeOSS => "code-oss", // also provides `code`
+            Self::VSCodium => "codium",  // also provides `vscodium`
+            Self::Cursor => "cursor",
+            Self::Windsurf => "windsurf",
+        }
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        match self.installation() {
+            Some(Installation::Portable) => self.portable_data_dir().map(|d| d.join("user-data")),
+            Some(Installation::Flatpak(_)) => {
+                dirs::home_dir().and_then(|h| self.flatpak_config_dir(&h))
+            }
+            Some(Installation::Snap(_)) => dirs::home_dir()
+                .and_then(|h| self.snap_config_dir(&h))
+                .filter(|p| p.exists())
+                .or_else(|| self.native_config_dir()),
+            _ => self.native_config_dir().filter(|p| p.exists()),
+        }
+    }
+
+    fn marketplace(&self) -> Marketplace {
+        match self {
+            Self::Code | Self::CodeInsiders => Marketplace::VisualStudio,
+            Self::CodeOSS | Self::VSCodium | Self::Cursor | Self::Windsurf => Marketplace::OpenVsx,
+        }
+    }
+
+    fn name(&self) -> &str {
+        self.config_subdir()
+    }
+
+    fn is_installed(&self) -> bool {
+        self.installation().is_some()
+    }
+
+    fn command(&self) -> Command {
+        match self.installation() {
+            Some(Installation::Flatpak(id)) => {
+                let mut cmd = Command::new(FLATPAK_CMD);
+                cmd.arg("run").arg(id);
+                cmd
+            }
+            Some(Installation::Snap(name)) => Command::new(Path::new(SNAP_BIN_DIR).join(name)),
+            Some(Installation::AppBundle(_)) => match self.installed_app_bundle_cmd() {
+                Some(cmd) => Command::new(cmd),
+                None => Command::new(resolve_cmd(self.cmd())),
+            },
+            _ => Command::new(resolve_cmd(self.cmd())),
+        }
+    }
+}
+
+/// A flavor given by the user, e.g. an unknown fork or a wrapper script
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct CustomFlavor {
+    /// The command to run the flavor
+    pub cmd: String,
+    /// The configuration directory of the flavor, if any
+    pub config_dir: Option<PathBuf>,
+    /// The state database, if it is not in the configuration directory
+    pub state_db: Option<PathBuf>,
+    /// Marketplace where the extensions of the flavor are published
+    pub marketplace: Marketplace,
+}
+
+impl EditorFlavor for CustomFlavor {
+    fn cmd(&self) -> &str {
+        &self.cmd
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        self.config_dir.clone().filter(|p| p.exists())
+    }
+
+    fn state_db(&self) -> Option<PathBuf> {
+        self.state_db
+            .clone()
+            .or_else(|| self.config_dir().map(|d| state_db_path(&d)))
+    }
+
+    fn marketplace(&self) -> Marketplace {
+        self.marketplace
+    }
+}
+
+impl<F: EditorFlavor + ?Sized> EditorFlavor for &F {
+    fn cmd(&self) -> &str {
+        (**self).cmd()
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        (**self).config_dir()
+    }
+
+    fn marketplace(&self) -> Marketplace {
+        (**self).marketplace()
+    }
+
+    fn state_db(&self) -> Option<PathBuf> {
+        (**self).state_db()
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn is_installed(&self) -> bool {
+        (**self).is_installed()
+    }
+
+    fn command(&self) -> Command {
+        (**self).command()
+    }
+
+    fn open_command(&self) -> Command {
+        (**self).open_command()
+    }
+
+    fn handoff_url(&self, recent: &Recent) -> Result<Option<Url>> {
+        (**self).handoff_url(recent)
+    }
+
+    fn open_recent(&self, recent: &Recent) -> Result<()> {
+        (**self).open_recent(recent)
+    }
+
+    fn open_profile(&self, profile: &Profile, recent: Option<&Recent>) -> Result<()> {
+        (**self).open_profile(profile, recent)
+    }
+
+    fn open_remote(&self, authority: &str, path: Option<&str>) -> Result<()> {
+        (**self).open_remote(authority, path)
+    }
+
+    fn goto(&self, location: &OsStr) -> Result<()> {
+        (**self).goto(location)
+    }
+
+    fn diff(&self, first: &OsStr, second: &OsStr) -> Result<()> {
+        (**self).diff(first, second)
+    }
+
+    fn open_local_path(&self, path: &OsStr) -> Result<()> {
+        (**self).open_local_path(path)
+    }
+}
+
+impl<F: EditorFlavor + ?Sized> EditorFlavor for Box<F> {
+    fn cmd(&self) -> &str {
+        (**self).cmd()
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        (**self).config_dir()
+    }
+
+    fn marketplace(&self) -> Marketplace {
+        (**self).marketplace()
+    }
+
+    fn state_db(&self) -> Option<PathBuf> {
+        (**self).state_db()
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn is_installed(&self) -> bool {
+        (**self).is_installed()
+    }
+
+    fn command(&self) -> Command {
+        (**self).command()
+    }
+
+    fn open_command(&self) -> Command {
+        (**self).open_command()
+    }
+
+    fn handoff_url(&self, recent: &Recent) -> Result<Option<Url>> {
+        (**self).handoff_url(recent)
+    }
+
+    fn open_recent(&self, recent: &Recent) -> Result<()> {
+        (**self).open_recent(recent)
+    }
+
+    fn open_profile(&self, profile: &Profile, recent: Option<&Recent>) -> Result<()> {
+        (**self).open_profile(profile, recent)
+    }
+
+    fn open_remote(&self, authority: &str, path: Option<&str>) -> Result<()> {
+        (**self).open_remote(authority, path)
+    }
+
+    fn goto(&self, location: &OsStr) -> Result<()> {
+        (**self).goto(location)
+    }
+
+    fn diff(&self, first: &OsStr, second: &OsStr) -> Result<()> {
+        (**self).diff(first, second)
+    }
+
+    fn open_local_path(&self, path: &OsStr) -> Result<()> {
+        (**self).open_local_path(path)
+    }
+}
+
+/// A flavor that is passed extra arguments whenever it opens something, e.g. `--new-window`
+///
+/// The arguments replace those of the wrapped flavor, if it is a [WithArgs] too,
+/// and they are not passed to the other commands, e.g. to manage extensions.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct WithArgs<F> {
+    /// The wrapped flavor
+    pub flavor: F,
+    /// The arguments, passed before the item to open
+    pub args: Vec<String>,
+}
+
+impl<F: EditorFlavor> EditorFlavor for WithArgs<F> {
+    fn cmd(&self) -> &str {
+        self.flavor.cmd()
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        self.flavor.config_dir()
+    }
+
+    fn marketplace(&self) -> Marketplace {
+        self.flavor.marketplace()
+    }
+
+    fn state_db(&self) -> Option<PathBuf> {
+        self.flavor.state_db()
+    }
+
+    fn name(&self) -> &str {
+        self.flavor.name()
+    }
+
+    fn is_installed(&self) -> bool {
+        self.flavor.is_installed()
+    }
+
+    fn handoff_url(&self, recent: &Recent) -> Result<Option<Url>> {
+        self.flavor.handoff_url(recent)
+    }
+
+    fn command(&self) -> Command {
+        self.flavor.command()
+    }
+
+    fn open_command(&self) -> Command {
+        let mut cmd = self.flavor.command();
+        cmd.args(&self.args);
+        cmd
+    }
+}
+
+/// A flavor run with a custom `--user-data-dir`, which then holds its configuration
+///
+/// The argument is passed to every command, so that the editor uses the same state that is read.
+#[derive(Debug)]
+pub struct WithUserDataDir<F> {
+    /// The wrapped flavor, whose configuration directory should be `dir`
+    pub flavor: F,
+    /// The user data directory
+    pub dir: PathBuf,
+}
+
+impl<F: EditorFlavor> EditorFlavor for WithUserDataDir<F> {
+    fn cmd(&self) -> &str {
+        self.flavor.cmd()
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        self.flavor.config_dir()
+    }
+
+    fn marketplace(&self) -> Marketplace {
+        self.flavor.marketplace()
+    }
+
+    fn state_db(&self) -> Option<PathBuf> {
+        self.flavor.state_db()
+    }
+
+    fn name(&self) -> &str {
+        self.flavor.name()
+    }
+
+    fn is_installed(&self) -> bool {
+        self.flavor.is_installed()
+    }
+
+    fn handoff_url(&self, recent: &Recent) -> Result<Option<Url>> {
+        self.flavor.handoff_url(recent)
+    }
+
+    fn command(&self) -> Command {
+        let mut cmd = self.flavor.command();
+        cmd.arg("--user-data-dir").arg(&self.dir);
+        cmd
+    }
+
+    fn open_command(&self) -> Command {
+        let mut cmd = self.flavor.open_command();
+        cmd.arg("--user-data-dir").arg(&self.dir);
+        cmd
+    }
+}
+
+/// A flavor whose recent items are the ones of a profile, opening them with this profile
+///
+/// Each profile keeps its own state database, see [profiles::profile_state_db].
+#[derive(Debug)]
+pub struct InProfile<F> {
+    /// The wrapped flavor
+    pub flavor: F,
+    /// The profile
+    pub profile: Profile,
+    /// Name of the flavor followed by the one of the profile
+    name: String,
+}
+
+impl<F: EditorFlavor> InProfile<F> {
+    /// Use the `profile` of `flavor`, named after the flavor `flavor_name`
+    pub fn new(flavor: F, profile: Profile, flavor_name: &str) -> Self {
+        let name = format!("{}: {}", flavor_name, profile.name);
+        Self {
+            flavor,
+            profile,
+            name,
+        }
+    }
+}
+
+impl<F: EditorFlavor> EditorFlavor for InProfile<F> {
+    fn cmd(&self) -> &str {
+        self.flavor.cmd()
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        self.flavor.config_dir()
+    }
+
+    fn marketplace(&self) -> Marketplace {
+        self.flavor.marketplace()
+    }
+
+    fn state_db(&self) -> Option<PathBuf> {
+        self.config_dir()
+            .map(|d| profiles::profile_state_db(&d, &self.profile))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_installed(&self) -> bool {
+        self.flavor.is_installed()
+    }
+
+    fn handoff_url(&self, recent: &Recent) -> Result<Option<Url>> {
+        self.flavor.handoff_url(recent)
+    }
+
+    fn command(&self) -> Command {
+        self.flavor.command()
+    }
+
+    fn open_command(&self) -> Command {
+        let mut cmd = self.flavor.open_command();
+        cmd.arg("--profile").arg(&self.profile.name);
+        cmd
     }
 }
 
 impl FromStr for Flavor {
-    type Err = anyhow::Error;
+    type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_ref() {
@@ -135,7 +851,9 @@ impl FromStr for Flavor {
             "code-insiders" => Ok(Self::CodeInsiders),
             "code-oss" => Ok(Self::CodeOSS),
             "vscodium" | "codium" => Ok(Self::VSCodium),
-            _ => Err(anyhow!("\"{}\" does not match any VSCode flavor", s)),
+            "cursor" => Ok(Self::Cursor),
+            "windsurf" => Ok(Self::Windsurf),
+            _ => Err(other!("\"{}\" does not match any VSCode flavor", s)),
         }
     }
 }
@@ -146,27 +864,50 @@ impl FromStr for Flavor {
 /// - [Workspaces History Main Service](https://github.com/microsoft/vscode/blob/main/src/vs/platform/workspaces/electron-main/workspacesHistoryMainService.ts)
 /// - [workspaces common definitions](https://github.com/microsoft/vscode/blob/main/src/vs/platform/workspaces/common/workspaces.ts)
 pub mod workspaces {
-    use super::{open_state_db, tildify, Flavor, SCHEME_FILE};
+    use super::{
+        file_path_to_url, file_url_to_path, icons, is_busy, jsonc, open_db, read_db, storage_json,
+        tildify, untildify, workspace_storage::LastOpened, EditorFlavor, DB_WRITE_TIMEOUT,
+        SCHEME_FILE, SCHEME_REMOTE, SCHEME_VIRTUAL,
+    };
     use std::{
         borrow::Cow,
+        cmp::Reverse,
+        collections::HashSet,
         fmt::{self, Display},
+        fs,
+        io::Write,
         path::{Path, PathBuf},
+        str::FromStr,
+        time::{SystemTime, UNIX_EPOCH},
     };
 
-    use anyhow::{anyhow, Context};
-    use rusqlite::{params, OpenFlags};
+    use crate::utils::{fuzzy_score, matches_wildcard, md5_hex};
+    use crate::{
+        config,
+        error::{other, Context, Error, Result},
+        logging::{debug, span},
+        remote,
+        stats::UsageStats,
+    };
+    use percent_encoding::percent_decode_str;
+    use rusqlite::{params, OpenFlags, OptionalExtension, TransactionBehavior};
     use serde::{Deserialize, Serialize};
     use serde_json::{json, Value};
     use url::Url;
 
     const VSCDB_HISTORY_KEY: &str = "history.recentlyOpenedPathsList";
+    const ENV_PRUNE_ON_WRITE: &str = "ROFI_VSCODE_PRUNE_ON_WRITE";
+    /// Number of backups of the history that are kept, the older ones are removed
+    const MAX_HISTORY_BACKUPS: usize = 20;
+    /// Prefix of the remote authority of items located in a WSL distribution
+    const WSL_AUTHORITY_PREFIX: &str = "wsl+";
 
     /// Identifies a multi-root Workspace
     ///
     /// The workspace has an associated `<name>.code-workspace` config file, which represented in the [`Self::config_path`].
     ///
     /// See [this documentation article](https://code.visualstudio.com/docs/editor/workspaces) for reference.
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     #[serde(rename_all = "camelCase")]
     pub struct WorkspaceIdentifier {
         /// Unique identifier of the workspace
@@ -218,7 +959,7 @@ pub mod workspaces {
     /// ```
     ///
     /// We currently support only local paths via [Self::file_path].
-    #[derive(Serialize, Deserialize, Debug)]
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     #[serde(untagged)]
     pub enum Recent {
         /// A multi-root workspace
@@ -252,7 +993,112 @@ pub mod workspaces {
         },
     }
 
+    /// The kind of a recent item
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum RecentKind {
+        /// A multi-root workspace
+        Workspace,
+        /// A workspace with a single folder
+        Folder,
+        /// A single file
+        File,
+    }
+
+    impl FromStr for RecentKind {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.trim().to_lowercase().as_str() {
+                "workspace" | "workspaces" => Ok(Self::Workspace),
+                "folder" | "folders" => Ok(Self::Folder),
+                "file" | "files" => Ok(Self::File),
+                _ => Err(other!("Unrecognized kind of recent item {}", s)),
+            }
+        }
+    }
+
+    impl Display for RecentKind {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::Workspace => write!(f, "workspace"),
+                Self::Folder => write!(f, "folder"),
+                Self::File => write!(f, "file"),
+            }
+        }
+    }
+
     impl Recent {
+        /// Create an item from a local path or a URI, as VSCode would record it when opened
+        ///
+        /// Unless `kind` is given, `.code-workspace` files are taken as workspaces,
+        /// and the other local paths as folders or files depending on what they point to.
+        /// Remote URIs are taken as folders.
+        pub fn from_target(target: &str, kind: Option<RecentKind>) -> Result<Self> {
+            let url = match Url::parse(target) {
+                // Skip Windows drive letters, which are parsed as schemes
+                Ok(url) if url.scheme().len() > 1 => url,
+                _ => {
+                    let path = std::path::absolute(untildify(target))?;
+                    if !path.exists() {
+                        return Err(other!("Path {:?} does not exist", path));
+                    }
+                    file_path_to_url(&path)
+                        .ok_or_else(|| other!("Could not convert path {:?} to URI", path))?
+                }
+            };
+            let kind = kind.unwrap_or_else(|| match file_url_to_path(&url) {
+                _ if url.path().ends_with(".code-workspace") => RecentKind::Workspace,
+                Some(path) if path.is_file() => RecentKind::File,
+                _ => RecentKind::Folder,
+            });
+            // The host is kept percent-encoded, while VSCode stores the authority decoded
+            let remote_authority = match url.scheme() {
+                "vscode-remote" => url
+                    .host_str()
+                    .map(|h| percent_decode_str(h).decode_utf8_lossy().into_owned()),
+                _ => None,
+            };
+            Ok(match kind {
+                RecentKind::Workspace => {
+                    // Same as `getWorkspaceIdentifier`, which ignores the case everywhere but on Linux
+                    let id = match file_url_to_path(&url) {
+                        Some(path) if cfg!(not(target_os = "linux")) => {
+                            md5_hex(path.to_string_lossy().to_lowercase().as_bytes())
+                        }
+                        Some(path) => md5_hex(path.as_os_str().as_encoded_bytes()),
+                        None => md5_hex(url.as_str().as_bytes()),
+                    };
+                    Self::Workspace {
+                        workspace: WorkspaceIdentifier {
+                            id,
+                            config_path: url,
+                        },
+                        label: None,
+                        remote_authority,
+                    }
+                }
+                RecentKind::Folder => Self::Folder {
+                    folder_uri: url,
+                    label: None,
+                    remote_authority,
+                },
+                RecentKind::File => Self::File {
+                    file_uri: url,
+                    label: None,
+                    remote_authority,
+                },
+            })
+        }
+
+        /// Returns the kind of the item
+        pub fn kind(&self) -> RecentKind {
+            match self {
+                Self::Workspace { .. } => RecentKind::Workspace,
+                Self::Folder { .. } => RecentKind::Folder,
+                Self::File { .. } => RecentKind::File,
+            }
+        }
+
         /// Locates the item in a local or remote filesystem
         pub fn url(&self) -> &Url {
             match self {
@@ -276,7 +1122,12 @@ pub mod workspaces {
 
         /// Tells whether the item is local
         pub fn is_local(&self) -> bool {
-            return self.url().scheme() == SCHEME_FILE;
+            self.url().scheme() == SCHEME_FILE
+        }
+
+        /// Tells whether the item is in a virtual filesystem, e.g. a GitHub repository opened without cloning it
+        pub fn is_virtual(&self) -> bool {
+            self.url().scheme() == SCHEME_VIRTUAL
         }
 
         /// Returns the remote where this item is located, if any
@@ -300,18 +1151,73 @@ pub mod workspaces {
             }
         }
 
-        /// Returns the local file path that can be used to open this recent item
+        /// Returns the local file path that can be used to open this recent item
+        ///
+        /// # Errors
+        /// The call will fail if the URL's scheme is other than `file://`, or if the URL path is not a valid system path.
+        pub fn file_path(&self) -> Result<PathBuf> {
+            let url = self.url();
+            match url.scheme() {
+                SCHEME_FILE => file_url_to_path(url)
+                    .ok_or_else(|| other!("Could not get path from file url {}", url)),
+                _ => Err(Error::NotLocal(url.clone())),
+            }
+        }
+
+        /// Returns the local file path with the symlinks resolved
+        ///
+        /// The path is returned as-is if it cannot be resolved, e.g. because it does not exist anymore.
+        ///
+        /// # Errors
+        /// The call fails in the same cases as [Self::file_path].
+        pub fn canonical_path(&self) -> Result<PathBuf> {
+            self.file_path().map(|p| canonical_path(&p))
+        }
+
+        /// The optional label of the item
+        fn label_mut(&mut self) -> &mut Option<String> {
+            match self {
+                Self::Workspace { label, .. } => label,
+                Self::Folder { label, .. } => label,
+                Self::File { label, .. } => label,
+            }
+        }
+
+        /// Tells whether the item is designated by `target`
         ///
-        /// # Errors
-        /// The call will fail if the URL's scheme is other than `file://`, or if the URL path is not a valid system path.
-        pub fn file_path(&self) -> anyhow::Result<PathBuf> {
-            let url = self.url();
-            match url.scheme() {
-                SCHEME_FILE => url
-                    .to_file_path()
-                    .map_err(|_| anyhow!("Could not get path from file url {}", url)),
-                _ => Err(anyhow!("Not a file url {}", url)),
-            }
+        /// The target can be the label of the item, its URI, or its local path, possibly starting with `~`.
+        pub fn matches_target(&self, target: &str) -> bool {
+            self.url().as_str() == target
+                || self.label().is_ok_and(|l| l == target)
+                || self.file_path().is_ok_and(|p| p == untildify(target))
+        }
+
+        /// Tells whether the label, the URI or the local path of the item match the wildcard `pattern`
+        ///
+        /// Paths in the pattern may start with `~`, see [matches_wildcard] for the syntax.
+        pub fn matches_pattern(&self, pattern: &str) -> bool {
+            let path_pattern = untildify(pattern);
+            matches_wildcard(pattern, self.url().as_str())
+                || self.label().is_ok_and(|l| matches_wildcard(pattern, &l))
+                || self.file_path().is_ok_and(|p| {
+                    matches_wildcard(&path_pattern.to_string_lossy(), &p.to_string_lossy())
+                })
+        }
+
+        /// Tells whether the item is local and its path does not exist anymore
+        pub fn is_stale(&self) -> bool {
+            self.file_path().is_ok_and(|p| !p.exists())
+        }
+
+        /// Tells whether the item is a local path under the `root` directory
+        pub fn is_within(&self, root: &Path) -> bool {
+            self.file_path().is_ok_and(|p| p.starts_with(root))
+        }
+
+        /// Returns the WSL distribution where this item is located, if any
+        pub fn wsl_distro(&self) -> Option<&str> {
+            self.remote()
+                .and_then(|r| r.strip_prefix(WSL_AUTHORITY_PREFIX))
         }
 
         /// Returns a displayable label
@@ -319,10 +1225,14 @@ pub mod workspaces {
         /// If the `label` field is assigned it will be returned as-is.
         /// Otherwise, the label will be computed by turning the `file://`
         /// URL to a path and replacing the `$HOME` prefix with `~`.
+        /// Remote items are labeled with their remote, like VSCode does,
+        /// e.g. `{path} [WSL: {distro}]` or `{path} [SSH: {host}]`, see [crate::remote::describe].
+        /// GitHub repositories opened as virtual workspaces are labeled `{owner}/{repo} (GitHub)`.
         ///
         /// # Errors
-        /// The call will fail if the URL has a scheme other than `file://` or if the URL path is not a valid system path.
-        pub fn label(&self) -> anyhow::Result<Cow<str>> {
+        /// The call will fail if the URL has a scheme other than `file://`, `vscode-remote://` or a GitHub `vscode-vfs://`,
+        /// or if the URL path is not a valid system path.
+        pub fn label(&self) -> Result<Cow<'_, str>> {
             match self {
                 Recent::Workspace {
                     workspace: _,
@@ -334,7 +1244,7 @@ pub mod workspaces {
                         .as_ref()
                         .map(Cow::from)
                         .ok_or(())
-                        .or_else(|_| Ok(Cow::from(tildify(&self.file_path()?))))
+                        .or_else(|_| self.forge_label().map(Cow::from))
                 }
                 Recent::Folder {
                     folder_uri: _,
@@ -346,7 +1256,7 @@ pub mod workspaces {
                         .as_ref()
                         .map(Cow::from)
                         .ok_or(())
-                        .or_else(|_| Ok(Cow::from(tildify(&self.file_path()?))))
+                        .or_else(|_| self.forge_label().map(Cow::from))
                 }
                 Recent::File {
                     file_uri: _,
@@ -358,17 +1268,47 @@ pub mod workspaces {
                         .as_ref()
                         .map(Cow::from)
                         .ok_or(())
-                        .or_else(|_| Ok(Cow::from(tildify(&self.file_path()?))))
+                        .or_else(|_| self.forge_label().map(Cow::from))
+                }
+            }
+        }
+
+        /// Compute a label from the URL, when none is given
+        fn forge_label(&self) -> Result<String> {
+            if let Some(location) = remote::github_location(self.url()) {
+                return Ok(format!("{} (GitHub)", location.display()));
+            }
+            match self.remote() {
+                Some(authority) if self.url().scheme() == SCHEME_REMOTE => {
+                    let path = percent_decode_str(self.url().path()).decode_utf8_lossy();
+                    Ok(format!("{} [{}]", path, remote::describe(authority)))
                 }
+                _ => Ok(tildify(&self.file_path()?)),
             }
         }
 
+        /// Tells whether the item is a workspace or folder in a GitHub repository opened as a virtual workspace
+        ///
+        /// Files keep the icons of their type.
+        fn is_github_repo(&self) -> bool {
+            self.kind() != RecentKind::File && remote::github_location(self.url()).is_some()
+        }
+
         /// Name of the icon to display from the icon theme
         ///
-        /// This name can be used to query the icon from the icon theme
+        /// This name can be used to query the icon from the icon theme.
+        /// Items located in a WSL distribution share the same icon.
+        /// Local folders get the icon of their kind, e.g. `folder-git` for repositories,
+        /// remote ones `folder-remote`, GitHub repositories `github`, and files the icon of their MIME type, see [icons].
         ///
         /// See the [Freedesktop documentation](https://specifications.freedesktop.org/icon-naming-spec/latest/ar01s04.html)
         pub fn icon_name(&self) -> &str {
+            if self.wsl_distro().is_some() {
+                return crate::wsl::WSL_ICON_NAME;
+            }
+            if self.is_github_repo() {
+                return icons::GITHUB_ICON_NAME;
+            }
             match self {
                 Self::Workspace {
                     workspace: _,
@@ -379,12 +1319,15 @@ pub mod workspaces {
                     folder_uri: _,
                     label: _,
                     remote_authority: _,
-                } => "folder",
+                } => match self.file_path() {
+                    Ok(path) => icons::folder_icon_name(&path),
+                    Err(_) => icons::REMOTE_FOLDER_ICON_NAME,
+                },
                 Self::File {
-                    file_uri: _,
+                    file_uri,
                     label: _,
                     remote_authority: _,
-                } => "text-x-generic",
+                } => icons::file_icon_name(Path::new(file_uri.path())),
             }
         }
 
@@ -392,6 +1335,12 @@ pub mod workspaces {
         ///
         /// See the [Nerd Fonts Cheat Sheet](https://www.nerdfonts.com/cheat-sheet)
         pub fn nerd_icon(&self) -> &str {
+            if self.wsl_distro().is_some() {
+                return crate::wsl::WSL_NERD_ICON;
+            }
+            if self.is_github_repo() {
+                return "\u{f09b}";
+            }
             match self {
                 Self::Workspace {
                     workspace: _,
@@ -426,34 +1375,37 @@ pub mod workspaces {
     ///
     /// # Warning
     /// Workspaces that fail to deserialize to known data structures will be ignored.
+    /// They are not lost when the entries are stored back, see [with_unknown_entries].
     ///
     /// The entries will be looked up from VSCode's global storage inside the given `config_dir` configuration directory
-    fn get_history_entries(config_dir: &Path, local_only: bool) -> anyhow::Result<Vec<Recent>> {
+    fn get_history_entries(db_path: &Path, local_only: bool) -> Result<Vec<Recent>> {
         // Reference from `restoreRecentlyOpened` in
         // https://github.com/microsoft/vscode/blob/main/src/vs/platform/workspaces/common/workspaces.ts
 
-        // Open the DB
-        let open_flags = Some(OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX);
-        let conn = open_state_db(config_dir, open_flags)?;
-
         // Retrieve the JSON value of the property
-        let res: Value = conn
-            .query_row(
+        let res: Option<Value> = read_db(db_path, |conn| {
+            conn.query_row(
                 "SELECT value FROM ItemTable WHERE key = (?)",
                 [VSCDB_HISTORY_KEY],
                 |r| r.get(0),
             )
-            .with_context(|| {
-                format!(
-                    "Could not retrieve key \"{}\" from state DB as JSON",
-                    VSCDB_HISTORY_KEY
-                )
-            })?;
+            .optional()
+        })
+        .with_context(|| {
+            format!(
+                "Could not retrieve key \"{}\" from state DB as JSON",
+                VSCDB_HISTORY_KEY
+            )
+        })?;
+        // The key is missing on a fresh profile
+        let Some(res) = res else {
+            return Ok(Vec::new());
+        };
 
         // Deserialize the JSON array to our datatypes
         let entries = res["entries"]
             .as_array()
-            .ok_or_else(|| anyhow!("History object's \"entries\" attribute is not an array"))?;
+            .ok_or_else(|| other!("History object's \"entries\" attribute is not an array"))?;
 
         let filter: fn(&Recent) -> bool = match local_only {
             false => |_| true,
@@ -468,27 +1420,264 @@ pub mod workspaces {
         Ok(entries)
     }
 
+    /// The value of the history to store in place of the `previous` one, with the given `entries`
+    ///
+    /// Only the `entries` attribute is replaced, keeping any other attribute of the previous object.
+    /// Unless `keep_unknown` is unset, the previous entries that could not be read are kept, see [with_unknown_entries].
+    pub(super) fn updated_history(
+        previous: Option<Value>,
+        entries: &[Recent],
+        keep_unknown: bool,
+    ) -> serde_json::Result<Value> {
+        let entries = match keep_unknown {
+            true => with_unknown_entries(previous.as_ref(), entries)?,
+            false => entries
+                .iter()
+                .map(serde_json::to_value)
+                .collect::<serde_json::Result<Vec<_>>>()?,
+        };
+        Ok(match previous {
+            Some(Value::Object(mut object)) => {
+                object.insert("entries".to_string(), Value::Array(entries));
+                Value::Object(object)
+            }
+            _ => json!({ "entries": entries }),
+        })
+    }
+
+    /// Insert the previous entries that are not known to this crate back among the `entries` to store
+    ///
+    /// Each one is put back right after the known entry that preceded it in the `previous` value of the history,
+    /// or the closest one before it that is still stored, or else first,
+    /// so that entries VSCode still understands are not lost when the list is modified.
+    fn with_unknown_entries(
+        previous: Option<&Value>,
+        entries: &[Recent],
+    ) -> serde_json::Result<Vec<Value>> {
+        let previous = previous
+            .and_then(|p| p.get("entries"))
+            .and_then(Value::as_array);
+        // The unknown entries placed first, then those following each of the `entries`
+        let mut leading: Vec<Value> = Vec::new();
+        let mut following: Vec<Vec<Value>> = vec![Vec::new(); entries.len()];
+        let mut anchor: Option<usize> = None;
+        for entry in previous.into_iter().flatten() {
+            match serde_json::from_value::<Recent>(entry.to_owned()) {
+                Ok(known) => {
+                    if let Some(i) = entries.iter().position(|e| e.url() == known.url()) {
+                        anchor = Some(i);
+                    }
+                }
+                Err(_) => {
+                    debug!("Keeping unknown recent item {}", entry);
+                    match anchor {
+                        Some(i) => following[i].push(entry.to_owned()),
+                        None => leading.push(entry.to_owned()),
+                    }
+                }
+            }
+        }
+        let mut values = leading;
+        for (entry, unknown) in entries.iter().zip(following) {
+            values.push(serde_json::to_value(entry)?);
+            values.extend(unknown);
+        }
+        Ok(values)
+    }
+
     /// Store the workspaces into VSCode's state
     ///
     /// Performs the reverse operation of [get_history_entries],
     /// see its documentation for details.
-    fn store_history_entries(config_dir: &Path, entries: &[Recent]) -> anyhow::Result<()> {
+    /// Unless `keep_unknown` is unset, the previous entries that could not be read are kept.
+    ///
+    /// The previous value is saved with [backup_history] before being replaced,
+    /// and the whole operation runs in a transaction.
+    fn store_history_entries(db_path: &Path, entries: &[Recent], keep_unknown: bool) -> Result<()> {
+        let _span = span!("Storing {} recent items into {:?}", entries.len(), db_path);
         // Open DB
         let open_flags = Some(OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX);
-        let conn = open_state_db(config_dir, open_flags)?;
+        let mut conn = open_db(db_path, open_flags)?;
+        // Wait for VSCode to release its lock
+        conn.busy_timeout(DB_WRITE_TIMEOUT)
+            .with_context(|| "Could not set busy timeout")?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| match is_busy(&e) {
+                true => Error::DbLocked(db_path.to_owned()),
+                false => Error::from(e).context("Could not start transaction on state DB"),
+            })?;
 
-        // Serialize to JSON
-        let value = json!({
-            "entries": entries,
-        });
+        // Back up the previous value
+        let previous: Option<String> = tx
+            .query_row(
+                "SELECT value FROM ItemTable WHERE key = (?)",
+                [VSCDB_HISTORY_KEY],
+                |r| r.get(0),
+            )
+            .optional()
+            .with_context(|| "Could not read state from DB")?;
+        if let Some(previous) = &previous {
+            backup_history(&backup_dir()?, previous)?;
+        }
 
-        // Update DB
-        conn.execute(
-            "UPDATE ItemTable SET value = (?2) WHERE key = (?1)",
-            params![VSCDB_HISTORY_KEY, value],
-        )
-        .with_context(|| "Could not update state in DB")
-        .map(|_| ())
+        // Serialize to JSON, replacing only the entries of the previous value
+        let previous: Option<Value> = previous.and_then(|p| serde_json::from_str(&p).ok());
+        let value = updated_history(previous, entries, keep_unknown)?;
+
+        // Update DB, inserting the key if it is missing
+        let updated = tx
+            .execute(
+                "INSERT INTO ItemTable (key, value) VALUES (?1, ?2)
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                params![VSCDB_HISTORY_KEY, value],
+            )
+            .with_context(|| "Could not update state in DB")?;
+        if updated != 1 {
+            return Err(other!(
+                "Could not store key \"{}\" in state DB",
+                VSCDB_HISTORY_KEY
+            ));
+        }
+        tx.commit().with_context(|| "Could not commit state to DB")
+    }
+
+    /// Directory where the backups of the history are kept, `$XDG_STATE_HOME/rofi-vscode-mode/`
+    ///
+    /// The tests use a temporary directory instead, to leave the user's backups alone.
+    fn backup_dir() -> Result<PathBuf> {
+        if cfg!(test) {
+            return Ok(std::env::temp_dir()
+                .join(format!("rofi-vscode-mode-backups-{}", std::process::id())));
+        }
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|d| d.join("rofi-vscode-mode"))
+            .ok_or_else(|| other!("Could not find a directory to back up the history"))
+    }
+
+    /// Save a value of the history to a timestamped file in `dir`, so that deleted entries can be recovered
+    ///
+    /// Only the last [MAX_HISTORY_BACKUPS] backups are kept, since the history may be stored very often,
+    /// e.g. by `vscode-recent touch` from a shell hook.
+    fn backup_history(dir: &Path, value: &str) -> Result<PathBuf> {
+        fs::create_dir_all(dir).with_context(|| format!("Could not create {:?}", dir))?;
+
+        // Backups are told apart and ordered by their timestamp, which must be past the last one
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let last = history_backups(dir)?.last().map(|(t, _)| t + 1);
+        let mut timestamp = last.map_or(now, |last| last.max(now));
+        let path = loop {
+            let path = dir.join(format!("history-{}.json", timestamp));
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    file.write_all(value.as_bytes())
+                        .with_context(|| format!("Could not back up history to {:?}", path))?;
+                    break path;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => timestamp += 1,
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Could not back up history to {:?}", path))
+                }
+            }
+        };
+        debug!("Backed up the previous history to {:?}", path);
+        if let Err(e) = prune_backups(dir, MAX_HISTORY_BACKUPS) {
+            debug!("Could not remove the old backups: {:?}", e);
+        }
+        Ok(path)
+    }
+
+    /// The backups of the history in `dir`, with their timestamps, from the oldest
+    fn history_backups(dir: &Path) -> Result<Vec<(u128, PathBuf)>> {
+        let mut backups: Vec<(u128, PathBuf)> = fs::read_dir(dir)
+            .with_context(|| format!("Could not list {:?}", dir))?
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name();
+                let timestamp = name
+                    .to_str()?
+                    .strip_prefix("history-")?
+                    .strip_suffix(".json")?
+                    .parse()
+                    .ok()?;
+                Some((timestamp, e.path()))
+            })
+            .collect();
+        backups.sort_unstable();
+        Ok(backups)
+    }
+
+    /// Remove the backups of the history in `dir` but the `keep` most recent ones
+    fn prune_backups(dir: &Path, keep: usize) -> Result<()> {
+        let backups = history_backups(dir)?;
+        let excess = backups.len().saturating_sub(keep);
+        for (_, path) in &backups[..excess] {
+            fs::remove_file(path).with_context(|| format!("Could not remove {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    /// Where the recent items of a flavor are stored
+    enum HistoryStorage {
+        /// The `state.vscdb` database of current versions
+        StateDb(PathBuf),
+        /// The `storage.json` file of older versions
+        StorageJson(PathBuf),
+    }
+
+    /// Find where the recent items of a flavor are stored
+    ///
+    /// The state database is preferred, falling back to `storage.json` when it does not exist.
+    fn history_storage(flavor: &dyn EditorFlavor) -> Result<HistoryStorage> {
+        let db_path = flavor.state_db();
+        if let Some(db_path) = db_path.as_ref().filter(|p| p.exists()) {
+            return Ok(HistoryStorage::StateDb(db_path.to_owned()));
+        }
+        if let Some(path) = flavor
+            .config_dir()
+            .and_then(|d| storage_json::storage_json_path(&d))
+        {
+            debug!("No state database for {}, using {:?}", flavor.name(), path);
+            return Ok(HistoryStorage::StorageJson(path));
+        }
+        db_path
+            .map(HistoryStorage::StateDb)
+            .ok_or_else(|| Error::DbNotFound(flavor.name().to_string()))
+    }
+
+    /// Where the recent items of `flavor` are stored, and whether a list of recent items is stored there
+    ///
+    /// The list is missing on a fresh profile, until VSCode has opened something.
+    ///
+    /// # Errors
+    /// The call fails if neither the state database nor `storage.json` is found, or if they cannot be read.
+    pub fn history_location(flavor: &dyn EditorFlavor) -> Result<(PathBuf, bool)> {
+        match history_storage(flavor)? {
+            HistoryStorage::StateDb(path) => {
+                let found = read_db(&path, |conn| {
+                    conn.query_row(
+                        "SELECT 1 FROM ItemTable WHERE key = (?)",
+                        [VSCDB_HISTORY_KEY],
+                        |_| Ok(()),
+                    )
+                    .optional()
+                })?;
+                Ok((path, found.is_some()))
+            }
+            HistoryStorage::StorageJson(path) => {
+                let found = storage_json::has_history(&path)?;
+                Ok((path, found))
+            }
+        }
     }
 
     /// Get recently opened workspaces, files and folders
@@ -504,31 +1693,613 @@ pub mod workspaces {
     ///
     /// The entries will be looked up from VSCode's global storage
     pub fn recently_opened_from_storage(
-        flavor: &Flavor,
+        flavor: &dyn EditorFlavor,
         local_only: bool,
-    ) -> anyhow::Result<Vec<Recent>> {
-        let config_dir = flavor.config_dir().ok_or_else(|| {
-            anyhow!(
-                "Could not find configuration directory for \"{:?}\"",
-                flavor
-            )
-        })?;
-        get_history_entries(&config_dir, local_only)
+    ) -> Result<Vec<Recent>> {
+        match history_storage(flavor)? {
+            HistoryStorage::StateDb(path) => get_history_entries(&path, local_only),
+            HistoryStorage::StorageJson(path) => storage_json::read_history(&path, local_only),
+        }
+    }
+
+    /// Store the workspaces into VSCode's state
+    ///
+    /// Performs the reverse operation of [recently_opened_from_storage],
+    /// see its documentation for details.
+    /// The previous entries that could not be read are kept among the new ones.
+    ///
+    /// If the `ROFI_VSCODE_PRUNE_ON_WRITE` setting is enabled, the [stale](Recent::is_stale) entries are dropped,
+    /// so that any change also cleans up VSCode's own list.
+    pub fn store_recently_opened(flavor: &dyn EditorFlavor, entries: &[Recent]) -> Result<()> {
+        if !config::flag(ENV_PRUNE_ON_WRITE) {
+            return store_history(flavor, entries, true);
+        }
+        let kept: Vec<Recent> = entries.iter().filter(|r| !r.is_stale()).cloned().collect();
+        if kept.len() < entries.len() {
+            debug!("Dropping {} stale items", entries.len() - kept.len());
+        }
+        store_history(flavor, &kept, true)
+    }
+
+    /// Store the workspaces into VSCode's state, keeping the previous unknown entries if `keep_unknown` is set
+    fn store_history(
+        flavor: &dyn EditorFlavor,
+        entries: &[Recent],
+        keep_unknown: bool,
+    ) -> Result<()> {
+        match history_storage(flavor)? {
+            HistoryStorage::StateDb(path) => store_history_entries(&path, entries, keep_unknown),
+            HistoryStorage::StorageJson(path) => {
+                storage_json::write_history(&path, entries, keep_unknown)
+            }
+        }
+    }
+
+    /// Get the recent items of several flavors, merged into a single list
+    ///
+    /// Each item is returned with the index of the flavor it comes from in `flavors`,
+    /// see [merge_recents] for how the lists are merged.
+    pub fn recently_opened_from_flavors(
+        flavors: &[Box<dyn EditorFlavor>],
+        local_only: bool,
+    ) -> Result<Vec<(usize, Recent)>> {
+        let lists = flavors
+            .iter()
+            .map(|f| recently_opened_from_storage(f.as_ref(), local_only))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(merge_recents(lists))
+    }
+
+    /// Merge several lists of recent items, tagging each item with the index of its list
+    ///
+    /// Since the items are not timestamped, the lists are interleaved by the rank of each item.
+    /// Items with the same URL are kept only once, with the best rank.
+    pub fn merge_recents(lists: Vec<Vec<Recent>>) -> Vec<(usize, Recent)> {
+        let mut iters: Vec<_> = lists.into_iter().map(|l| l.into_iter()).collect();
+        let mut merged: Vec<(usize, Recent)> = Vec::new();
+        loop {
+            let mut exhausted = true;
+            for (i, iter) in iters.iter_mut().enumerate() {
+                if let Some(recent) = iter.next() {
+                    exhausted = false;
+                    if !merged.iter().any(|(_, r)| r.url() == recent.url()) {
+                        merged.push((i, recent));
+                    }
+                }
+            }
+            if exhausted {
+                return merged;
+            }
+        }
+    }
+
+    /// Which entry is kept when a folder is also part of a workspace
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum DedupPreference {
+        /// Keep the workspace, hiding the folders it contains
+        Workspace,
+        /// Keep the folders, hiding the workspaces made only of listed folders
+        Folder,
+    }
+
+    impl FromStr for DedupPreference {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.to_lowercase().as_str() {
+                "workspace" | "workspaces" => Ok(Self::Workspace),
+                "folder" | "folders" => Ok(Self::Folder),
+                _ => Err(other!("Unrecognized deduplication preference {}", s)),
+            }
+        }
+    }
+
+    /// Read the definitions of the folders from the `.code-workspace` file at `config_path`
+    ///
+    /// Each folder has either a `path` or a `uri`, and optionally a `name`.
+    fn read_workspace_folders(config_path: &Path) -> Result<Vec<Value>> {
+        let contents = fs::read_to_string(config_path)
+            .with_context(|| format!("Could not read {:?}", config_path))?;
+        let config: Value = jsonc::from_str(&contents)
+            .with_context(|| format!("Could not parse {:?}", config_path))?;
+        Ok(config
+            .get("folders")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /// Get the folders of the multi-root workspace defined in the `.code-workspace` file at `config_path`
+    ///
+    /// Relative paths are resolved against the directory of the file,
+    /// folders given by URI instead of path are skipped.
+    pub fn workspace_folders(config_path: &Path) -> Result<Vec<PathBuf>> {
+        let root = config_path.parent().unwrap_or(Path::new("/"));
+        Ok(read_workspace_folders(config_path)?
+            .iter()
+            .filter_map(|f| f.get("path").and_then(Value::as_str))
+            .map(|p| root.join(p))
+            .collect())
+    }
+
+    /// Get the names of the folders of the multi-root workspace defined in the `.code-workspace` file at `config_path`
+    ///
+    /// Like VSCode, each folder is named after its `name` attribute if given,
+    /// otherwise after the last component of its path or URI.
+    pub fn workspace_folder_names(config_path: &Path) -> Result<Vec<String>> {
+        Ok(read_workspace_folders(config_path)?
+            .iter()
+            .filter_map(|f| {
+                if let Some(name) = f.get("name").and_then(Value::as_str) {
+                    return Some(name.to_string());
+                }
+                let location = f
+                    .get("path")
+                    .or_else(|| f.get("uri"))
+                    .and_then(Value::as_str)?;
+                let location = location.trim_end_matches(['/', '\\']);
+                let name = location.rsplit(['/', '\\']).next().unwrap_or(location);
+                Some(percent_decode_str(name).decode_utf8_lossy().into_owned())
+            })
+            .collect())
+    }
+
+    /// Resolve symlinks in a local path, leaving it as-is if it cannot be resolved
+    fn canonical_path(path: &Path) -> PathBuf {
+        fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    /// Resolve the symlinks in the paths of local entries, collapsing those that point to the same item
+    ///
+    /// Entries without an explicit label are labeled after their resolved path.
+    /// Their URL is left untouched, so that they can still be removed from the history.
+    pub fn resolve_symlinks(entries: Vec<(usize, Recent)>) -> Vec<(usize, Recent)> {
+        let mut seen = HashSet::new();
+        entries
+            .into_iter()
+            .filter_map(|(i, mut recent)| {
+                if let Ok(path) = recent.canonical_path() {
+                    if recent.file_path().is_ok_and(|p| p != path) {
+                        let label = recent.label_mut();
+                        if label.is_none() {
+                            *label = Some(tildify(&path));
+                        }
+                    }
+                    if !seen.insert((recent.kind(), path)) {
+                        return None;
+                    }
+                }
+                Some((i, recent))
+            })
+            .collect()
+    }
+
+    /// Collapse the entries that point to the same directory
+    ///
+    /// Local folders and workspaces reached through different (e.g. symlinked) paths are kept only once,
+    /// with the best rank. Then folders that are also part of a workspace are collapsed according to `preference`.
+    /// Files and remote items are left untouched.
+    pub fn dedup_recents(
+        entries: Vec<(usize, Recent)>,
+        preference: DedupPreference,
+    ) -> Vec<(usize, Recent)> {
+        // Canonical path of each local entry, and of the folders of each workspace
+        let paths: Vec<Option<PathBuf>> = entries
+            .iter()
+            .map(|(_, r)| match r {
+                Recent::File { .. } => None,
+                _ => r.file_path().ok().map(|p| canonical_path(&p)),
+            })
+            .collect();
+        let folders: Vec<Vec<PathBuf>> = entries
+            .iter()
+            .zip(&paths)
+            .map(|((_, r), path)| match (r, path) {
+                (Recent::Workspace { .. }, Some(path)) => workspace_folders(path)
+                    .map(|folders| folders.iter().map(|f| canonical_path(f)).collect())
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            })
+            .collect();
+
+        let listed_folders: HashSet<&PathBuf> = entries
+            .iter()
+            .zip(&paths)
+            .filter(|((_, r), _)| matches!(r, Recent::Folder { .. }))
+            .filter_map(|(_, path)| path.as_ref())
+            .collect();
+        let workspace_folders: HashSet<&PathBuf> = folders.iter().flatten().collect();
+
+        let mut seen: HashSet<&PathBuf> = HashSet::new();
+        let keep: Vec<bool> = entries
+            .iter()
+            .zip(&paths)
+            .zip(&folders)
+            .map(|(((_, recent), path), folders)| {
+                let Some(path) = path else {
+                    return true;
+                };
+                let hidden = match (recent, preference) {
+                    (Recent::Folder { .. }, DedupPreference::Workspace) => {
+                        workspace_folders.contains(path)
+                    }
+                    (Recent::Workspace { .. }, DedupPreference::Folder) => {
+                        !folders.is_empty() && folders.iter().all(|f| listed_folders.contains(f))
+                    }
+                    _ => false,
+                };
+                !hidden && seen.insert(path)
+            })
+            .collect();
+
+        entries
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(entry, keep)| keep.then_some(entry))
+            .collect()
+    }
+
+    /// Sort the entries into groups of the given kinds, in that order
+    ///
+    /// The order of the entries within each group is preserved,
+    /// the kinds that are not given are grouped at the end.
+    pub fn group_recents(entries: &mut [(usize, Recent)], order: &[RecentKind]) {
+        entries.sort_by_key(|(_, r)| {
+            order
+                .iter()
+                .position(|&k| k == r.kind())
+                .unwrap_or(order.len())
+        });
+    }
+
+    /// How the recent items are sorted
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub enum RecentSort {
+        /// In the order of the list kept by VSCode (default)
+        #[default]
+        Recency,
+        /// By the time each workspace or folder was last opened, see [LastOpened]
+        ///
+        /// Items without a known time, like files, come last in their original order.
+        LastOpened,
+        /// By how often and how recently each item was opened, see [stats](crate::stats)
+        ///
+        /// Items that were never opened through the plugin or the command line come last in their original order.
+        MostUsed,
+    }
+
+    impl FromStr for RecentSort {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.trim().to_lowercase().as_str() {
+                "recency" | "recent" => Ok(Self::Recency),
+                "last-opened" | "last_opened" | "time" => Ok(Self::LastOpened),
+                "most-used" | "most_used" | "frecency" => Ok(Self::MostUsed),
+                _ => Err(other!("Unrecognized sort order {}", s)),
+            }
+        }
+    }
+
+    /// Where the remote and virtual items are placed among the local ones
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub enum RemotePlacement {
+        /// Wherever they fall in the order (default)
+        #[default]
+        Mixed,
+        /// After all the local items, in their original order
+        Last,
+        /// Not shown at all
+        Hidden,
+    }
+
+    impl FromStr for RemotePlacement {
+        type Err = Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s.trim().to_lowercase().as_str() {
+                "mixed" => Ok(Self::Mixed),
+                "last" | "bottom" => Ok(Self::Last),
+                "hidden" | "hide" => Ok(Self::Hidden),
+                _ => Err(other!("Unrecognized placement of remote items {}", s)),
+            }
+        }
+    }
+
+    /// Move the remote and virtual entries after the local ones, preserving the order within each
+    pub fn remote_last(entries: &mut [(usize, Recent)]) {
+        entries.sort_by_key(|(_, r)| !r.is_local());
+    }
+
+    /// Keep only the entries matching the fuzzy `query`, from the best to the worst match
+    ///
+    /// Both the label and the full path (or URL) of each entry are matched, see [fuzzy_score].
+    pub fn rank_recents(entries: Vec<(usize, Recent)>, query: &str) -> Vec<(usize, Recent)> {
+        let mut ranked: Vec<(i64, (usize, Recent))> = entries
+            .into_iter()
+            .filter_map(|(i, recent)| {
+                let location = match recent.file_path() {
+                    Ok(path) => path.to_string_lossy().into_owned(),
+                    Err(_) => recent.url().to_string(),
+                };
+                let label_score = recent.label().ok().and_then(|l| fuzzy_score(query, &l));
+                let score = label_score.max(fuzzy_score(query, &location))?;
+                Some((score, (i, recent)))
+            })
+            .collect();
+        ranked.sort_by_key(|(score, _)| Reverse(*score));
+        ranked.into_iter().map(|(_, entry)| entry).collect()
+    }
+
+    /// How the list of recent items is filtered and ordered before being shown
+    #[derive(Debug, Default, Clone)]
+    pub struct RecentOptions {
+        /// Show only the items of these kinds, or all of them if empty
+        pub kinds: Vec<RecentKind>,
+        /// Resolve the symlinks in the paths of local items, see [resolve_symlinks]
+        pub resolve_symlinks: bool,
+        /// Show only the local items under this directory
+        pub within: Option<PathBuf>,
+        /// Collapse the entries pointing to the same directory
+        pub dedup: Option<DedupPreference>,
+        /// How the entries are sorted
+        pub sort: RecentSort,
+        /// Group the entries by kind, in this order
+        pub group: Vec<RecentKind>,
+        /// Where the remote and virtual entries are placed
+        pub remote: RemotePlacement,
+        /// Hide the virtual entries, whatever the placement of the remote ones
+        pub hide_virtual: bool,
+        /// Show only the entries matching this fuzzy query, ranked by how well they match
+        pub query: Option<String>,
+        /// Maximum number of entries
+        pub max_entries: Option<usize>,
+    }
+
+    impl RecentOptions {
+        /// Filter and order the entries of the given flavors, truncating them at the end
+        pub fn apply(
+            &self,
+            flavors: &[Box<dyn EditorFlavor>],
+            mut entries: Vec<(usize, Recent)>,
+        ) -> Vec<(usize, Recent)> {
+            if !self.kinds.is_empty() {
+                entries.retain(|(_, r)| self.kinds.contains(&r.kind()));
+            }
+            if self.remote == RemotePlacement::Hidden {
+                entries.retain(|(_, r)| r.is_local());
+            }
+            if self.hide_virtual {
+                entries.retain(|(_, r)| !r.is_virtual());
+            }
+            if self.resolve_symlinks {
+                entries = resolve_symlinks(entries);
+            }
+            if let Some(root) = &self.within {
+                entries.retain(|(_, r)| r.is_within(root));
+            }
+            if let Some(preference) = self.dedup {
+                entries = dedup_recents(entries, preference);
+            }
+            match self.sort {
+                RecentSort::Recency => {}
+                RecentSort::LastOpened => {
+                    let last_opened = LastOpened::read(flavors);
+                    entries.sort_by_key(|(f, r)| Reverse(last_opened.get(*f, r)));
+                }
+                RecentSort::MostUsed => match UsageStats::read() {
+                    Ok(stats) => stats.rank(&mut entries),
+                    Err(e) => debug!("{:?}", e),
+                },
+            }
+            group_recents(&mut entries, &self.group);
+            if self.remote == RemotePlacement::Last {
+                remote_last(&mut entries);
+            }
+            if let Some(query) = &self.query {
+                entries = rank_recents(entries, query);
+            }
+            if let Some(max_entries) = self.max_entries {
+                entries.truncate(max_entries);
+            }
+            entries
+        }
+    }
+
+    /// Remove the item with the given URL from the recent items of a flavor
+    ///
+    /// The items are read again from the storage, so that the other ones are left untouched.
+    /// Returns the position that the item had in the list, if it was found.
+    pub fn remove_recently_opened(flavor: &dyn EditorFlavor, url: &Url) -> Result<Option<usize>> {
+        let mut entries = recently_opened_from_storage(flavor, false)?;
+        let position = entries.iter().position(|r| r.url() == url);
+        entries.retain(|r| r.url() != url);
+        store_recently_opened(flavor, &entries).map(|_| position)
+    }
+
+    /// A change between two versions of a list of recent items
+    #[derive(Debug, Clone)]
+    pub enum RecentChange {
+        /// The item was added at the given position of the new list
+        Added(usize, Recent),
+        /// The item was removed from the given position of the old list
+        Removed(usize, Recent),
+    }
+
+    /// Compare two versions of a list of recent items by URL
+    ///
+    /// Items that only moved are not reported, the removed ones come first.
+    pub fn diff_recents(old: &[Recent], new: &[Recent]) -> Vec<RecentChange> {
+        let old_urls: HashSet<&Url> = old.iter().map(Recent::url).collect();
+        let new_urls: HashSet<&Url> = new.iter().map(Recent::url).collect();
+        let removed = old
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| !new_urls.contains(r.url()))
+            .map(|(i, r)| RecentChange::Removed(i, r.clone()));
+        let added = new
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| !old_urls.contains(r.url()))
+            .map(|(i, r)| RecentChange::Added(i, r.clone()));
+        removed.chain(added).collect()
+    }
+
+    /// Put an item at the top of the recent items of a flavor, removing its other occurrences
+    pub fn add_recently_opened(flavor: &dyn EditorFlavor, recent: &Recent) -> Result<()> {
+        let mut entries = recently_opened_from_storage(flavor, false)?;
+        entries.retain(|r| r.url() != recent.url());
+        entries.insert(0, recent.clone());
+        store_recently_opened(flavor, &entries)
+    }
+
+    /// Record the local directory at `path` as the most recent folder of a flavor, e.g. from a shell hook
+    ///
+    /// Nothing is written if it already is the most recent item, so that it is cheap to call often.
+    /// Returns whether the list was changed.
+    pub fn touch_recent(flavor: &dyn EditorFlavor, path: &Path) -> Result<bool> {
+        let path = std::path::absolute(path)?;
+        if !path.is_dir() {
+            return Err(other!("{:?} is not a directory", path));
+        }
+        let url = file_path_to_url(&path)
+            .ok_or_else(|| other!("Could not convert path {:?} to URI", path))?;
+        let mut entries = recently_opened_from_storage(flavor, false)?;
+        if entries.first().is_some_and(|r| r.url() == &url) {
+            return Ok(false);
+        }
+        entries.retain(|r| r.url() != &url);
+        entries.insert(
+            0,
+            Recent::Folder {
+                folder_uri: url,
+                label: None,
+                remote_authority: None,
+            },
+        );
+        store_recently_opened(flavor, &entries).map(|_| true)
+    }
+
+    /// Serialize the recent items to JSON, in the same format as VSCode's state
+    pub fn history_to_json(entries: &[Recent]) -> Result<String> {
+        serde_json::to_string_pretty(&json!({ "entries": entries }))
+            .with_context(|| "Could not serialize the recent items")
+    }
+
+    /// Deserialize the recent items from JSON
+    ///
+    /// Performs the reverse operation of [history_to_json], also accepting a bare array of items.
+    /// Unlike when reading VSCode's state, items that fail to deserialize are an error.
+    pub fn history_from_json(s: &str) -> Result<Vec<Recent>> {
+        let value: Value = serde_json::from_str(s).with_context(|| "Invalid JSON")?;
+        let entries = match value {
+            Value::Array(entries) => entries,
+            Value::Object(mut object) => match object.remove("entries") {
+                Some(Value::Array(entries)) => entries,
+                _ => return Err(other!("The \"entries\" attribute is not an array")),
+            },
+            _ => return Err(other!("Expected an object or an array of recent items")),
+        };
+        entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, e)| {
+                serde_json::from_value(e)
+                    .map_err(|source| Error::DeserializeEntry { index: i, source })
+            })
+            .collect()
+    }
+
+    /// Replace the recent items of a flavor with `entries`
+    ///
+    /// If `merge` is set, the previous items are kept after the new ones, without duplicates.
+    pub fn import_recently_opened(
+        flavor: &dyn EditorFlavor,
+        entries: Vec<Recent>,
+        merge: bool,
+    ) -> Result<()> {
+        let mut entries = entries;
+        if merge {
+            let previous = recently_opened_from_storage(flavor, false)?;
+            entries.extend(previous);
+        }
+        let mut seen = HashSet::new();
+        entries.retain(|r| seen.insert(r.url().clone()));
+        store_recently_opened(flavor, &entries)
+    }
+
+    /// Merge the recent items of the flavor `from` into the ones of the flavor `to`
+    ///
+    /// The two lists are interleaved as in [merge_recents], and the result is stored into `to` only.
+    /// Returns the number of items that were added to `to`.
+    pub fn sync_recently_opened(from: &dyn EditorFlavor, to: &dyn EditorFlavor) -> Result<usize> {
+        let target = recently_opened_from_storage(to, false)?;
+        let count = target.len();
+        let merged: Vec<Recent> =
+            merge_recents(vec![recently_opened_from_storage(from, false)?, target])
+                .into_iter()
+                .map(|(_, r)| r)
+                .collect();
+        store_recently_opened(to, &merged)?;
+        Ok(merged.len() - count)
+    }
+
+    /// Remove the items satisfying `predicate` from the recent items of a flavor
+    ///
+    /// Returns the removed items. If `dry_run` is set, they are only returned and the list is left untouched.
+    pub fn remove_recently_opened_if(
+        flavor: &dyn EditorFlavor,
+        predicate: impl Fn(&Recent) -> bool,
+        dry_run: bool,
+    ) -> Result<Vec<Recent>> {
+        let (removed, entries): (Vec<Recent>, Vec<Recent>) =
+            recently_opened_from_storage(flavor, false)?
+                .into_iter()
+                .partition(|r| predicate(r));
+        if !dry_run && !removed.is_empty() {
+            store_recently_opened(flavor, &entries)?;
+        }
+        Ok(removed)
     }
 
-    /// Store the workspaces into VSCode's state
+    /// Remove the stale items, whose local paths do not exist anymore, from the recent items of a flavor
     ///
-    /// Performs the reverse operation of [recently_opened_from_storage],
-    /// see its documentation for details.
-    pub fn store_recently_opened(flavor: &Flavor, entries: &[Recent]) -> anyhow::Result<()> {
-        let config_dir = flavor.config_dir().ok_or_else(|| {
-            anyhow!(
-                "Could not find configuration directory for \"{:?}\"",
-                flavor
-            )
-        })?;
+    /// Returns the removed items. If `dry_run` is set, they are only returned and the list is left untouched.
+    pub fn prune_recently_opened(flavor: &dyn EditorFlavor, dry_run: bool) -> Result<Vec<Recent>> {
+        remove_recently_opened_if(flavor, Recent::is_stale, dry_run)
+    }
+
+    /// Remove all the recent items of a flavor, including the ones that could not be read
+    pub fn clear_recently_opened(flavor: &dyn EditorFlavor) -> Result<()> {
+        store_history(flavor, &[], false)
+    }
 
-        store_history_entries(&config_dir, entries)
+    /// Put back a removed item into the recent items of a flavor, at the given `position`
+    ///
+    /// The item is left out if it has been added again in the meantime.
+    pub fn restore_recently_opened(
+        flavor: &dyn EditorFlavor,
+        recent: &Recent,
+        position: usize,
+    ) -> Result<()> {
+        let mut entries = recently_opened_from_storage(flavor, false)?;
+        if entries.iter().any(|r| r.url() == recent.url()) {
+            return Ok(());
+        }
+        entries.insert(position.min(entries.len()), recent.clone());
+        store_recently_opened(flavor, &entries)
+    }
+
+    /// Move the item with the given URL to the top of the recent items of a flavor, as if it was just opened
+    ///
+    /// Returns `false` if the item was not found, leaving the list untouched.
+    pub fn bump_recently_opened(flavor: &dyn EditorFlavor, url: &Url) -> Result<bool> {
+        let mut entries = recently_opened_from_storage(flavor, false)?;
+        let Some(position) = entries.iter().position(|r| r.url() == url) else {
+            return Ok(false);
+        };
+        entries[..=position].rotate_right(1);
+        store_recently_opened(flavor, &entries).map(|_| true)
     }
 
     #[cfg(test)]
@@ -538,7 +2309,344 @@ pub mod workspaces {
         use serde_json::json;
         use url::Url;
 
-        use super::Recent;
+        use super::super::tests::temp_state_db;
+        use super::super::{tildify, CustomFlavor, Marketplace};
+        use super::{backup_dir, backup_history, MAX_HISTORY_BACKUPS, VSCDB_HISTORY_KEY};
+        use super::{
+            bump_recently_opened, dedup_recents, diff_recents, get_history_entries, group_recents,
+            history_from_json, history_location, history_to_json, merge_recents,
+            recently_opened_from_storage, remote_last, remove_recently_opened, resolve_symlinks,
+            restore_recently_opened, store_history_entries, sync_recently_opened, touch_recent,
+            updated_history, with_unknown_entries, workspace_folder_names, DedupPreference, Recent,
+            RecentChange, RecentKind, RecentOptions,
+        };
+        use crate::error::Error;
+
+        fn folder(path: &str) -> Recent {
+            serde_json::from_value(json!({ "folderUri": format!("file://{}", path) }))
+                .expect("could not deserialize")
+        }
+
+        #[test]
+        fn store_history_on_fresh_profile() {
+            let db = temp_state_db("fresh");
+            let db_path = db.path();
+
+            let entries = get_history_entries(db_path, false).expect("could not read history");
+            assert!(entries.is_empty());
+
+            store_history_entries(db_path, &[folder("/a")], true).expect("could not store history");
+            let entries = get_history_entries(db_path, false).expect("could not read history");
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].url().path(), "/a");
+        }
+
+        #[test]
+        fn store_history_with_backup() {
+            let db = temp_state_db("backup");
+            let db_path = db.path();
+            let previous = json!({
+                "entries": [
+                    { "folderUri": "file:///srv/scranton" },
+                    { "unknownUri": "mystery://beet-farm" },
+                ],
+                "version": 2,
+            })
+            .to_string();
+            let conn = rusqlite::Connection::open(db_path).expect("could not open database");
+            conn.execute(
+                "INSERT INTO ItemTable VALUES (?1, ?2)",
+                [VSCDB_HISTORY_KEY, &previous],
+            )
+            .expect("could not insert history");
+            let stored = || -> serde_json::Value {
+                let value: String = conn
+                    .query_row(
+                        "SELECT value FROM ItemTable WHERE key = ?1",
+                        [VSCDB_HISTORY_KEY],
+                        |r| r.get(0),
+                    )
+                    .expect("could not read history");
+                serde_json::from_str(&value).expect("could not parse history")
+            };
+
+            // The value is replaced, keeping the unknown entry and the other attributes
+            store_history_entries(db_path, &[folder("/a"), folder("/srv/scranton")], true)
+                .expect("could not store history");
+            assert_eq!(
+                stored(),
+                json!({
+                    "entries": [
+                        { "folderUri": "file:///a" },
+                        { "folderUri": "file:///srv/scranton" },
+                        { "unknownUri": "mystery://beet-farm" },
+                    ],
+                    "version": 2,
+                })
+            );
+            let backed_up = std::fs::read_dir(backup_dir().expect("no backup directory"))
+                .expect("could not list backups")
+                .filter_map(|e| std::fs::read_to_string(e.ok()?.path()).ok())
+                .any(|backup| backup == previous);
+            assert!(backed_up, "the previous history was not backed up");
+
+            // Nothing is written while another connection holds the database
+            conn.execute_batch("BEGIN IMMEDIATE")
+                .expect("could not lock database");
+            let err = store_history_entries(db_path, &[folder("/b")], true)
+                .expect_err("stored into a locked database");
+            assert!(matches!(err.root(), Error::DbLocked(_)));
+            conn.execute_batch("ROLLBACK")
+                .expect("could not unlock database");
+            assert_eq!(stored()["entries"][0], json!({ "folderUri": "file:///a" }));
+        }
+
+        #[test]
+        fn prune_history_backups() {
+            let dir =
+                std::env::temp_dir().join(format!("rofi-vscode-mode-prune-{}", std::process::id()));
+            let paths: Vec<_> = (0..MAX_HISTORY_BACKUPS + 5)
+                .map(|i| backup_history(&dir, &i.to_string()).expect("could not back up"))
+                .collect();
+            let mut kept: Vec<_> = std::fs::read_dir(&dir)
+                .expect("could not list backups")
+                .map(|e| e.expect("could not read entry").path())
+                .collect();
+            kept.sort();
+            assert_eq!(kept.len(), MAX_HISTORY_BACKUPS);
+            assert!(!paths[4].exists());
+            assert_eq!(
+                std::fs::read_to_string(paths.last().unwrap()).unwrap(),
+                (MAX_HISTORY_BACKUPS + 4).to_string()
+            );
+
+            std::fs::remove_dir_all(&dir).expect("could not remove directory");
+        }
+
+        #[test]
+        fn remove_and_restore_entry() {
+            let db = temp_state_db("undo");
+            store_history_entries(db.path(), &[folder("/a"), folder("/b"), folder("/c")], true)
+                .expect("could not store history");
+            let flavor = CustomFlavor {
+                cmd: "code".to_string(),
+                config_dir: None,
+                state_db: Some(db.path().to_path_buf()),
+                marketplace: Marketplace::VisualStudio,
+            };
+            let paths = || -> Vec<String> {
+                recently_opened_from_storage(&flavor, false)
+                    .expect("could not read history")
+                    .iter()
+                    .map(|r| r.url().path().to_string())
+                    .collect()
+            };
+
+            let removed = folder("/b");
+            let position =
+                remove_recently_opened(&flavor, removed.url()).expect("could not remove");
+            assert_eq!(position, Some(1));
+            assert_eq!(paths(), vec!["/a", "/c"]);
+
+            restore_recently_opened(&flavor, &removed, 1).expect("could not restore");
+            restore_recently_opened(&flavor, &removed, 0).expect("could not restore");
+            assert_eq!(paths(), vec!["/a", "/b", "/c"]);
+        }
+
+        #[test]
+        fn bump_entry() {
+            let db = temp_state_db("bump");
+            store_history_entries(db.path(), &[folder("/a"), folder("/b"), folder("/c")], true)
+                .expect("could not store history");
+            let flavor = CustomFlavor {
+                cmd: "code".to_string(),
+                config_dir: None,
+                state_db: Some(db.path().to_path_buf()),
+                marketplace: Marketplace::VisualStudio,
+            };
+            let paths = || -> Vec<String> {
+                recently_opened_from_storage(&flavor, false)
+                    .expect("could not read history")
+                    .iter()
+                    .map(|r| r.url().path().to_string())
+                    .collect()
+            };
+
+            assert!(bump_recently_opened(&flavor, folder("/c").url()).expect("could not bump"));
+            assert_eq!(paths(), vec!["/c", "/a", "/b"]);
+            assert!(!bump_recently_opened(&flavor, folder("/d").url()).expect("could not bump"));
+            assert_eq!(paths(), vec!["/c", "/a", "/b"]);
+        }
+
+        #[test]
+        fn locate_history() {
+            let db = temp_state_db("locate");
+            let flavor = CustomFlavor {
+                cmd: "code".to_string(),
+                config_dir: None,
+                state_db: Some(db.path().to_path_buf()),
+                marketplace: Marketplace::VisualStudio,
+            };
+
+            let (path, found) = history_location(&flavor).expect("could not locate history");
+            assert_eq!(path, db.path());
+            assert!(!found);
+            store_history_entries(db.path(), &[folder("/a")], true)
+                .expect("could not store history");
+            assert!(
+                history_location(&flavor)
+                    .expect("could not locate history")
+                    .1
+            );
+        }
+
+        #[test]
+        fn touch_directory() {
+            let dir = std::env::temp_dir();
+            let db = temp_state_db("touch");
+            store_history_entries(db.path(), &[folder("/a")], true)
+                .expect("could not store history");
+            let flavor = CustomFlavor {
+                cmd: "code".to_string(),
+                config_dir: None,
+                state_db: Some(db.path().to_path_buf()),
+                marketplace: Marketplace::VisualStudio,
+            };
+
+            assert!(touch_recent(&flavor, &dir).expect("could not touch"));
+            assert!(!touch_recent(&flavor, &dir).expect("could not touch"));
+            assert!(touch_recent(&flavor, db.path()).is_err());
+            let entries = recently_opened_from_storage(&flavor, false).expect("could not read");
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0].file_path().expect("expected a path"), dir);
+        }
+
+        #[test]
+        fn keep_unknown_entries() {
+            let unknown = json!({ "notebookUri": "file:///home/dwight/beets.ipynb" });
+            let previous = json!({
+                "entries": [
+                    { "folderUri": "file:///a" },
+                    unknown,
+                    { "folderUri": "file:///b" },
+                ],
+            });
+            // The entry preceding the unknown one is removed, so it goes first
+            let values = with_unknown_entries(Some(&previous), &[folder("/b")])
+                .expect("could not serialize");
+            assert_eq!(values.len(), 2);
+            assert_eq!(values[0], unknown);
+
+            // The unknown entry follows the one it preceded, wherever it moved
+            let values =
+                with_unknown_entries(Some(&previous), &[folder("/b"), folder("/c"), folder("/a")])
+                    .expect("could not serialize");
+            assert_eq!(
+                values,
+                vec![
+                    json!({ "folderUri": "file:///b" }),
+                    json!({ "folderUri": "file:///c" }),
+                    json!({ "folderUri": "file:///a" }),
+                    unknown.clone(),
+                ]
+            );
+
+            let values = with_unknown_entries(None, &[folder("/b")]).expect("could not serialize");
+            assert_eq!(values.len(), 1);
+        }
+
+        #[test]
+        fn keep_history_attributes() {
+            let previous = json!({
+                "entries": [{ "folderUri": "file:///a" }],
+                "version": 2,
+            });
+            let value = updated_history(Some(previous), &[folder("/b")], true)
+                .expect("could not serialize");
+            assert_eq!(value["version"], 2);
+            assert_eq!(value["entries"], json!([{ "folderUri": "file:///b" }]));
+
+            let value = updated_history(None, &[], false).expect("could not serialize");
+            assert_eq!(value, json!({ "entries": [] }));
+
+            // Without keeping the unknown entries, only the given ones are stored
+            let previous = json!({
+                "entries": [{ "notebookUri": "file:///home/dwight/beets.ipynb" }],
+                "version": 2,
+            });
+            let value = updated_history(Some(previous), &[folder("/a"), folder("/b")], false)
+                .expect("could not serialize");
+            assert_eq!(
+                value,
+                json!({
+                    "entries": [{ "folderUri": "file:///a" }, { "folderUri": "file:///b" }],
+                    "version": 2,
+                })
+            );
+        }
+
+        #[test]
+        fn merge_recents_from_flavors() {
+            let merged = merge_recents(vec![
+                vec![folder("/a"), folder("/b"), folder("/c")],
+                vec![folder("/b"), folder("/d")],
+            ]);
+            let merged: Vec<(usize, &str)> =
+                merged.iter().map(|(i, r)| (*i, r.url().path())).collect();
+            assert_eq!(merged, vec![(0, "/a"), (1, "/b"), (1, "/d"), (0, "/c")]);
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn dedup_folders_of_workspace() {
+            let dir =
+                std::env::temp_dir().join(format!("rofi-vscode-mode-dedup-{}", std::process::id()));
+            let project = dir.join("project");
+            std::fs::create_dir_all(&project).expect("could not create folder");
+            std::os::unix::fs::symlink(&project, dir.join("link")).expect("could not link");
+            let config_path = dir.join("farm.code-workspace");
+            std::fs::write(&config_path, r#"{ "folders": [{ "path": "project" }] }"#)
+                .expect("could not write workspace");
+            let workspace = || -> Recent {
+                serde_json::from_value(json!({
+                    "workspace": {
+                        "id": "0dd79faa9035cb0db768dbe5812a110cc0814402251661dc2d8973f89767444e",
+                        "configPath": Url::from_file_path(&config_path).expect("invalid path"),
+                    }
+                }))
+                .expect("could not deserialize")
+            };
+            let entries = |workspace: Recent| {
+                vec![
+                    (0, folder(&dir.join("link").to_string_lossy())),
+                    (0, workspace),
+                    (0, folder(&project.to_string_lossy())),
+                    (0, folder("/nonexistent")),
+                ]
+            };
+
+            let deduped = dedup_recents(entries(workspace()), DedupPreference::Workspace);
+            let kept: Vec<&str> = deduped.iter().map(|(_, r)| r.url().path()).collect();
+            assert_eq!(
+                kept,
+                vec![config_path.to_str().expect("invalid path"), "/nonexistent"]
+            );
+
+            let deduped = dedup_recents(entries(workspace()), DedupPreference::Folder);
+            let kept: Vec<String> = deduped
+                .iter()
+                .map(|(_, r)| r.url().path().to_string())
+                .collect();
+            assert_eq!(
+                kept,
+                vec![
+                    dir.join("link").to_string_lossy().to_string(),
+                    "/nonexistent".to_string()
+                ]
+            );
+
+            std::fs::remove_dir_all(&dir).expect("could not remove folder");
+        }
 
         #[test]
         fn local_workspace_properties() {
@@ -627,18 +2735,525 @@ pub mod workspaces {
                 panic!("Expected to deserialize a workspace")
             }
         }
+
+        #[test]
+        #[cfg(unix)]
+        fn collapse_symlinked_entries() {
+            let dir = std::env::temp_dir()
+                .join(format!("rofi-vscode-mode-symlinks-{}", std::process::id()));
+            let project = dir.join("project");
+            std::fs::create_dir_all(&project).expect("could not create folder");
+            std::os::unix::fs::symlink(&project, dir.join("link")).expect("could not link");
+
+            let entries = vec![
+                (0, folder(&dir.join("link").to_string_lossy())),
+                (1, folder(&project.to_string_lossy())),
+                (0, folder("/nonexistent")),
+            ];
+            let resolved = resolve_symlinks(entries);
+            assert_eq!(resolved.len(), 2);
+            assert_eq!(resolved[0].0, 0);
+            assert_eq!(resolved[0].1.label().expect("no label"), tildify(&project));
+            assert_eq!(
+                resolved[0].1.url().path(),
+                dir.join("link").to_string_lossy()
+            );
+
+            std::fs::remove_dir_all(&dir).expect("could not remove folder");
+        }
+
+        #[test]
+        fn name_workspace_folders() {
+            let config_path = std::env::temp_dir().join(format!(
+                "rofi-vscode-mode-names-{}.code-workspace",
+                std::process::id()
+            ));
+            std::fs::write(
+                &config_path,
+                r#"{
+                    // Folders of the farm
+                    "folders": [
+                        { "path": "beet-farm/" },
+                        { "path": "/srv/barn", "name": "Barn" },
+                        { "uri": "vscode-remote://ssh-remote%2Bschrute/home/dwight/bed%20and%20breakfast" },
+                    ],
+                }"#,
+            )
+            .expect("could not write workspace");
+
+            let names = workspace_folder_names(&config_path).expect("could not read workspace");
+            assert_eq!(names, vec!["beet-farm", "Barn", "bed and breakfast"]);
+
+            std::fs::remove_file(&config_path).expect("could not remove workspace");
+        }
+
+        #[test]
+        fn group_by_kind() {
+            let file = |path: &str| -> Recent {
+                serde_json::from_value(json!({ "fileUri": format!("file://{}", path) }))
+                    .expect("could not deserialize")
+            };
+            let mut entries = vec![
+                (0, file("/a.txt")),
+                (0, folder("/b")),
+                (0, file("/c.txt")),
+                (0, folder("/d")),
+            ];
+            group_recents(&mut entries, &[RecentKind::Folder]);
+            let paths: Vec<&str> = entries.iter().map(|(_, r)| r.url().path()).collect();
+            assert_eq!(paths, vec!["/b", "/d", "/a.txt", "/c.txt"]);
+        }
+
+        #[test]
+        fn remote_entries_last() {
+            let remote = |path: &str| -> Recent {
+                serde_json::from_value(json!({
+                    "folderUri": format!("vscode-remote://ssh-remote%2Bdevbox{}", path),
+                    "remoteAuthority": "ssh-remote+devbox",
+                }))
+                .expect("could not deserialize")
+            };
+            let mut entries = vec![
+                (0, remote("/a")),
+                (0, folder("/b")),
+                (0, remote("/c")),
+                (0, folder("/d")),
+            ];
+            remote_last(&mut entries);
+            let paths: Vec<&str> = entries.iter().map(|(_, r)| r.url().path()).collect();
+            assert_eq!(paths, vec!["/b", "/d", "/a", "/c"]);
+        }
+
+        #[test]
+        fn hide_virtual_entries() {
+            let entries: Vec<(usize, Recent)> = [
+                json!({ "folderUri": "vscode-vfs://github/dunder/paper" }),
+                json!({
+                    "folderUri": "vscode-remote://ssh-remote%2Bdevbox/srv",
+                    "remoteAuthority": "ssh-remote+devbox",
+                }),
+                json!({ "folderUri": "file:///srv/beet-farm" }),
+            ]
+            .into_iter()
+            .map(|v| (0, serde_json::from_value(v).expect("could not deserialize")))
+            .collect();
+            let options = RecentOptions {
+                hide_virtual: true,
+                ..Default::default()
+            };
+            let entries = options.apply(&[], entries);
+            let schemes: Vec<&str> = entries.iter().map(|(_, r)| r.url().scheme()).collect();
+            assert_eq!(schemes, vec!["vscode-remote", "file"]);
+        }
+
+        #[test]
+        fn match_target() {
+            let recent = folder("/home/dwight/beet-farm");
+            assert!(recent.matches_target("/home/dwight/beet-farm"));
+            assert!(recent.matches_target("file:///home/dwight/beet-farm"));
+            assert!(!recent.matches_target("/home/dwight/beet"));
+            assert!(!recent.matches_target("beet-farm"));
+        }
+
+        #[test]
+        fn match_pattern() {
+            let recent = folder("/home/dwight/beet-farm");
+            assert!(recent.matches_pattern("/home/dwight/beet-farm"));
+            assert!(recent.matches_pattern("/home/dwight/*"));
+            assert!(recent.matches_pattern("file:///home/*-farm"));
+            assert!(!recent.matches_pattern("/home/jim/*"));
+            assert!(!recent.matches_pattern("beet-farm"));
+        }
+
+        #[test]
+        fn recent_from_target() {
+            let dir = std::env::temp_dir();
+            let recent = Recent::from_target(&dir.to_string_lossy(), None).expect("no recent");
+            assert_eq!(recent.kind(), RecentKind::Folder);
+            assert_eq!(recent.file_path().expect("not local"), dir);
+
+            let recent = Recent::from_target(
+                "vscode-remote://ssh-remote+beet-farm/home/dwight/farm.code-workspace",
+                None,
+            )
+            .expect("no recent");
+            assert_eq!(recent.kind(), RecentKind::Workspace);
+            assert_eq!(recent.remote(), Some("ssh-remote+beet-farm"));
+
+            let recent =
+                Recent::from_target("vscode-remote://ssh-remote%2Bbeet-farm/home/dwight", None)
+                    .expect("no recent");
+            assert_eq!(recent.kind(), RecentKind::Folder);
+            assert_eq!(recent.remote(), Some("ssh-remote+beet-farm"));
+            assert_eq!(recent.url().path(), "/home/dwight");
+
+            assert!(Recent::from_target("/does/not/exist", None).is_err());
+        }
+
+        #[test]
+        fn history_json_round_trip() {
+            let entries = vec![folder("/a"), folder("/b")];
+            let json = history_to_json(&entries).expect("could not serialize");
+            let parsed = history_from_json(&json).expect("could not deserialize");
+            let urls: Vec<&str> = parsed.iter().map(|r| r.url().path()).collect();
+            assert_eq!(urls, vec!["/a", "/b"]);
+
+            let parsed = history_from_json(r#"[{ "fileUri": "file:///c.txt" }]"#)
+                .expect("could not deserialize");
+            assert_eq!(parsed[0].kind(), RecentKind::File);
+
+            let error =
+                history_from_json(r#"[{ "folderUri": "file:///a" }, { "nothing": "here" }]"#)
+                    .expect_err("deserialized an invalid item");
+            assert!(matches!(
+                error.root(),
+                Error::DeserializeEntry { index: 1, .. }
+            ));
+            assert!(history_from_json(r#"{ "entries": 42 }"#).is_err());
+        }
+
+        #[test]
+        fn sync_flavors() {
+            let (from_db, to_db) = (temp_state_db("sync-from"), temp_state_db("sync-to"));
+            let flavor = |db_path: &std::path::Path, entries: &[Recent]| {
+                store_history_entries(db_path, entries, true).expect("could not store history");
+                CustomFlavor {
+                    cmd: "code".to_string(),
+                    config_dir: None,
+                    state_db: Some(db_path.to_path_buf()),
+                    marketplace: Marketplace::VisualStudio,
+                }
+            };
+            let from = flavor(from_db.path(), &[folder("/a"), folder("/b")]);
+            let to = flavor(to_db.path(), &[folder("/c"), folder("/a")]);
+
+            let added = sync_recently_opened(&from, &to).expect("could not sync");
+            assert_eq!(added, 1);
+            let paths: Vec<String> = recently_opened_from_storage(&to, false)
+                .expect("could not read history")
+                .iter()
+                .map(|r| r.url().path().to_string())
+                .collect();
+            assert_eq!(paths, vec!["/a", "/c", "/b"]);
+        }
+
+        #[test]
+        fn diff_lists() {
+            let old = [folder("/a"), folder("/b"), folder("/c")];
+            let new = [folder("/d"), folder("/c"), folder("/a")];
+            let changes: Vec<String> = diff_recents(&old, &new)
+                .iter()
+                .map(|c| match c {
+                    RecentChange::Added(i, r) => format!("+{} {}", i, r.url().path()),
+                    RecentChange::Removed(i, r) => format!("-{} {}", i, r.url().path()),
+                })
+                .collect();
+            assert_eq!(changes, vec!["-1 /b", "+0 /d"]);
+        }
+
+        #[test]
+        fn stale_entries() {
+            let dir = std::env::temp_dir();
+            assert!(!folder(&dir.to_string_lossy()).is_stale());
+            assert!(folder("/nonexistent/beet-farm").is_stale());
+            let remote: Recent = serde_json::from_value(json!({
+                "folderUri": "vscode-remote://ssh-remote%2Bschrute/nonexistent",
+                "remoteAuthority": "ssh-remote+schrute",
+            }))
+            .expect("could not deserialize");
+            assert!(!remote.is_stale());
+        }
+
+        #[test]
+        fn within_directory() {
+            let recent = folder("/home/dwight/work/beet-farm");
+            assert!(recent.is_within(Path::new("/home/dwight/work")));
+            assert!(recent.is_within(Path::new("/home/dwight/work/beet-farm")));
+            assert!(!recent.is_within(Path::new("/home/dwight/wo")));
+            assert!(!recent.is_within(Path::new("/home/jim")));
+        }
+
+        #[test]
+        fn wsl_folder_properties() {
+            let json = json!({
+                "folderUri": "vscode-remote://wsl%2Bubuntu/home/dwight/beet%20farm",
+                "remoteAuthority": "wsl+ubuntu",
+            });
+
+            let recent: Recent = serde_json::from_value(json).expect("could not deserialize");
+            assert!(!recent.is_local());
+            assert_eq!(recent.wsl_distro(), Some("ubuntu"));
+
+            let label = recent.label().expect("expected a label");
+            assert_eq!(label, "/home/dwight/beet farm [WSL: ubuntu]");
+            assert_eq!(recent.icon_name(), crate::wsl::WSL_ICON_NAME);
+        }
+
+        #[test]
+        fn ssh_folder_label() {
+            let json = json!({
+                "folderUri": "vscode-remote://ssh-remote%2Bdevbox/srv/beets",
+                "remoteAuthority": "ssh-remote+devbox",
+            });
+
+            let recent: Recent = serde_json::from_value(json).expect("could not deserialize");
+            let label = recent.label().expect("expected a label");
+            assert_eq!(label, "/srv/beets [SSH: devbox]");
+        }
+
+        #[test]
+        fn github_folder_label() {
+            let folder: Recent = serde_json::from_value(json!({
+                "folderUri": "vscode-vfs://github/dunder/paper?ref=main",
+            }))
+            .expect("could not deserialize");
+            assert_eq!(folder.label().unwrap(), "dunder/paper (GitHub)");
+            assert_eq!(folder.icon_name(), "github");
+
+            let file: Recent = serde_json::from_value(json!({
+                "fileUri": "vscode-vfs://github/dunder/paper/src/main.rs",
+            }))
+            .expect("could not deserialize");
+            assert_eq!(file.label().unwrap(), "dunder/paper/src/main.rs (GitHub)");
+            assert_ne!(file.icon_name(), "github");
+        }
     }
 }
 
-fn open_state_db(config_dir: &Path, open_flags: Option<OpenFlags>) -> anyhow::Result<Connection> {
-    let open_flags = open_flags.unwrap_or_default();
-    let db_path = config_dir
+/// Append the arguments to open a recent item to `cmd`
+fn push_recent_args(cmd: &mut Command, recent: &Recent) {
+    let url = recent.url().to_string();
+    match recent {
+        Recent::Workspace {
+            workspace: _,
+            label: _,
+            remote_authority: _,
+        } => {
+            cmd.arg("--file-uri").arg(url);
+        }
+        Recent::Folder {
+            folder_uri: _,
+            label: _,
+            remote_authority: _,
+        } => {
+            cmd.arg("--folder-uri").arg(url);
+        }
+        Recent::File {
+            file_uri: _,
+            label: _,
+            remote_authority: _,
+        } => {
+            cmd.arg("--file-uri").arg(url);
+        }
+    }
+}
+
+/// Path of the global state database inside the configuration directory
+fn state_db_path(config_dir: &Path) -> PathBuf {
+    config_dir
         .join("User")
         .join("globalStorage")
-        .join("state.vscdb");
+        .join("state.vscdb")
+}
 
-    Connection::open_with_flags(&db_path, open_flags)
-        .with_context(|| format!("Could not open database {:?}", &db_path))
+fn open_db(db_path: &Path, open_flags: Option<OpenFlags>) -> Result<Connection> {
+    debug!("Opening database {:?}", db_path);
+    let open_flags = open_flags.unwrap_or_default();
+    Connection::open_with_flags(db_path, open_flags)
+        .with_context(|| format!("Could not open database {:?}", db_path))
+}
+
+/// Tells whether the error is due to another connection holding a lock on the database
+fn is_busy(error: &rusqlite::Error) -> bool {
+    matches!(
+        error.sqlite_error_code(),
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+/// Run a read-only `query` on a database that may be locked by a running VSCode instance
+///
+/// While the database is busy the query is retried with exponential backoff.
+/// As a last resort, it is run on a temporary copy of the database and of its write-ahead log, if any.
+fn read_db<T>(db_path: &Path, query: impl Fn(&Connection) -> rusqlite::Result<T>) -> Result<T> {
+    let _span = span!("Reading database {:?}", db_path);
+    let open_flags = Some(OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX);
+    let mut delay = DB_RETRY_DELAY;
+    for _ in 0..DB_READ_ATTEMPTS {
+        let conn = open_db(db_path, open_flags)?;
+        // Fail immediately instead of using the default busy timeout
+        conn.busy_timeout(Duration::ZERO)
+            .with_context(|| "Could not set busy timeout")?;
+        match query(&conn) {
+            Err(e) if is_busy(&e) => {
+                debug!("Database {:?} is busy, retrying in {:?}", db_path, delay);
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            res => return res.with_context(|| format!("Could not read database {:?}", db_path)),
+        }
+    }
+
+    warning!("Database {:?} is locked, reading a copy", db_path);
+    // Without a copy, the database is as good as locked
+    let locked = |e: Error| {
+        warning!("{}: {}", e, e.root());
+        Error::DbLocked(db_path.to_owned())
+    };
+    let copy_dir = private_copy_dir().map_err(locked)?;
+    let copy_path = copy_dir.join("state.vscdb");
+    let res = copy_new(db_path, &copy_path)
+        .and_then(|_| copy_wal(db_path, &copy_path))
+        .with_context(|| format!("Could not copy it to {:?}", copy_path))
+        .map_err(locked)
+        // Writable, so that the changes in the log can be read
+        .and_then(|_| open_db(&copy_path, None))
+        .and_then(|conn| {
+            query(&conn).with_context(|| format!("Could not read copy of database {:?}", db_path))
+        });
+    let _ = fs::remove_dir_all(&copy_dir);
+    res
+}
+
+/// Path of the write-ahead log of the database at `db_path`
+fn wal_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push("-wal");
+    PathBuf::from(path)
+}
+
+/// Copy the write-ahead log of the database `from` next to its copy `to`, if there is one
+///
+/// It holds the changes that are not yet written into the database itself.
+fn copy_wal(from: &Path, to: &Path) -> io::Result<()> {
+    match copy_new(&wal_path(from), &wal_path(to)) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
+
+/// Create a new directory that only the user can access, to hold a copy of a database
+///
+/// It is created in `$XDG_RUNTIME_DIR`, or else in the temporary directory, with a name unique to this call.
+/// Creating it fails rather than reusing anything already there, e.g. a symlink.
+fn private_copy_dir() -> Result<PathBuf> {
+    static COPIES: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    let dir = dirs::runtime_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join(format!(
+            "rofi-vscode-mode-{}-{}-{}",
+            process::id(),
+            COPIES.fetch_add(1, Ordering::Relaxed),
+            nanos
+        ));
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(&dir)
+        .with_context(|| format!("Could not create {:?}", dir))?;
+    Ok(dir)
+}
+
+/// Copy the file `from` to the path `to`, which must not exist yet
+fn copy_new(from: &Path, to: &Path) -> io::Result<()> {
+    let mut source = fs::File::open(from)?;
+    let mut target = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(to)?;
+    io::copy(&mut source, &mut target).map(|_| ())
+}
+
+/// Run `cmd` without waiting for it to exit
+///
+/// If the failed launches are [notified](crate::notify), the command is [watched] for errors once started.
+fn spawn(cmd: &mut Command) -> Result<()> {
+    debug!("Running {:?}", cmd);
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let launch_failed = |source| Error::LaunchFailed {
+        cmd: program.clone(),
+        source,
+    };
+    if determine_notify() < NotifyLevel::Errors {
+        return cmd.spawn().map(|_| ()).map_err(launch_failed);
+    }
+    // The shell always starts, so a missing command is reported here
+    which(&program).map_err(|e| launch_failed(io::Error::new(io::ErrorKind::NotFound, e)))?;
+    watched(cmd, &format!("Could not run {}", program))
+        .spawn()
+        .map(|_| ())
+        .map_err(launch_failed)
+}
+
+/// Data directory of the portable installation whose executable is `exe`, if it is one
+///
+/// The directory holds the `user-data/` configuration directory, see [Installation::Portable].
+fn portable_data_dir_of(exe: &Path) -> Option<PathBuf> {
+    exe.ancestors()
+        .skip(1)
+        .take(PORTABLE_MAX_DEPTH)
+        .flat_map(|dir| PORTABLE_DATA_DIRS.iter().map(move |name| dir.join(name)))
+        .find(|data| data.join("user-data").is_dir())
+}
+
+/// The portable data directory `data` given by `VSCODE_PORTABLE`, if the executable `exe` belongs to its installation
+///
+/// The variable is inherited by every program launched from a portable installation, including other flavors,
+/// so it is only trusted for the executables inside the directory holding `data`.
+fn portable_data_dir_from_env(exe: &Path, data: &Path) -> Option<PathBuf> {
+    let data = fs::canonicalize(data).ok()?;
+    let root = data.parent()?;
+    (exe.starts_with(root) && data.join("user-data").is_dir()).then_some(data)
+}
+
+/// Find the full path of the command `cmd` in `$PATH`, falling back to `cmd` itself
+///
+/// On Windows the commands of the flavors are batch files like `code.cmd`, which [Command] does not look up by name.
+fn resolve_cmd(cmd: &str) -> PathBuf {
+    which(cmd).unwrap_or_else(|_| PathBuf::from(cmd))
+}
+
+/// Decode the colon after a Windows drive letter in a `file://` URL, e.g. `file:///c%3A/Users`
+///
+/// VSCode encodes it, while [Url::to_file_path] only recognizes drive letters followed by a plain colon.
+fn decode_drive_letter(url: &Url) -> Cow<'_, Url> {
+    let path = url.path();
+    match path.as_bytes() {
+        [b'/', drive, b'%', b'3', b'a' | b'A', ..] if drive.is_ascii_alphabetic() => {
+            let mut url = url.clone();
+            url.set_path(&format!("/{}:{}", *drive as char, &path[5..]));
+            Cow::Owned(url)
+        }
+        _ => Cow::Borrowed(url),
+    }
+}
+
+/// Convert a `file://` URL to a local path, also on Windows
+pub fn file_url_to_path(url: &Url) -> Option<PathBuf> {
+    decode_drive_letter(url).to_file_path().ok()
+}
+
+/// Convert an absolute local path to a `file://` URL, encoded like VSCode does
+///
+/// On Windows, the drive letter is lowercased and the colon after it is encoded, e.g. `file:///c%3A/Users`.
+pub fn file_path_to_url(path: &Path) -> Option<Url> {
+    let mut url = Url::from_file_path(path).ok()?;
+    if cfg!(windows) {
+        if let [b'/', drive, b':', ..] = url.path().as_bytes() {
+            let rest = url.path()[3..].to_string();
+            url.set_path(&format!(
+                "/{}%3A{}",
+                drive.to_ascii_lowercase() as char,
+                rest
+            ));
+        }
+    }
+    Some(url)
 }
 
 /// Replace the home directory prefix of `path` with `~`
@@ -676,9 +3291,281 @@ pub fn untildify(path: &str) -> PathBuf {
 
 #[cfg(test)]
 mod tests {
-    use std::path::Path;
+    use std::{
+        env, fs,
+        path::{Path, PathBuf},
+        process,
+        process::Command,
+        str::FromStr,
+    };
+
+    use rusqlite::Connection;
+    use serde_json::json;
+
+    use super::{
+        copy_new, decode_drive_letter, portable_data_dir_from_env, portable_data_dir_of,
+        private_copy_dir, push_recent_args, read_db, tildify, untildify, wal_path,
+        workspaces::Recent, EditorFlavor, Flavor, WithArgs, WithUserDataDir,
+    };
+    use crate::error::Result;
+    use url::Url;
+
+    /// A state database in the temporary directory, removed with its log when dropped
+    pub(super) struct TempDb(PathBuf);
+
+    impl TempDb {
+        pub(super) fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+            let _ = fs::remove_file(wal_path(&self.0));
+        }
+    }
 
-    use super::{tildify, untildify};
+    /// Create a state database with an empty table, in a file named after `name`
+    pub(super) fn temp_state_db(name: &str) -> TempDb {
+        let path =
+            env::temp_dir().join(format!("rofi-vscode-mode-{}-{}.vscdb", name, process::id()));
+        // Left over by an aborted run
+        let _ = fs::remove_file(&path);
+        Connection::open(&path)
+            .and_then(|c| {
+                c.execute_batch(
+                    "CREATE TABLE ItemTable (key TEXT UNIQUE ON CONFLICT REPLACE, value BLOB)",
+                )
+            })
+            .expect("could not create database");
+        TempDb(path)
+    }
+
+    /// A flavor overriding how items are opened, which records them instead
+    #[derive(Debug, Default)]
+    struct RecordingFlavor(std::sync::Mutex<Vec<String>>);
+
+    impl EditorFlavor for RecordingFlavor {
+        fn cmd(&self) -> &str {
+            "recording"
+        }
+
+        fn config_dir(&self) -> Option<std::path::PathBuf> {
+            None
+        }
+
+        fn open_recent(&self, recent: &Recent) -> Result<()> {
+            self.0.lock().unwrap().push(recent.url().to_string());
+            Ok(())
+        }
+
+        fn handoff_url(&self, recent: &Recent) -> Result<Option<Url>> {
+            Ok(Some(recent.url().to_owned()))
+        }
+
+        fn goto(&self, location: &std::ffi::OsStr) -> Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(location.to_string_lossy().to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn forward_overrides() {
+        let folder: Recent =
+            serde_json::from_value(json!({ "folderUri": "file:///srv/beet-farm" })).unwrap();
+        let flavor = RecordingFlavor::default();
+        {
+            let by_ref: &dyn EditorFlavor = &flavor;
+            let boxed: Box<dyn EditorFlavor + '_> = Box::new(by_ref);
+            boxed.open_recent(&folder).unwrap();
+            let boxed_ref: &dyn EditorFlavor = &boxed;
+            boxed_ref
+                .goto("/srv/beet-farm/crops.md:3".as_ref())
+                .unwrap();
+        }
+        assert_eq!(
+            *flavor.0.lock().unwrap(),
+            vec!["file:///srv/beet-farm", "/srv/beet-farm/crops.md:3"]
+        );
+
+        let wrapped = WithUserDataDir {
+            flavor: WithArgs {
+                flavor: &flavor,
+                args: vec!["--new-window".to_string()],
+            },
+            dir: "/srv/beet-farm/.vscode-data".into(),
+        };
+        assert_eq!(
+            wrapped.handoff_url(&folder).unwrap().unwrap().as_str(),
+            "file:///srv/beet-farm"
+        );
+    }
+
+    #[test]
+    fn github_folder_args() {
+        let folder: Recent = serde_json::from_value(json!({
+            "folderUri": "vscode-vfs://github/dunder/paper?ref=sales",
+        }))
+        .unwrap();
+        let mut cmd = Command::new("code");
+        push_recent_args(&mut cmd, &folder);
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(
+            args,
+            vec!["--folder-uri", "vscode-vfs://github/dunder/paper?ref=sales"]
+        );
+    }
+
+    #[test]
+    fn portable_installation() {
+        let root = env::temp_dir().join(format!("rofi-vscode-mode-portable-{}", process::id()));
+        let install = root.join("VSCode-linux-x64");
+        fs::create_dir_all(install.join("bin")).expect("could not create directory");
+        assert_eq!(
+            portable_data_dir_of(&install.join("bin").join("code")),
+            None
+        );
+
+        let data = install.join("data");
+        fs::create_dir_all(data.join("user-data")).expect("could not create directory");
+        assert_eq!(
+            portable_data_dir_of(&install.join("bin").join("code")),
+            Some(data.clone())
+        );
+        assert_eq!(portable_data_dir_of(&install.join("code")), Some(data));
+
+        let install = fs::canonicalize(&install).expect("could not resolve directory");
+        let custom = install.join("custom-data");
+        fs::create_dir_all(custom.join("user-data")).expect("could not create directory");
+        assert_eq!(
+            portable_data_dir_from_env(&install.join("bin").join("code"), &custom),
+            Some(custom.clone())
+        );
+        let other = install.parent().unwrap().join("VSCodium").join("codium");
+        assert_eq!(portable_data_dir_from_env(&other, &custom), None);
+
+        fs::remove_dir_all(&root).expect("could not remove directory");
+    }
+
+    #[test]
+    fn read_locked_db() {
+        let db = temp_state_db("locked");
+        let conn = Connection::open(db.path()).expect("could not open database");
+        conn.execute_batch(
+            "INSERT INTO ItemTable VALUES ('answer', '42');
+             BEGIN EXCLUSIVE;",
+        )
+        .expect("could not lock database");
+
+        let value: String = read_db(db.path(), |conn| {
+            conn.query_row(
+                "SELECT value FROM ItemTable WHERE key = 'answer'",
+                [],
+                |r| r.get(0),
+            )
+        })
+        .expect("could not read locked database");
+        assert_eq!(value, "42");
+    }
+
+    #[test]
+    fn read_locked_wal_db() {
+        let db = temp_state_db("locked-wal");
+        let conn = Connection::open(db.path()).expect("could not open database");
+        // Keep the change in the log, and the other connections out
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA wal_autocheckpoint = 0;
+             PRAGMA locking_mode = EXCLUSIVE;
+             INSERT INTO ItemTable VALUES ('answer', '42');",
+        )
+        .expect("could not lock database");
+        assert!(wal_path(db.path()).exists());
+
+        let value: String = read_db(db.path(), |conn| {
+            conn.query_row(
+                "SELECT value FROM ItemTable WHERE key = 'answer'",
+                [],
+                |r| r.get(0),
+            )
+        })
+        .expect("could not read locked database");
+        assert_eq!(value, "42");
+    }
+
+    #[test]
+    fn private_copies() {
+        let first = private_copy_dir().expect("could not create directory");
+        let second = private_copy_dir().expect("could not create directory");
+        assert_ne!(first, second);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        let source = first.join("source");
+        fs::write(&source, "42").unwrap();
+        let copy = second.join("copy");
+        copy_new(&source, &copy).expect("could not copy");
+        assert_eq!(fs::read_to_string(&copy).unwrap(), "42");
+        // An existing file, or a symlink planted there, is never written through
+        assert!(copy_new(&source, &copy).is_err());
+
+        fs::remove_dir_all(&first).unwrap();
+        fs::remove_dir_all(&second).unwrap();
+    }
+
+    #[test]
+    fn flatpak_config_dir() {
+        let home = Path::new("/home/dwight");
+        assert_eq!(
+            Flavor::VSCodium.flatpak_config_dir(home),
+            Some(home.join(".var/app/com.vscodium.codium/config/VSCodium"))
+        );
+        assert_eq!(Flavor::Cursor.flatpak_config_dir(home), None);
+    }
+
+    #[test]
+    fn snap_config_dir() {
+        let home = Path::new("/home/dwight");
+        assert_eq!(
+            Flavor::Code.snap_config_dir(home),
+            Some(home.join("snap/code/current/.config/Code"))
+        );
+        assert_eq!(Flavor::CodeOSS.snap_config_dir(home), None);
+    }
+
+    #[test]
+    fn windows_drive_letter() {
+        let url = Url::parse("file:///c%3A/Users/dwight").expect("invalid url");
+        assert_eq!(decode_drive_letter(&url).path(), "/c:/Users/dwight");
+        let url = Url::parse("file:///home/dwight").expect("invalid url");
+        assert_eq!(decode_drive_letter(&url).path(), "/home/dwight");
+    }
+
+    #[test]
+    fn app_bundle_cmd() {
+        let apps = Path::new("/Applications");
+        assert_eq!(
+            Flavor::Code.app_bundle_cmd(apps),
+            apps.join("Visual Studio Code.app/Contents/Resources/app/bin/code")
+        );
+    }
+
+    #[test]
+    fn flavor_from_str() {
+        assert_eq!(Flavor::from_str("VSCodium").unwrap(), Flavor::VSCodium);
+        assert_eq!(Flavor::from_str("cursor").unwrap(), Flavor::Cursor);
+        assert_eq!(Flavor::from_str("Windsurf").unwrap(), Flavor::Windsurf);
+        assert_eq!(Flavor::Windsurf.cmd(), "windsurf");
+        assert!(Flavor::from_str("notepad").is_err());
+    }
 
     #[test]
     fn tildify_inside_home() {
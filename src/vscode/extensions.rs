@@ -0,0 +1,156 @@
+//! VSCode extensions management
+//!
+//! Extensions are managed through the command line interface of each flavor,
+//! see [this documentation article](https://code.visualstudio.com/docs/editor/extension-marketplace#_command-line-extension-management).
+
+use super::{EditorFlavor, Marketplace};
+
+use crate::error::{other, Context, Result};
+
+/// An installed extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extension {
+    /// Identifier of the extension, of the form `{publisher}.{name}`
+    pub id: String,
+    /// Installed version, if known
+    pub version: Option<String>,
+}
+
+impl Extension {
+    /// URL of the extension's page on the marketplace used by the flavor
+    ///
+    /// Open source flavors use the [Open VSX Registry](https://open-vsx.org),
+    /// while the proprietary ones use the [Visual Studio Marketplace](https://marketplace.visualstudio.com).
+    pub fn page_url(&self, flavor: &dyn EditorFlavor) -> String {
+        match flavor.marketplace() {
+            Marketplace::VisualStudio => format!(
+                "https://marketplace.visualstudio.com/items?itemName={}",
+                self.id
+            ),
+            Marketplace::OpenVsx => {
+                let (publisher, name) = self.id.split_once('.').unwrap_or(("", &self.id));
+                format!("https://open-vsx.org/extension/{}/{}", publisher, name)
+            }
+        }
+    }
+}
+
+/// Run the flavor's command with the given arguments and return its output
+fn run_cli(flavor: &dyn EditorFlavor, args: &[&str]) -> Result<String> {
+    let output = flavor
+        .command()
+        .args(args)
+        .output()
+        .with_context(|| format!("Could not execute {}", flavor.cmd()))?;
+    if !output.status.success() {
+        return Err(other!(
+            "{} {} failed: {}",
+            flavor.cmd(),
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parse the output of `--list-extensions --show-versions`, one extension per line
+fn parse_extensions(output: &str) -> Vec<Extension> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| match l.split_once('@') {
+            Some((id, version)) => Extension {
+                id: id.to_string(),
+                version: Some(version.to_string()),
+            },
+            None => Extension {
+                id: l.to_string(),
+                version: None,
+            },
+        })
+        .collect()
+}
+
+/// Get the extensions installed for a specific flavor
+///
+/// # Errors
+/// The call fails if [EditorFlavor::cmd()] cannot be executed or it reports an error.
+pub fn installed_extensions(flavor: &dyn EditorFlavor) -> Result<Vec<Extension>> {
+    run_cli(flavor, &["--list-extensions", "--show-versions"]).map(|o| parse_extensions(&o))
+}
+
+/// Uninstall an extension, waiting for the command to complete
+///
+/// # Errors
+/// The call fails if [EditorFlavor::cmd()] cannot be executed or it reports an error.
+pub fn uninstall_extension(flavor: &dyn EditorFlavor, extension: &Extension) -> Result<()> {
+    run_cli(flavor, &["--uninstall-extension", &extension.id]).map(|_| ())
+}
+
+/// Open a new window with the given extension disabled
+///
+/// The extension is disabled only for the new window's session.
+///
+/// # Errors
+/// Opening the window may fail if [EditorFlavor::cmd()] is not found in `PATH`.
+pub fn open_with_extension_disabled(
+    flavor: &dyn EditorFlavor,
+    extension: &Extension,
+) -> Result<()> {
+    flavor
+        .command()
+        .arg("--new-window")
+        .arg("--disable-extension")
+        .arg(&extension.id)
+        .spawn()
+        .map(|_| ())
+        .with_context(|| format!("Could not execute {}", flavor.cmd()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_extensions, Extension};
+    use crate::vscode::Flavor;
+
+    #[test]
+    fn parse_extension_list() {
+        let output =
+            "rust-lang.rust-analyzer@0.3.2177\nms-python.python@2024.20.0\n\nvscodevim.vim\n";
+
+        let extensions = parse_extensions(output);
+        assert_eq!(
+            extensions,
+            vec![
+                Extension {
+                    id: "rust-lang.rust-analyzer".to_string(),
+                    version: Some("0.3.2177".to_string()),
+                },
+                Extension {
+                    id: "ms-python.python".to_string(),
+                    version: Some("2024.20.0".to_string()),
+                },
+                Extension {
+                    id: "vscodevim.vim".to_string(),
+                    version: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn extension_page_url() {
+        let extension = Extension {
+            id: "rust-lang.rust-analyzer".to_string(),
+            version: None,
+        };
+        assert_eq!(
+            extension.page_url(&Flavor::Code),
+            "https://marketplace.visualstudio.com/items?itemName=rust-lang.rust-analyzer"
+        );
+        assert_eq!(
+            extension.page_url(&Flavor::VSCodium),
+            "https://open-vsx.org/extension/rust-lang/rust-analyzer"
+        );
+    }
+}
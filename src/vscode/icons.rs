@@ -0,0 +1,375 @@
+//! Icon names of the recent items, picked from cheap checks of their paths
+//!
+//! The names follow the [Freedesktop naming spec](https://specifications.freedesktop.org/icon-naming-spec/latest/ar01s04.html)
+//! where it has one, and otherwise the names used by popular themes like Papirus and Breeze,
+//! e.g. `folder-git` or the MIME type of a file with `/` replaced by `-`.
+//!
+//! The MIME type of a file is looked up by its name in the [shared-mime-info](https://specifications.freedesktop.org/shared-mime-info-spec/latest/)
+//! database, as `xdg-mime` does, falling back to a few well-known extensions if the database is not installed.
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use url::Url;
+
+use crate::utils::{matches_wildcard, md5_hex};
+
+/// Icon of a generic folder
+pub const FOLDER_ICON_NAME: &str = "folder";
+/// Icon of a folder on another machine
+pub const REMOTE_FOLDER_ICON_NAME: &str = "folder-remote";
+/// Icon of a git repository
+pub const GIT_FOLDER_ICON_NAME: &str = "folder-git";
+/// Icon of a GitHub repository opened as a virtual workspace
+pub const GITHUB_ICON_NAME: &str = "github";
+/// Icon of a generic text file
+pub const FILE_ICON_NAME: &str = "text-x-generic";
+
+/// Icons of the files with a known extension, compared without case
+const EXTENSION_ICON_NAMES: &[(&str, &str)] = &[
+    ("c", "text-x-csrc"),
+    ("h", "text-x-chdr"),
+    ("cc", "text-x-c++src"),
+    ("cpp", "text-x-c++src"),
+    ("cxx", "text-x-c++src"),
+    ("hpp", "text-x-c++hdr"),
+    ("cs", "text-x-csharp"),
+    ("css", "text-css"),
+    ("go", "text-x-go"),
+    ("html", "text-html"),
+    ("htm", "text-html"),
+    ("java", "text-x-java"),
+    ("js", "application-javascript"),
+    ("mjs", "application-javascript"),
+    ("ts", "text-x-typescript"),
+    ("json", "application-json"),
+    ("jsonc", "application-json"),
+    ("md", "text-markdown"),
+    ("markdown", "text-markdown"),
+    ("py", "text-x-python"),
+    ("rb", "application-x-ruby"),
+    ("rs", "text-x-rust"),
+    ("sh", "application-x-shellscript"),
+    ("bash", "application-x-shellscript"),
+    ("zsh", "application-x-shellscript"),
+    ("tex", "text-x-tex"),
+    ("toml", "application-toml"),
+    ("xml", "text-xml"),
+    ("yaml", "application-x-yaml"),
+    ("yml", "application-x-yaml"),
+    ("sql", "application-sql"),
+    ("png", "image-x-generic"),
+    ("jpg", "image-x-generic"),
+    ("jpeg", "image-x-generic"),
+    ("svg", "image-svg+xml"),
+    ("pdf", "application-pdf"),
+];
+
+/// A glob of the shared-mime-info database, from a line of a `globs2` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MimeGlob {
+    /// Priority over the other matching globs, between 0 and 100
+    weight: u32,
+    mime_type: String,
+    pattern: String,
+    /// Whether the pattern is matched with case, otherwise it is lowercase
+    case_sensitive: bool,
+}
+
+/// The parts of the shared-mime-info database needed to pick the icons of files
+#[derive(Debug, Default)]
+struct MimeDatabase {
+    globs: Vec<MimeGlob>,
+    /// Icons of the MIME types
+    icons: HashMap<String, String>,
+}
+
+impl MimeDatabase {
+    /// Read the database from the `mime` directories under the XDG data directories
+    ///
+    /// The directories of the user come first, so their icons take precedence over the system ones.
+    fn read() -> Self {
+        let mut dirs: Vec<PathBuf> = dirs::data_dir().into_iter().collect();
+        let system_dirs =
+            env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+        dirs.extend(env::split_paths(&system_dirs));
+
+        let mut globs = String::new();
+        let mut icons = String::new();
+        for dir in dirs.iter().map(|d| d.join("mime")) {
+            globs.extend(fs::read_to_string(dir.join("globs2")));
+            icons.extend(fs::read_to_string(dir.join("icons")));
+        }
+        Self::parse(&globs, &icons)
+    }
+
+    /// Build the database from the contents of the `globs2` and `icons` files
+    ///
+    /// The MIME types without an entry in `icons` get the type with `/` replaced by `-`.
+    fn parse(globs: &str, icons: &str) -> Self {
+        let mut db = MimeDatabase {
+            globs: parse_globs(globs).collect(),
+            icons: HashMap::new(),
+        };
+        for (mime_type, icon) in parse_icons(icons) {
+            db.icons.entry(mime_type).or_insert(icon);
+        }
+        for glob in &db.globs {
+            db.icons
+                .entry(glob.mime_type.clone())
+                .or_insert_with(|| glob.mime_type.replace('/', "-"));
+        }
+        db
+    }
+
+    /// MIME type of the file named `name`, from the matching glob with the highest weight
+    ///
+    /// Among globs of the same weight the longest one wins, as it is the most specific.
+    fn mime_type(&self, name: &str) -> Option<&str> {
+        let lowercase = name.to_lowercase();
+        self.globs
+            .iter()
+            .filter(|g| {
+                let name = if g.case_sensitive { name } else { &lowercase };
+                matches_wildcard(&g.pattern, name)
+            })
+            .max_by_key(|g| (g.weight, g.pattern.len()))
+            .map(|g| g.mime_type.as_str())
+    }
+
+    /// Icon of the file named `name`, from its MIME type
+    fn icon(&self, name: &str) -> Option<&str> {
+        self.icons.get(self.mime_type(name)?).map(String::as_str)
+    }
+}
+
+/// Parse the lines `weight:type:glob[:flags]` of a `globs2` file, skipping comments
+fn parse_globs(contents: &str) -> impl Iterator<Item = MimeGlob> + '_ {
+    contents
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, ':');
+            let weight = fields.next()?.parse().ok()?;
+            let mime_type = fields.next()?.to_string();
+            let pattern = fields.next()?;
+            let case_sensitive = fields
+                .next()
+                .is_some_and(|f| f.split(',').any(|f| f == "cs"));
+            Some(MimeGlob {
+                weight,
+                mime_type,
+                pattern: if case_sensitive {
+                    pattern.to_string()
+                } else {
+                    pattern.to_lowercase()
+                },
+                case_sensitive,
+            })
+        })
+}
+
+/// Parse the lines `type:icon` of an `icons` file
+fn parse_icons(contents: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    contents.lines().filter_map(|line| {
+        let (mime_type, icon) = line.split_once(':')?;
+        Some((mime_type.to_string(), icon.to_string()))
+    })
+}
+
+/// The shared-mime-info database, read once
+fn mime_database() -> &'static MimeDatabase {
+    static MIME_DATABASE: OnceLock<MimeDatabase> = OnceLock::new();
+    MIME_DATABASE.get_or_init(MimeDatabase::read)
+}
+
+/// The special directories of the user with their icons, resolved once
+fn special_dirs() -> &'static [(PathBuf, &'static str)] {
+    static SPECIAL_DIRS: OnceLock<Vec<(PathBuf, &'static str)>> = OnceLock::new();
+    SPECIAL_DIRS.get_or_init(|| {
+        [
+            (dirs::home_dir(), "user-home"),
+            (dirs::desktop_dir(), "user-desktop"),
+            (dirs::document_dir(), "folder-documents"),
+            (dirs::download_dir(), "folder-download"),
+            (dirs::audio_dir(), "folder-music"),
+            (dirs::picture_dir(), "folder-pictures"),
+            (dirs::video_dir(), "folder-videos"),
+            (dirs::public_dir(), "folder-publicshare"),
+            (dirs::template_dir(), "folder-templates"),
+        ]
+        .into_iter()
+        .filter_map(|(dir, name)| dir.map(|d| (d, name)))
+        .collect()
+    })
+}
+
+/// Icon of the local folder at `path`
+///
+/// The special directories of the user, like the documents, have their own icons,
+/// as do git repositories; any other folder gets the generic icon.
+pub fn folder_icon_name(path: &Path) -> &'static str {
+    if let Some((_, name)) = special_dirs().iter().find(|(dir, _)| dir == path) {
+        return name;
+    }
+    if path.join(".git").exists() {
+        return GIT_FOLDER_ICON_NAME;
+    }
+    FOLDER_ICON_NAME
+}
+
+/// Icon of the file at `path`, derived from the MIME type of its name
+///
+/// The file itself is not read, so this works for remote files as well.
+pub fn file_icon_name(path: &Path) -> &'static str {
+    let from_database = path
+        .file_name()
+        .and_then(|name| mime_database().icon(&name.to_string_lossy()));
+    from_database.unwrap_or_else(|| extension_icon_name(path))
+}
+
+/// Sizes of the thumbnails in the XDG cache, by their directory, from the smallest
+const THUMBNAIL_SIZES: &[(&str, u32)] = &[
+    ("normal", 128),
+    ("large", 256),
+    ("x-large", 512),
+    ("xx-large", 1024),
+];
+
+/// Whether the file at `path` is an image, judging from its name
+pub fn is_image(path: &Path) -> bool {
+    let from_database = path.file_name().and_then(|name| {
+        mime_database()
+            .mime_type(&name.to_string_lossy())
+            .map(|m| m.starts_with("image/"))
+    });
+    from_database.unwrap_or_else(|| extension_icon_name(path).starts_with("image-"))
+}
+
+/// Image to show as the icon of the local image at `path`, scaled down to `height` pixels
+///
+/// This is the thumbnail of the file from the [XDG thumbnail cache](https://specifications.freedesktop.org/thumbnail-spec/latest/),
+/// the smallest one at least as tall as `height` if there are several,
+/// or else the image itself if there is no thumbnail or it is older than the file.
+/// Returns `None` if the file is not an image or does not exist.
+pub fn thumbnail(path: &Path, height: u32) -> Option<PathBuf> {
+    if !is_image(path) {
+        return None;
+    }
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let cache = dirs::cache_dir()?.join("thumbnails");
+    let name = format!(
+        "{}.png",
+        md5_hex(Url::from_file_path(path).ok()?.as_str().as_bytes())
+    );
+    let fresh: Vec<(u32, PathBuf)> = THUMBNAIL_SIZES
+        .iter()
+        .map(|(dir, size)| (*size, cache.join(dir).join(&name)))
+        .filter(|(_, thumb)| {
+            fs::metadata(thumb)
+                .and_then(|m| m.modified())
+                .is_ok_and(|t| t >= modified)
+        })
+        .collect();
+    let best = fresh
+        .iter()
+        .find(|(size, _)| *size >= height)
+        .or(fresh.last())
+        .map(|(_, thumb)| thumb.clone());
+    Some(best.unwrap_or_else(|| path.to_path_buf()))
+}
+
+/// Icon of the file at `path` from the well-known extensions, for when there is no MIME database
+fn extension_icon_name(path: &Path) -> &'static str {
+    let Some(extension) = path.extension().map(|e| e.to_string_lossy().to_lowercase()) else {
+        return FILE_ICON_NAME;
+    };
+    EXTENSION_ICON_NAMES
+        .iter()
+        .find(|(e, _)| *e == extension)
+        .map_or(FILE_ICON_NAME, |(_, name)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path, process};
+
+    use super::{extension_icon_name, folder_icon_name, thumbnail, MimeDatabase};
+
+    #[test]
+    fn file_icons() {
+        assert_eq!(
+            extension_icon_name(Path::new("/srv/farm/main.rs")),
+            "text-x-rust"
+        );
+        assert_eq!(
+            extension_icon_name(Path::new("/srv/farm/README.MD")),
+            "text-markdown"
+        );
+        assert_eq!(
+            extension_icon_name(Path::new("/srv/farm/beets.xyz")),
+            "text-x-generic"
+        );
+        assert_eq!(
+            extension_icon_name(Path::new("/srv/farm/Makefile")),
+            "text-x-generic"
+        );
+    }
+
+    #[test]
+    fn mime_database_icons() {
+        let globs = "\
+# This file was automatically generated
+60:text/x-python:*.py
+50:text/x-python3:*.py
+50:text/rust:*.rs
+50:text/x-makefile:makefile
+50:text/x-makefile:*.mk
+50:text/x-readme:README*
+50:text/x-csrc:*.c:cs
+50:text/x-c++src:*.C:cs
+";
+        let icons = "application/x-python-bytecode:text-x-python\ntext/x-csrc:text-x-c\n";
+        let db = MimeDatabase::parse(globs, icons);
+        assert_eq!(db.icon("main.rs"), Some("text-rust"));
+        assert_eq!(db.icon("beets.py"), Some("text-x-python"));
+        assert_eq!(db.icon("Makefile"), Some("text-x-makefile"));
+        assert_eq!(db.icon("README.md"), Some("text-x-readme"));
+        assert_eq!(db.icon("farm.c"), Some("text-x-c"));
+        assert_eq!(db.icon("farm.C"), Some("text-x-c++src"));
+        assert_eq!(db.icon("beets.xyz"), None);
+    }
+
+    #[test]
+    fn image_thumbnails() {
+        let dir = env::temp_dir().join(format!("rofi-vscode-mode-thumbs-{}", process::id()));
+        fs::create_dir_all(&dir).expect("could not create folder");
+        let image = dir.join("beets.png");
+        let text = dir.join("beets.txt");
+        fs::write(&image, b"").expect("could not write image");
+        fs::write(&text, b"").expect("could not write file");
+        // Without a thumbnail in the cache the image is shown itself
+        assert_eq!(thumbnail(&image, 32), Some(image.clone()));
+        assert_eq!(thumbnail(&text, 32), None);
+        assert_eq!(thumbnail(&dir.join("missing.png"), 32), None);
+        fs::remove_dir_all(&dir).expect("could not remove folder");
+    }
+
+    #[test]
+    fn folder_icons() {
+        let dir = env::temp_dir().join(format!("rofi-vscode-mode-icons-{}", process::id()));
+        fs::create_dir_all(&dir).expect("could not create folder");
+        assert_eq!(folder_icon_name(&dir), "folder");
+        fs::create_dir(dir.join(".git")).expect("could not create repository");
+        assert_eq!(folder_icon_name(&dir), "folder-git");
+        fs::remove_dir_all(&dir).expect("could not remove folder");
+
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(folder_icon_name(&home), "user-home");
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! JSON with comments
+//!
+//! VSCode configuration files (e.g. `tasks.json`, `.code-workspace`) allow comments and trailing commas,
+//! which must be stripped before parsing them as standard JSON.
+
+use serde::de::DeserializeOwned;
+
+/// Deserialize an instance of `T` from a string of JSON with comments
+pub fn from_str<T: DeserializeOwned>(s: &str) -> serde_json::Result<T> {
+    serde_json::from_str(&strip(s))
+}
+
+/// Remove comments and trailing commas, leaving strings untouched
+fn strip(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    // Position in `out` of a comma that may turn out to be trailing
+    let mut pending_comma: Option<usize> = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                pending_comma = None;
+                out.push(c);
+                // Copy the string literal, including escapes
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => {
+                            if let Some(escaped) = chars.next() {
+                                out.push(escaped);
+                            }
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                // Line comment
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push(c);
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                // Block comment
+                chars.next();
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            ',' => {
+                pending_comma = Some(out.len());
+                out.push(c);
+            }
+            '}' | ']' => {
+                if let Some(pos) = pending_comma.take() {
+                    out.remove(pos);
+                }
+                out.push(c);
+            }
+            c if c.is_whitespace() => out.push(c),
+            c => {
+                pending_comma = None;
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::from_str;
+
+    #[test]
+    fn parse_json_with_comments() {
+        let s = r#"{
+            // The tasks
+            "tasks": [
+                { "label": "build", /* inline */ "command": "make // not a comment", },
+            ],
+            "url": "https://example.com/a,]",
+        }"#;
+
+        let value: Value = from_str(s).expect("could not parse");
+        assert_eq!(
+            value,
+            json!({
+                "tasks": [{ "label": "build", "command": "make // not a comment" }],
+                "url": "https://example.com/a,]",
+            })
+        );
+    }
+}
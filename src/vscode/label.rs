@@ -0,0 +1,270 @@
+//! Custom labels of the recent items
+//!
+//! A [LabelTemplate] lays out the label of each item from placeholders, e.g. `{basename}  {dir}  {remote}`,
+//! instead of the label stored by VSCode or the tildified path.
+//! Each [Field] can also be rendered on its own, e.g. as a column of tabular output.
+
+use std::{borrow::Cow, fmt, path::Path, str::FromStr};
+
+use percent_encoding::percent_decode_str;
+
+use super::{tildify, workspaces::Recent};
+use crate::error::{other, Error, Result};
+use crate::remote;
+
+/// Extension of the configuration files of multi-root workspaces, left out of their base name
+const WORKSPACE_EXTENSION: &str = ".code-workspace";
+
+/// A placeholder of a [LabelTemplate]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// The usual label, see [Recent::label]
+    Label,
+    /// The last component of the path
+    Basename,
+    /// The directory containing the item, tildified
+    Dir,
+    /// The whole path, tildified
+    Path,
+    /// The kind of the item, see [super::workspaces::RecentKind]
+    Kind,
+    /// The remote where the item is located, if any, see [remote::describe]
+    Remote,
+    /// The name of the flavor or the source the item comes from
+    Flavor,
+    /// The URI of the item
+    Uri,
+}
+
+impl Field {
+    /// The value of the field for `recent`, coming from the flavor or source named `flavor`
+    ///
+    /// Fields that do not apply to the item, e.g. `remote` for local items, are empty.
+    ///
+    /// # Errors
+    /// The call fails in the same cases as [Recent::label], for [Field::Label].
+    pub fn render(&self, recent: &Recent, flavor: &str) -> Result<String> {
+        Ok(match self {
+            Field::Label => recent.label()?.into_owned(),
+            Field::Basename => basename(recent, &display_path(recent)).into_owned(),
+            Field::Dir => dir(&display_path(recent)),
+            Field::Path => display_path(recent),
+            Field::Kind => recent.kind().to_string(),
+            Field::Remote => recent.remote().map(remote::describe).unwrap_or_default(),
+            Field::Flavor => flavor.to_string(),
+            Field::Uri => recent.url().to_string(),
+        })
+    }
+}
+
+impl FromStr for Field {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "label" => Ok(Self::Label),
+            "basename" => Ok(Self::Basename),
+            "dir" => Ok(Self::Dir),
+            "path" => Ok(Self::Path),
+            "kind" => Ok(Self::Kind),
+            "remote" => Ok(Self::Remote),
+            "flavor" => Ok(Self::Flavor),
+            "uri" => Ok(Self::Uri),
+            _ => Err(other!(
+                "Unrecognized field {}, expected one of label, basename, dir, path, kind, remote, flavor, uri",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Field::Label => "label",
+            Field::Basename => "basename",
+            Field::Dir => "dir",
+            Field::Path => "path",
+            Field::Kind => "kind",
+            Field::Remote => "remote",
+            Field::Flavor => "flavor",
+            Field::Uri => "uri",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A part of a [LabelTemplate]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Field(Field),
+}
+
+/// A layout of the labels of the recent items, with placeholders between braces
+///
+/// The placeholders are `{label}`, `{basename}`, `{dir}`, `{path}`, `{kind}`, `{remote}`, `{flavor}` and `{uri}`, see [Field],
+/// while `{{` and `}}` stand for literal braces.
+/// The placeholders that do not apply to an item, e.g. `{remote}` for local items, are left empty,
+/// and the label is trimmed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelTemplate(Vec<Segment>);
+
+impl FromStr for LabelTemplate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut rest = s;
+        while let Some(i) = rest.find(['{', '}']) {
+            text.push_str(&rest[..i]);
+            let (brace, after) = rest[i..].split_at(1);
+            if after.starts_with(brace) {
+                // Escaped brace
+                text.push_str(brace);
+                rest = &after[1..];
+                continue;
+            }
+            if brace == "}" {
+                return Err(other!("Unmatched }} in label template {}", s));
+            }
+            let end = after
+                .find('}')
+                .ok_or_else(|| other!("Unterminated placeholder in label template {}", s))?;
+            if !text.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut text)));
+            }
+            segments.push(Segment::Field(after[..end].trim().parse()?));
+            rest = &after[end + 1..];
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(LabelTemplate(segments))
+    }
+}
+
+impl LabelTemplate {
+    /// The label of `recent`, coming from the flavor or source named `flavor`
+    ///
+    /// # Errors
+    /// The call fails in the same cases as [Recent::label], if the template uses `{label}`.
+    pub fn render(&self, recent: &Recent, flavor: &str) -> Result<String> {
+        let mut label = String::new();
+        for segment in &self.0 {
+            match segment {
+                Segment::Text(text) => label.push_str(text),
+                Segment::Field(field) => label.push_str(&field.render(recent, flavor)?),
+            }
+        }
+        Ok(label.trim().to_string())
+    }
+}
+
+/// Path of the item shown to the user, tildified if it is local, or prefixed by its repository if it is on GitHub
+fn display_path(recent: &Recent) -> String {
+    if let Some(location) = remote::github_location(recent.url()) {
+        return location.display();
+    }
+    match recent.file_path() {
+        Ok(path) => tildify(&path),
+        Err(_) => percent_decode_str(recent.url().path())
+            .decode_utf8_lossy()
+            .into_owned(),
+    }
+}
+
+/// The last component of `path`, without the extension of workspaces
+fn basename<'a>(recent: &Recent, path: &'a str) -> Cow<'a, str> {
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or(Cow::Borrowed(path));
+    match recent {
+        Recent::Workspace { .. } => match name.strip_suffix(WORKSPACE_EXTENSION) {
+            Some(stem) => Cow::Owned(stem.to_string()),
+            None => name,
+        },
+        _ => name,
+    }
+}
+
+/// The directory containing `path`
+fn dir(path: &str) -> String {
+    Path::new(path)
+        .parent()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Field, LabelTemplate};
+    use crate::vscode::workspaces::Recent;
+
+    #[test]
+    fn parse_templates() {
+        assert!("{basename}  {dir}".parse::<LabelTemplate>().is_ok());
+        assert!("{{literal}}".parse::<LabelTemplate>().is_ok());
+        assert!("{beets}".parse::<LabelTemplate>().is_err());
+        assert!("{basename".parse::<LabelTemplate>().is_err());
+        assert!("basename}".parse::<LabelTemplate>().is_err());
+    }
+
+    #[test]
+    fn render_labels() {
+        let template: LabelTemplate = "{basename}  {dir}  {remote}".parse().unwrap();
+        let local: Recent =
+            serde_json::from_value(json!({ "folderUri": "file:///srv/beet-farm" })).unwrap();
+        assert_eq!(template.render(&local, "code").unwrap(), "beet-farm  /srv");
+
+        let remote: Recent = serde_json::from_value(json!({
+            "folderUri": "vscode-remote://ssh-remote%2Bdevbox/srv/beet%20farm",
+            "remoteAuthority": "ssh-remote+devbox",
+        }))
+        .unwrap();
+        assert_eq!(
+            template.render(&remote, "code").unwrap(),
+            "beet farm  /srv  SSH: devbox"
+        );
+
+        let template: LabelTemplate = "{{{kind}}} {basename} ({flavor})".parse().unwrap();
+        let workspace: Recent = serde_json::from_value(json!({
+            "workspace": {
+                "id": "0dd79faa9035cb0db768dbe5812a110c",
+                "configPath": "file:///srv/farm.code-workspace"
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            template.render(&workspace, "codium").unwrap(),
+            "{workspace} farm (codium)"
+        );
+    }
+
+    #[test]
+    fn render_fields() {
+        let remote: Recent = serde_json::from_value(json!({
+            "fileUri": "vscode-remote://ssh-remote%2Bdevbox/srv/beets.txt",
+            "remoteAuthority": "ssh-remote+devbox",
+        }))
+        .unwrap();
+        let render = |name: &str| {
+            let field: Field = name.parse().unwrap();
+            assert_eq!(field.to_string(), name);
+            field.render(&remote, "code").unwrap()
+        };
+        assert_eq!(render("kind"), "file");
+        assert_eq!(render("dir"), "/srv");
+        assert_eq!(render("remote"), "SSH: devbox");
+        assert_eq!(
+            render("uri"),
+            "vscode-remote://ssh-remote%2Bdevbox/srv/beets.txt"
+        );
+        assert!("beets".parse::<Field>().is_err());
+    }
+}
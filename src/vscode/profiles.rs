@@ -0,0 +1,136 @@
+//! VSCode profiles management
+//!
+//! Profiles are registered in the global `storage.json` file under the `userDataProfiles` key,
+//! while their data is kept in `$CODE_CONFIG_DIR/User/profiles/{location}/`,
+//! including their own global state in `globalStorage/state.vscdb`.
+//!
+//! For reference see VSCode's source code:
+//! - [User Data Profile service](https://github.com/microsoft/vscode/blob/main/src/vs/platform/userDataProfile/common/userDataProfile.ts)
+
+use super::EditorFlavor;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::error::{other, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+const STORAGE_PROFILES_KEY: &str = "userDataProfiles";
+
+/// A VSCode profile
+///
+/// See [this documentation article](https://code.visualstudio.com/docs/editor/profiles) for reference.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    /// Name of the profile, which is shown to the user and passed to `--profile`
+    pub name: String,
+    /// Name of the folder holding the profile's data inside `User/profiles/`
+    pub location: String,
+    /// Codicon associated to the profile, if any
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+/// Extract the profiles from the parsed contents of `storage.json`
+///
+/// A missing key is not an error, since it just means that no profile was ever created.
+fn parse_profiles(storage: &Value) -> Result<Vec<Profile>> {
+    match storage.get(STORAGE_PROFILES_KEY) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(profiles) => serde_json::from_value(profiles.to_owned()).with_context(|| {
+            format!(
+                "Could not deserialize \"{}\" from storage",
+                STORAGE_PROFILES_KEY
+            )
+        }),
+    }
+}
+
+/// Get the profiles from the `storage.json` inside the given `config_dir` configuration directory
+fn get_profiles(config_dir: &Path) -> Result<Vec<Profile>> {
+    let storage_path = config_dir
+        .join("User")
+        .join("globalStorage")
+        .join("storage.json");
+
+    let contents = fs::read_to_string(&storage_path)
+        .with_context(|| format!("Could not read storage file {:?}", &storage_path))?;
+    let storage: Value = serde_json::from_str(&contents)
+        .with_context(|| format!("Could not parse storage file {:?}", &storage_path))?;
+
+    parse_profiles(&storage)
+}
+
+/// Get the profiles defined for a specific flavor
+///
+/// The default profile is not included, since it is always available.
+pub fn profiles_from_storage(flavor: &dyn EditorFlavor) -> Result<Vec<Profile>> {
+    let config_dir = flavor.config_dir().ok_or_else(|| {
+        other!(
+            "Could not find configuration directory for \"{:?}\"",
+            flavor
+        )
+    })?;
+    get_profiles(&config_dir)
+}
+
+/// Path of the state database of `profile` inside the `config_dir` configuration directory
+pub fn profile_state_db(config_dir: &Path, profile: &Profile) -> PathBuf {
+    config_dir
+        .join("User")
+        .join("profiles")
+        .join(&profile.location)
+        .join("globalStorage")
+        .join("state.vscdb")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use std::path::Path;
+
+    use super::{parse_profiles, profile_state_db};
+
+    #[test]
+    fn parse_storage_with_profiles() {
+        let storage = json!({
+            "userDataProfiles": [
+                {
+                    "location": "-5a3b2d1c",
+                    "name": "Scranton Branch",
+                    "icon": "briefcase",
+                },
+                {
+                    "location": "7f0e9d8c",
+                    "name": "Schrute Farms",
+                    "useDefaultFlags": { "settings": true },
+                },
+            ],
+            "theme": "vs-dark",
+        });
+
+        let profiles = parse_profiles(&storage).expect("could not parse profiles");
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].name, "Scranton Branch");
+        assert_eq!(profiles[0].location, "-5a3b2d1c");
+        assert_eq!(profiles[0].icon.as_deref(), Some("briefcase"));
+        assert_eq!(profiles[1].name, "Schrute Farms");
+        assert!(profiles[1].icon.is_none());
+        assert_eq!(
+            profile_state_db(Path::new("/home/dwight/.config/Code"), &profiles[1]),
+            Path::new("/home/dwight/.config/Code/User/profiles/7f0e9d8c/globalStorage/state.vscdb")
+        );
+    }
+
+    #[test]
+    fn parse_storage_without_profiles() {
+        let storage = json!({ "theme": "vs-dark" });
+
+        let profiles = parse_profiles(&storage).expect("could not parse profiles");
+        assert!(profiles.is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! Projects saved with the Project Manager extension
+//!
+//! The [Project Manager](https://marketplace.visualstudio.com/items?itemName=alefragnani.project-manager)
+//! extension keeps the projects curated by the user in
+//! `$CODE_CONFIG_DIR/User/globalStorage/alefragnani.project-manager/projects.json`.
+
+use super::{
+    untildify,
+    workspaces::{Recent, WorkspaceIdentifier},
+    EditorFlavor,
+};
+use std::{fs, path::Path};
+
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+use url::Url;
+
+use crate::error::{other, Context, Result};
+
+const EXTENSION_ID: &str = "alefragnani.project-manager";
+const PROJECTS_FILE: &str = "projects.json";
+const WORKSPACE_EXTENSION: &str = ".code-workspace";
+const SCHEME_REMOTE: &str = "vscode-remote";
+
+/// A project saved with the Project Manager extension
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    /// Name of the project, which is shown to the user
+    pub name: String,
+    /// Location of the project's folder or `.code-workspace` file
+    ///
+    /// It is either a local path, which may start with `~` or `$home`,
+    /// or an URI such as `vscode-remote://ssh-remote+{host}/{path}`.
+    pub root_path: String,
+    /// Tags assigned by the user to group projects
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Whether the project is enabled, disabled ones are hidden by the extension
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl Project {
+    /// Location of the project as an URL
+    ///
+    /// # Errors
+    /// The call fails if the root path is neither a valid URI nor an absolute path.
+    pub fn url(&self) -> Result<Url> {
+        let root_path = self.root_path.trim();
+        if root_path.contains("://") {
+            return Url::parse(root_path)
+                .with_context(|| format!("Invalid URI for project \"{}\"", self.name));
+        }
+        let path = match root_path.strip_prefix("$home") {
+            Some(rest) => untildify(&format!("~{}", rest)),
+            None => untildify(root_path),
+        };
+        Url::from_file_path(&path)
+            .map_err(|_| other!("Invalid path {:?} for project \"{}\"", path, self.name))
+    }
+
+    /// Convert the project to a recent item, so that it can be opened in the same way
+    ///
+    /// Projects whose root path is a `.code-workspace` file are multi-root workspaces,
+    /// the others are folders. The label of the item is the project's name.
+    pub fn to_recent(&self) -> Result<Recent> {
+        let url = self.url()?;
+        let remote_authority = match url.scheme() {
+            SCHEME_REMOTE => url
+                .host_str()
+                .map(|h| percent_decode_str(h).decode_utf8_lossy().to_string()),
+            _ => None,
+        };
+        let label = Some(self.name.clone());
+        if url.path().ends_with(WORKSPACE_EXTENSION) {
+            Ok(Recent::Workspace {
+                workspace: WorkspaceIdentifier {
+                    // The identifier is not known, it is only used to locate the workspace storage
+                    id: String::new(),
+                    config_path: url,
+                },
+                label,
+                remote_authority,
+            })
+        } else {
+            Ok(Recent::Folder {
+                folder_uri: url,
+                label,
+                remote_authority,
+            })
+        }
+    }
+}
+
+/// Parse the contents of `projects.json`, keeping only the enabled projects
+fn parse_projects(contents: &str) -> Result<Vec<Project>> {
+    let projects: Vec<Project> =
+        serde_json::from_str(contents).context("Could not parse projects")?;
+    Ok(projects.into_iter().filter(|p| p.enabled).collect())
+}
+
+/// Get the projects from the extension's storage inside the given `config_dir` configuration directory
+///
+/// A missing file is not an error, since it just means that the extension is not installed.
+fn get_projects(config_dir: &Path) -> Result<Vec<Project>> {
+    let path = config_dir
+        .join("User")
+        .join("globalStorage")
+        .join(EXTENSION_ID)
+        .join(PROJECTS_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("Could not read {:?}", &path))?;
+    parse_projects(&contents).with_context(|| format!("Invalid projects file {:?}", &path))
+}
+
+/// Get the projects saved with the Project Manager extension for a specific flavor
+pub fn projects_from_storage(flavor: &dyn EditorFlavor) -> Result<Vec<Project>> {
+    let config_dir = flavor.config_dir().ok_or_else(|| {
+        other!(
+            "Could not find configuration directory for \"{:?}\"",
+            flavor
+        )
+    })?;
+    get_projects(&config_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_projects;
+    use crate::vscode::workspaces::Recent;
+
+    #[test]
+    fn parse_project_list() {
+        let contents = r#"[
+            {
+                "name": "beet-farm",
+                "rootPath": "$home/projects/beet-farm",
+                "paths": [],
+                "tags": ["work", "rust"],
+                "enabled": true
+            },
+            {
+                "name": "paper",
+                "rootPath": "vscode-remote://ssh-remote+scranton/srv/paper.code-workspace",
+                "paths": []
+            },
+            {
+                "name": "old",
+                "rootPath": "/tmp/old",
+                "enabled": false
+            }
+        ]"#;
+
+        let projects = parse_projects(contents).expect("could not parse");
+        assert_eq!(projects.len(), 2);
+        assert_eq!(projects[0].tags, vec!["work", "rust"]);
+
+        let home = dirs::home_dir().expect("no home directory");
+        let folder = projects[0].to_recent().expect("invalid project");
+        assert!(matches!(folder, Recent::Folder { .. }));
+        assert_eq!(
+            folder.file_path().expect("not a local project"),
+            home.join("projects/beet-farm")
+        );
+        assert_eq!(folder.label().expect("no label"), "beet-farm");
+
+        let workspace = projects[1].to_recent().expect("invalid project");
+        assert!(matches!(workspace, Recent::Workspace { .. }));
+        assert_eq!(workspace.remote(), Some("ssh-remote+scranton"));
+    }
+}
@@ -0,0 +1,168 @@
+//! VSCode Server installations
+//!
+//! When a client connects with [Remote SSH](https://code.visualstudio.com/docs/remote/ssh),
+//! the flavor installs its server on the host, e.g. in `~/.vscode-server/`,
+//! which keeps the state of the remote sessions in `data/` with the same layout as a configuration directory.
+//! On a headless host, this is where the recent items are read from.
+//!
+//! The remote command line that opens items is only available in the integrated terminal of the client.
+//! Elsewhere, the item is handed off instead, see [EditorFlavor::handoff_url]: its `vscode-remote://` URI
+//! is printed by the command line tool, or copied to the clipboard by the Rofi mode,
+//! and the client opens it with `code --folder-uri` or `--file-uri`.
+
+use std::{env, fs, path::PathBuf};
+
+use url::Url;
+use which::which;
+
+use super::{workspaces::Recent, EditorFlavor, Flavor, Marketplace};
+use crate::config;
+use crate::error::{other, Context, Result};
+
+const ENV_SERVER_HOST: &str = "ROFI_VSCODE_SERVER_HOST";
+
+/// The server of a flavor, installed in the home directory of a remote host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Server(pub Flavor);
+
+impl Server {
+    /// Name of the server's directory inside the home directory, if the flavor supports Remote SSH
+    fn home_subdir(&self) -> Option<&'static str> {
+        match self.0 {
+            Flavor::Code => Some(".vscode-server"),
+            Flavor::CodeInsiders => Some(".vscode-server-insiders"),
+            Flavor::CodeOSS => None,
+            Flavor::VSCodium => Some(".vscodium-server"),
+            Flavor::Cursor => Some(".cursor-server"),
+            Flavor::Windsurf => Some(".windsurf-server"),
+        }
+    }
+
+    /// Name shown to the user
+    fn server_name(&self) -> &'static str {
+        match self.0 {
+            Flavor::Code => "Code Server",
+            Flavor::CodeInsiders => "Code - Insiders Server",
+            Flavor::CodeOSS => "Code - OSS Server",
+            Flavor::VSCodium => "VSCodium Server",
+            Flavor::Cursor => "Cursor Server",
+            Flavor::Windsurf => "Windsurf Server",
+        }
+    }
+
+    /// The servers installed in the home directory, in the order of preference of the flavors
+    pub fn installed() -> Vec<Self> {
+        [
+            Flavor::VSCodium,
+            Flavor::CodeInsiders,
+            Flavor::Code,
+            Flavor::Cursor,
+            Flavor::Windsurf,
+        ]
+        .into_iter()
+        .map(Server)
+        .filter(|s| s.is_installed())
+        .collect()
+    }
+
+    /// The preferred server installed in the home directory, if any
+    pub fn detect() -> Option<Self> {
+        Self::installed().into_iter().next()
+    }
+}
+
+impl EditorFlavor for Server {
+    fn cmd(&self) -> &str {
+        self.0.cmd()
+    }
+
+    fn config_dir(&self) -> Option<PathBuf> {
+        let subdir = self.home_subdir()?;
+        dirs::home_dir()
+            .map(|h| h.join(subdir).join("data"))
+            .filter(|d| d.exists())
+    }
+
+    fn marketplace(&self) -> Marketplace {
+        self.0.marketplace()
+    }
+
+    fn name(&self) -> &str {
+        self.server_name()
+    }
+
+    /// Whether the server has kept any state, the command is not required
+    fn is_installed(&self) -> bool {
+        self.config_dir().is_some()
+    }
+
+    /// The remote URI of the item, unless the remote command line is available to open it
+    fn handoff_url(&self, recent: &Recent) -> Result<Option<Url>> {
+        if which(self.cmd()).is_ok() {
+            return Ok(None);
+        }
+        remote_url(recent, &server_host()?).map(Some)
+    }
+}
+
+/// Name of this host as the clients connect to it
+///
+/// It is the `ROFI_VSCODE_SERVER_HOST` setting, e.g. the alias of the host in the SSH configuration of the clients,
+/// or else the host name.
+///
+/// # Errors
+/// The call fails if the setting is not given and the host name cannot be read.
+pub fn server_host() -> Result<String> {
+    if let Some(host) = config::var(ENV_SERVER_HOST).filter(|h| !h.is_empty()) {
+        return Ok(host);
+    }
+    env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .ok_or_else(|| other!("Could not find the host name, set {}", ENV_SERVER_HOST))
+}
+
+/// URL of a local item as the clients connecting to `host` with Remote SSH open it
+///
+/// Items that are already remote or virtual are left as they are.
+///
+/// # Errors
+/// The call fails if the remote URL cannot be built, e.g. for an invalid `host`.
+pub fn remote_url(recent: &Recent, host: &str) -> Result<Url> {
+    let url = recent.url();
+    if !recent.is_local() {
+        return Ok(url.to_owned());
+    }
+    let remote = format!("vscode-remote://ssh-remote+{}{}", host, url.path());
+    Url::parse(&remote).with_context(|| format!("Could not build a remote URL for {}", url))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::remote_url;
+    use crate::vscode::workspaces::Recent;
+
+    #[test]
+    fn remote_urls() {
+        let folder: Recent =
+            serde_json::from_value(json!({ "folderUri": "file:///srv/beet%20farm" })).unwrap();
+        assert_eq!(
+            remote_url(&folder, "devbox").unwrap().as_str(),
+            "vscode-remote://ssh-remote+devbox/srv/beet%20farm"
+        );
+
+        let remote: Recent = serde_json::from_value(json!({
+            "folderUri": "vscode-remote://ssh-remote+paper/home/michael",
+            "remoteAuthority": "ssh-remote+paper"
+        }))
+        .unwrap();
+        assert_eq!(
+            remote_url(&remote, "devbox").unwrap().as_str(),
+            "vscode-remote://ssh-remote+paper/home/michael"
+        );
+    }
+}
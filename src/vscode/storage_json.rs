@@ -0,0 +1,190 @@
+//! Recent items in the `storage.json` file of older versions
+//!
+//! Before the state database was introduced, VSCode kept the recent items under the `openedPathsList`
+//! key of `storage.json`, either with the same `entries` as the database,
+//! or in a legacy format with separate lists of workspaces and files.
+
+use super::workspaces::{updated_history, Recent, WorkspaceIdentifier};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+use url::Url;
+
+use crate::error::{other, Context, Result};
+
+const STORAGE_HISTORY_KEY: &str = "openedPathsList";
+
+/// Path of the `storage.json` file inside the configuration directory, if it exists
+///
+/// Its location moved to `User/globalStorage/` over time, so both places are looked up.
+pub fn storage_json_path(config_dir: &Path) -> Option<PathBuf> {
+    [
+        config_dir
+            .join("User")
+            .join("globalStorage")
+            .join("storage.json"),
+        config_dir.join("storage.json"),
+    ]
+    .into_iter()
+    .find(|p| p.exists())
+}
+
+/// Label at index `i` of the legacy list of labels with the given `key`
+fn legacy_label(list: &Value, key: &str, i: usize) -> Option<String> {
+    list.get(key)?.get(i)?.as_str().map(String::from)
+}
+
+/// Parse the value of the `openedPathsList` key
+fn parse_history(list: &Value) -> Vec<Recent> {
+    if let Some(entries) = list.get("entries").and_then(Value::as_array) {
+        return entries
+            .iter()
+            .filter_map(|e| serde_json::from_value(e.to_owned()).ok())
+            .collect();
+    }
+
+    // Legacy format, workspaces are either folder URIs or workspace identifiers
+    let mut recents = Vec::new();
+    let workspaces = list.get("workspaces3").and_then(Value::as_array);
+    for (i, w) in workspaces.into_iter().flatten().enumerate() {
+        let label = legacy_label(list, "workspaceLabels", i);
+        let recent = match w {
+            Value::String(uri) => Url::parse(uri).ok().map(|folder_uri| Recent::Folder {
+                folder_uri,
+                label,
+                remote_authority: None,
+            }),
+            Value::Object(o) => {
+                let id = o.get("id").and_then(Value::as_str);
+                let config_path = o
+                    .get("configURIPath")
+                    .and_then(Value::as_str)
+                    .and_then(|p| Url::parse(p).ok());
+                id.zip(config_path)
+                    .map(|(id, config_path)| Recent::Workspace {
+                        workspace: WorkspaceIdentifier {
+                            id: id.to_string(),
+                            config_path,
+                        },
+                        label,
+                        remote_authority: None,
+                    })
+            }
+            _ => None,
+        };
+        recents.extend(recent);
+    }
+    let files = list.get("files2").and_then(Value::as_array);
+    for (i, f) in files.into_iter().flatten().enumerate() {
+        let file_uri = f.as_str().and_then(|uri| Url::parse(uri).ok());
+        recents.extend(file_uri.map(|file_uri| Recent::File {
+            file_uri,
+            label: legacy_label(list, "fileLabels", i),
+            remote_authority: None,
+        }));
+    }
+    recents
+}
+
+/// Read and parse `storage.json`
+fn read_storage(path: &Path) -> Result<Value> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Could not read storage file {:?}", path))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Could not parse storage file {:?}", path))
+}
+
+/// Get the recent items from the `storage.json` file at `path`
+///
+/// If `local_only` is set, recent items for which [Recent::is_local()] does not hold will be discarded.
+pub fn read_history(path: &Path, local_only: bool) -> Result<Vec<Recent>> {
+    let storage = read_storage(path)?;
+    let recents = storage
+        .get(STORAGE_HISTORY_KEY)
+        .map(parse_history)
+        .unwrap_or_default();
+    Ok(recents
+        .into_iter()
+        .filter(|r| !local_only || r.is_local())
+        .collect())
+}
+
+/// Whether the `storage.json` file at `path` holds a list of recent items
+pub fn has_history(path: &Path) -> Result<bool> {
+    Ok(read_storage(path)?.get(STORAGE_HISTORY_KEY).is_some())
+}
+
+/// Store the recent items into the `storage.json` file at `path`
+///
+/// Unless `keep_unknown` is unset, the previous entries that could not be read are kept.
+///
+/// # Errors
+/// Only the `entries` format can be written, the call fails if the file uses the legacy one.
+pub fn write_history(path: &Path, entries: &[Recent], keep_unknown: bool) -> Result<()> {
+    let mut storage = read_storage(path)?;
+    let Some(object) = storage.as_object_mut() else {
+        return Err(other!("Storage file {:?} is not an object", path));
+    };
+    if object
+        .get(STORAGE_HISTORY_KEY)
+        .is_some_and(|list| list.get("entries").is_none())
+    {
+        return Err(other!(
+            "Cannot update the legacy list of recent items in {:?}",
+            path
+        ));
+    }
+    let previous = object.remove(STORAGE_HISTORY_KEY);
+    object.insert(
+        STORAGE_HISTORY_KEY.to_string(),
+        updated_history(previous, entries, keep_unknown)?,
+    );
+
+    let contents = serde_json::to_string_pretty(&storage)?;
+    fs::write(path, contents).with_context(|| format!("Could not write storage file {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse_history;
+    use crate::vscode::workspaces::Recent;
+
+    #[test]
+    fn parse_legacy_history() {
+        let list = json!({
+            "workspaces3": [
+                "file:///home/dwight/beet-farm",
+                {
+                    "id": "0dd79faa9035cb0db768dbe5812a110cc0814402251661dc2d8973f89767444e",
+                    "configURIPath": "file:///home/dwight/farm.code-workspace"
+                },
+            ],
+            "files2": ["file:///home/dwight/notes.txt"],
+            "workspaceLabels": ["Beet Farm"],
+        });
+
+        let recents = parse_history(&list);
+        assert_eq!(recents.len(), 3);
+        assert!(matches!(recents[0], Recent::Folder { .. }));
+        assert_eq!(recents[0].label().expect("no label"), "Beet Farm");
+        assert!(matches!(recents[1], Recent::Workspace { .. }));
+        assert!(matches!(recents[2], Recent::File { .. }));
+        assert_eq!(recents[2].url().path(), "/home/dwight/notes.txt");
+    }
+
+    #[test]
+    fn parse_entries_history() {
+        let list = json!({
+            "entries": [
+                { "folderUri": "file:///home/dwight/beet-farm" },
+                { "fileUri": "file:///home/dwight/notes.txt" },
+            ],
+        });
+        assert_eq!(parse_history(&list).len(), 2);
+    }
+}
@@ -0,0 +1,374 @@
+//! VSCode tasks
+//!
+//! Tasks are defined in the `.vscode/tasks.json` file of each folder,
+//! or in the `tasks` attribute of a `.code-workspace` file.
+//! Only tasks that run a command (`shell`, `process` and `npm`) are supported,
+//! since the others are provided by extensions.
+//!
+//! See [this documentation article](https://code.visualstudio.com/docs/editor/tasks) for reference.
+
+use super::{
+    jsonc,
+    workspaces::{workspace_folders, Recent},
+};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde_json::Value;
+
+use crate::error::{other, Context, Result};
+
+/// How the command of a task is executed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskKind {
+    /// The command line is interpreted by a shell
+    Shell,
+    /// The command is executed directly with its arguments
+    Process,
+}
+
+/// A task that runs a command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    /// Name of the task
+    pub label: String,
+    /// How the command is executed
+    pub kind: TaskKind,
+    /// The command to execute
+    pub command: String,
+    /// Arguments of the command
+    pub args: Vec<String>,
+    /// Working directory of the command
+    pub cwd: PathBuf,
+}
+
+impl Task {
+    /// The full command line, with the arguments quoted for the shell
+    pub fn command_line(&self) -> String {
+        let mut line = self.command.clone();
+        for arg in &self.args {
+            line.push(' ');
+            line.push_str(&shell_quote(arg));
+        }
+        line
+    }
+
+    /// Run the task in the background
+    ///
+    /// If a `terminal` command is given (e.g. `alacritty -e`), the task is run inside it
+    /// so that its output can be seen.
+    ///
+    /// # Errors
+    /// The call fails if the command cannot be executed.
+    pub fn run(&self, terminal: Option<&str>) -> Result<()> {
+        let mut argv: Vec<String> = terminal
+            .map(|t| t.split_whitespace().map(String::from).collect())
+            .unwrap_or_default();
+        match self.kind {
+            TaskKind::Shell => {
+                argv.extend(["sh".to_string(), "-c".to_string(), self.command_line()])
+            }
+            TaskKind::Process => {
+                argv.push(self.command.clone());
+                argv.extend(self.args.iter().cloned());
+            }
+        }
+
+        let (program, args) = argv
+            .split_first()
+            .ok_or_else(|| other!("Task \"{}\" has an empty command", self.label))?;
+        Command::new(program)
+            .args(args)
+            .current_dir(&self.cwd)
+            .spawn()
+            .map(|_| ())
+            .with_context(|| format!("Could not run task \"{}\"", self.label))
+    }
+}
+
+/// Quote a string so that it is interpreted literally by the shell
+fn shell_quote(s: &str) -> String {
+    let safe = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c));
+    if safe {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+/// Replace the predefined variables supported outside of VSCode
+///
+/// See the [variables reference](https://code.visualstudio.com/docs/editor/variables-reference).
+fn substitute_variables(s: &str, folder: &Path) -> String {
+    let mut s = s
+        .replace("${workspaceFolder}", &folder.to_string_lossy())
+        .replace("${workspaceRoot}", &folder.to_string_lossy())
+        .replace(
+            "${workspaceFolderBasename}",
+            &folder
+                .file_name()
+                .map(|n| n.to_string_lossy())
+                .unwrap_or_default(),
+        )
+        .replace("${pathSeparator}", "/");
+    if let Some(home) = dirs::home_dir() {
+        s = s.replace("${userHome}", &home.to_string_lossy());
+    }
+    substitute_env(&s, |name| env::var(name).ok())
+}
+
+/// Replace the environment variables, of the form `${env:NAME}`, with their values from `lookup`
+///
+/// The values are inserted as is, variables appearing in them are not replaced.
+fn substitute_env(s: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    const PREFIX: &str = "${env:";
+    let mut s = s.to_string();
+    let mut from = 0;
+    while let Some(start) = s[from..].find(PREFIX).map(|i| from + i) {
+        let Some(len) = s[start..].find('}') else {
+            break;
+        };
+        let value = lookup(&s[start + PREFIX.len()..start + len]).unwrap_or_default();
+        s.replace_range(start..=start + len, &value);
+        from = start + value.len();
+    }
+    s
+}
+
+/// Convert a command or argument, which may be a plain string or a `{ "value": ... }` object
+fn string_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.to_owned()),
+        Value::Array(parts) => Some(
+            parts
+                .iter()
+                .filter_map(string_value)
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        Value::Object(o) => o.get("value").and_then(string_value),
+        _ => None,
+    }
+}
+
+/// Parse a task definition, returning `None` if it is not supported
+fn parse_task(value: &Value, folder: &Path) -> Option<Task> {
+    // Merge the Linux-specific properties over the common ones
+    let mut def = value.as_object()?.to_owned();
+    if let Some(Value::Object(linux)) = value.get("linux") {
+        def.extend(linux.to_owned());
+    }
+
+    let task_type = def.get("type").and_then(Value::as_str).unwrap_or("process");
+    let (kind, command, default_label) = match task_type {
+        "shell" | "process" => {
+            let command = def.get("command").and_then(string_value)?;
+            let kind = match task_type {
+                "shell" => TaskKind::Shell,
+                _ => TaskKind::Process,
+            };
+            (kind, command.clone(), command)
+        }
+        "npm" => {
+            let script = def.get("script").and_then(Value::as_str)?;
+            (
+                TaskKind::Shell,
+                format!("npm run {}", script),
+                format!("npm: {}", script),
+            )
+        }
+        _ => return None,
+    };
+
+    let label = def
+        .get("label")
+        .or_else(|| def.get("taskName"))
+        .and_then(Value::as_str)
+        .map(String::from)
+        .unwrap_or(default_label);
+    let args: Vec<String> = def
+        .get("args")
+        .and_then(Value::as_array)
+        .map(|args| args.iter().filter_map(string_value).collect())
+        .unwrap_or_default();
+    let cwd = def
+        .get("options")
+        .and_then(|o| o.get("cwd"))
+        .and_then(Value::as_str)
+        .map(|cwd| folder.join(substitute_variables(cwd, folder)))
+        .unwrap_or_else(|| folder.to_path_buf());
+
+    Some(Task {
+        label,
+        kind,
+        command: substitute_variables(&command, folder),
+        args: args
+            .iter()
+            .map(|a| substitute_variables(a, folder))
+            .collect(),
+        cwd,
+    })
+}
+
+/// Parse the supported tasks of a `tasks` object, running in `folder`
+fn parse_tasks(tasks: &Value, folder: &Path) -> Vec<Task> {
+    tasks
+        .get("tasks")
+        .and_then(Value::as_array)
+        .map(|tasks| tasks.iter().filter_map(|t| parse_task(t, folder)).collect())
+        .unwrap_or_default()
+}
+
+/// Read a JSON with comments file
+fn read_jsonc(path: &Path) -> Result<Value> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Could not read {:?}", path))?;
+    jsonc::from_str(&contents).with_context(|| format!("Could not parse {:?}", path))
+}
+
+/// Get the tasks defined in the `.vscode/tasks.json` file of a folder
+///
+/// A missing file means that there are no tasks.
+pub fn folder_tasks(folder: &Path) -> Result<Vec<Task>> {
+    let path = folder.join(".vscode").join("tasks.json");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    read_jsonc(&path).map(|tasks| parse_tasks(&tasks, folder))
+}
+
+/// Get the tasks of a multi-root workspace
+///
+/// These are the tasks defined in the `.code-workspace` file at `config_path`,
+/// followed by the ones of each folder of the workspace.
+pub fn workspace_tasks(config_path: &Path) -> Result<Vec<Task>> {
+    let config = read_jsonc(config_path)?;
+    let root = config_path.parent().unwrap_or(Path::new("/"));
+
+    let mut tasks = config
+        .get("tasks")
+        .map(|t| parse_tasks(t, root))
+        .unwrap_or_default();
+
+    for folder in workspace_folders(config_path)? {
+        tasks.extend(folder_tasks(&folder)?);
+    }
+    Ok(tasks)
+}
+
+/// Get the tasks of a recent workspace or folder
+///
+/// # Errors
+/// The call fails for files and for items that are not local.
+pub fn recent_tasks(recent: &Recent) -> Result<Vec<Task>> {
+    let path = recent.file_path()?;
+    match recent {
+        Recent::Workspace {
+            workspace: _,
+            label: _,
+            remote_authority: _,
+        } => workspace_tasks(&path),
+        Recent::Folder {
+            folder_uri: _,
+            label: _,
+            remote_authority: _,
+        } => folder_tasks(&path),
+        Recent::File {
+            file_uri: _,
+            label: _,
+            remote_authority: _,
+        } => Err(other!("Files do not have tasks")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::json;
+
+    use super::{parse_tasks, substitute_env, Task, TaskKind};
+
+    #[test]
+    fn substitute_env_variables() {
+        let lookup = |name: &str| match name {
+            "PAPER" => Some("dunder".to_string()),
+            "SELF" => Some("${env:SELF}".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            substitute_env("${env:PAPER}-${env:MISSING}-${env:PAPER}", lookup),
+            "dunder--dunder"
+        );
+        // Values are not expanded again, which would never end
+        assert_eq!(
+            substitute_env("[${env:SELF}] ${env:PAPER}", lookup),
+            "[${env:SELF}] dunder"
+        );
+        assert_eq!(substitute_env("${env:PAPER", lookup), "${env:PAPER");
+    }
+
+    #[test]
+    fn parse_supported_tasks() {
+        let tasks = json!({
+            "version": "2.0.0",
+            "tasks": [
+                {
+                    "label": "build",
+                    "type": "shell",
+                    "command": "cargo build",
+                    "args": ["--features", { "value": "rofi icons", "quoting": "strong" }],
+                },
+                {
+                    "label": "serve",
+                    "type": "process",
+                    "command": "python3",
+                    "args": ["-m", "http.server"],
+                    "options": { "cwd": "${workspaceFolder}/public" },
+                    "linux": { "command": "/usr/bin/python3" },
+                },
+                { "type": "npm", "script": "lint" },
+                { "label": "all", "dependsOn": ["build", "serve"] },
+                { "label": "watch", "type": "typescript", "tsconfig": "tsconfig.json" },
+            ],
+        });
+
+        let folder = Path::new("/home/dwight/beet-farm");
+        let tasks = parse_tasks(&tasks, folder);
+        assert_eq!(
+            tasks,
+            vec![
+                Task {
+                    label: "build".to_string(),
+                    kind: TaskKind::Shell,
+                    command: "cargo build".to_string(),
+                    args: vec!["--features".to_string(), "rofi icons".to_string()],
+                    cwd: folder.to_path_buf(),
+                },
+                Task {
+                    label: "serve".to_string(),
+                    kind: TaskKind::Process,
+                    command: "/usr/bin/python3".to_string(),
+                    args: vec!["-m".to_string(), "http.server".to_string()],
+                    cwd: folder.join("public"),
+                },
+                Task {
+                    label: "npm: lint".to_string(),
+                    kind: TaskKind::Shell,
+                    command: "npm run lint".to_string(),
+                    args: vec![],
+                    cwd: folder.to_path_buf(),
+                },
+            ]
+        );
+        assert_eq!(
+            tasks[0].command_line(),
+            "cargo build --features 'rofi icons'"
+        );
+    }
+}
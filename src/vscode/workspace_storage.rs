@@ -0,0 +1,378 @@
+//! Per-workspace storage
+//!
+//! VSCode keeps the state of each workspace or folder in `$CODE_CONFIG_DIR/User/workspaceStorage/{id}/`.
+//! The folder contains a `workspace.json` file identifying the workspace, and a `state.vscdb` database
+//! with the same layout as the global one.
+//!
+//! For reference see VSCode's source code:
+//! - [Workspaces Storage Main Service](https://github.com/microsoft/vscode/blob/main/src/vs/platform/storage/electron-main/storageMainService.ts)
+//! - [Editor History Service](https://github.com/microsoft/vscode/blob/main/src/vs/workbench/services/history/browser/historyService.ts)
+
+use super::{file_url_to_path, read_db, workspaces::Recent, EditorFlavor};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use rusqlite::OptionalExtension;
+use serde::Deserialize;
+use serde_json::Value;
+use url::Url;
+
+use crate::error::{other, Context, Result};
+
+const VSCDB_EDITOR_HISTORY_KEY: &str = "history.entries";
+
+/// Contents of the `workspace.json` file
+#[derive(Deserialize, Debug)]
+struct WorkspaceJson {
+    /// URI of the folder, for single-folder workspaces
+    folder: Option<Url>,
+    /// URI of the `.code-workspace` file, for multi-root workspaces
+    workspace: Option<Url>,
+}
+
+/// The storage folder of a workspace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceStorage {
+    /// Path of the storage folder
+    pub dir: PathBuf,
+    /// URI of the folder or `.code-workspace` file, if it could be read
+    pub uri: Option<Url>,
+}
+
+impl WorkspaceStorage {
+    /// Read the storage folder at `dir`
+    fn read(dir: PathBuf) -> Self {
+        let uri = fs::read_to_string(dir.join("workspace.json"))
+            .ok()
+            .and_then(|s| serde_json::from_str::<WorkspaceJson>(&s).ok())
+            .and_then(|w| w.folder.or(w.workspace));
+        WorkspaceStorage { dir, uri }
+    }
+
+    /// When the workspace was last opened
+    ///
+    /// This is the latest modification time of the storage folder and of its files,
+    /// since VSCode updates them while the workspace is open.
+    pub fn last_opened(&self) -> Option<SystemTime> {
+        [
+            self.dir.clone(),
+            self.dir.join("workspace.json"),
+            self.dir.join("state.vscdb"),
+        ]
+        .iter()
+        .filter_map(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+        .max()
+    }
+
+    /// Total size in bytes of the files in the storage folder
+    pub fn size(&self) -> u64 {
+        dir_size(&self.dir)
+    }
+
+    /// Tell whether the storage folder is orphaned, given the URLs of the recent items
+    ///
+    /// This is the case when it has not been used for at least `min_age`, and either its workspace
+    /// is not among the recent items or it is local and does not exist anymore.
+    fn is_orphaned(&self, recent_urls: &[&Url], min_age: Duration) -> bool {
+        let old = self
+            .last_opened()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age >= min_age);
+        let gone = match &self.uri {
+            Some(uri) => {
+                !recent_urls.contains(&uri)
+                    || (uri.scheme() == "file"
+                        && file_url_to_path(uri).is_some_and(|p| !p.exists()))
+            }
+            None => true,
+        };
+        old && gone
+    }
+
+    /// Delete the storage folder
+    ///
+    /// # Errors
+    /// The call fails if the folder cannot be removed.
+    pub fn remove(&self) -> Result<()> {
+        fs::remove_dir_all(&self.dir)
+            .with_context(|| format!("Could not remove directory {:?}", &self.dir))
+    }
+
+    /// Get the editors recently opened in the workspace, from the most to the least recent
+    ///
+    /// The editors are returned as [Recent::File] items.
+    /// Editors that do not refer to a file (e.g. settings, diffs) are ignored.
+    pub fn editor_history(&self) -> Result<Vec<Recent>> {
+        let db_path = self.dir.join("state.vscdb");
+        let res: Option<String> = read_db(&db_path, |conn| {
+            conn.query_row(
+                "SELECT value FROM ItemTable WHERE key = (?)",
+                [VSCDB_EDITOR_HISTORY_KEY],
+                |r| r.get(0),
+            )
+            .optional()
+        })?;
+        let Some(res) = res else {
+            return Ok(Vec::new());
+        };
+        let entries: Value = serde_json::from_str(&res).with_context(|| {
+            format!(
+                "Could not parse key \"{}\" from {:?}",
+                VSCDB_EDITOR_HISTORY_KEY, &db_path
+            )
+        })?;
+        Ok(parse_editor_history(&entries))
+    }
+}
+
+/// Extract the resources of the editors in the history
+fn parse_editor_history(entries: &Value) -> Vec<Recent> {
+    entries
+        .as_array()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|e| editor_resource(e.get("editor")?))
+                .map(|file_uri| {
+                    // Non-local files cannot be labeled from their path
+                    let label = (file_uri.scheme() != "file").then(|| file_uri.to_string());
+                    Recent::File {
+                        file_uri,
+                        label,
+                        remote_authority: None,
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Get the resource of an editor
+///
+/// Recent versions store it in the `resource` attribute,
+/// while older ones stored it in a serialized `value` attribute.
+fn editor_resource(editor: &Value) -> Option<Url> {
+    if let Some(resource) = editor.get("resource").and_then(Value::as_str) {
+        return Url::parse(resource).ok();
+    }
+    let value: Value = serde_json::from_str(editor.get("value")?.as_str()?).ok()?;
+    let resource = value.get("resourceJSON")?;
+    resource
+        .get("external")
+        .and_then(Value::as_str)
+        .and_then(|s| Url::parse(s).ok())
+        .or_else(|| {
+            let scheme = resource.get("scheme")?.as_str()?;
+            let path = resource.get("path")?.as_str()?;
+            let authority = resource
+                .get("authority")
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            Url::parse(&format!("{}://{}{}", scheme, authority, path)).ok()
+        })
+}
+
+/// List the storage folders inside the given `config_dir` configuration directory
+fn list_storage(config_dir: &Path) -> Result<Vec<WorkspaceStorage>> {
+    let storage_dir = config_dir.join("User").join("workspaceStorage");
+    let entries = fs::read_dir(&storage_dir)
+        .with_context(|| format!("Could not read directory {:?}", &storage_dir))?;
+    Ok(entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .map(WorkspaceStorage::read)
+        .collect())
+}
+
+/// Total size in bytes of the files in a directory and its subdirectories
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| Some((e.path(), e.metadata().ok()?)))
+        .map(|(path, metadata)| {
+            if metadata.is_dir() {
+                dir_size(&path)
+            } else {
+                metadata.len()
+            }
+        })
+        .sum()
+}
+
+/// List the storage folders of every workspace of a specific flavor
+pub fn workspace_storages(flavor: &dyn EditorFlavor) -> Result<Vec<WorkspaceStorage>> {
+    let config_dir = flavor.config_dir().ok_or_else(|| {
+        other!(
+            "Could not find configuration directory for \"{:?}\"",
+            flavor
+        )
+    })?;
+    list_storage(&config_dir)
+}
+
+/// When the workspaces and folders of several flavors were last opened
+#[derive(Debug, Default)]
+pub struct LastOpened(Vec<HashMap<Url, SystemTime>>);
+
+impl LastOpened {
+    /// Scan the storage folders of each flavor
+    ///
+    /// Flavors whose storage cannot be read have no known times.
+    pub fn read(flavors: &[Box<dyn EditorFlavor>]) -> Self {
+        LastOpened(
+            flavors
+                .iter()
+                .map(|f| {
+                    workspace_storages(f.as_ref())
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|s| Some((s.uri.clone()?, s.last_opened()?)))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    /// When an item of the flavor at index `flavor` was last opened, if known
+    pub fn get(&self, flavor: usize, recent: &Recent) -> Option<SystemTime> {
+        self.0.get(flavor)?.get(recent.url()).copied()
+    }
+}
+
+/// Find the storage folders of a flavor that are orphaned
+///
+/// These are the ones not used for at least `min_age`, whose workspace is not among the `recents`
+/// of the flavor or is local and does not exist anymore.
+pub fn orphaned_storages(
+    flavor: &dyn EditorFlavor,
+    recents: &[Recent],
+    min_age: Duration,
+) -> Result<Vec<WorkspaceStorage>> {
+    let recent_urls: Vec<&Url> = recents.iter().map(Recent::url).collect();
+    Ok(workspace_storages(flavor)?
+        .into_iter()
+        .filter(|s| s.is_orphaned(&recent_urls, min_age))
+        .collect())
+}
+
+/// Find the storage folder of a recent workspace or folder
+///
+/// Multi-root workspaces are looked up by their identifier,
+/// while for the other items the `workspace.json` files are scanned to find a matching URI.
+pub fn storage_for_recent(
+    flavor: &dyn EditorFlavor,
+    recent: &Recent,
+) -> Result<Option<WorkspaceStorage>> {
+    if let Recent::Workspace {
+        workspace,
+        label: _,
+        remote_authority: _,
+    } = recent
+    {
+        if let Some(config_dir) = flavor.config_dir() {
+            let dir = config_dir
+                .join("User")
+                .join("workspaceStorage")
+                .join(&workspace.id);
+            if dir.is_dir() {
+                return Ok(Some(WorkspaceStorage::read(dir)));
+            }
+        }
+    }
+    Ok(workspace_storages(flavor)?
+        .into_iter()
+        .find(|s| s.uri.as_ref() == Some(recent.url())))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process, time::Duration};
+
+    use serde_json::json;
+    use url::Url;
+
+    use super::{list_storage, parse_editor_history};
+
+    #[test]
+    fn find_orphaned_storage() {
+        let config_dir = env::temp_dir().join(format!("rofi-vscode-mode-gc-{}", process::id()));
+        let storage_dir = config_dir.join("User").join("workspaceStorage");
+        let project = config_dir.join("project");
+        fs::create_dir_all(&project).expect("could not create project");
+        let project_url = Url::from_directory_path(&project).expect("invalid path");
+        for (id, folder) in [
+            ("kept", project_url.as_str()),
+            ("gone", "file:///nonexistent/beet-farm"),
+            (
+                "forgotten",
+                "vscode-remote://ssh-remote%2Bschrute/home/dwight",
+            ),
+        ] {
+            let dir = storage_dir.join(id);
+            fs::create_dir_all(&dir).expect("could not create storage");
+            fs::write(
+                dir.join("workspace.json"),
+                json!({ "folder": folder }).to_string(),
+            )
+            .expect("could not write workspace.json");
+        }
+
+        let storages = list_storage(&config_dir).expect("could not list storage");
+        let recent_urls = vec![&project_url];
+        let mut orphans: Vec<String> = storages
+            .iter()
+            .filter(|s| s.is_orphaned(&recent_urls, Duration::ZERO))
+            .map(|s| {
+                s.dir
+                    .file_name()
+                    .expect("no name")
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        orphans.sort();
+        assert_eq!(orphans, vec!["forgotten", "gone"]);
+        assert!(storages
+            .iter()
+            .all(|s| !s.is_orphaned(&recent_urls, Duration::from_secs(3600))));
+        assert!(storages.iter().all(|s| s.size() > 0));
+
+        fs::remove_dir_all(&config_dir).expect("could not remove config dir");
+    }
+
+    #[test]
+    fn parse_history_entries() {
+        let entries = json!([
+            { "editor": { "resource": "file:///home/pam/art/watercolor.md", "forceFile": true } },
+            {
+                "editor": {
+                    "id": "workbench.editors.files.fileEditorInput",
+                    "value": "{\"resourceJSON\":{\"$mid\":1,\"fsPath\":\"/home/pam/art/sketch.txt\",\"external\":\"file:///home/pam/art/sketch.txt\",\"path\":\"/home/pam/art/sketch.txt\",\"scheme\":\"file\"}}"
+                }
+            },
+            { "editor": { "resource": "vscode-remote://ssh-remote%2Bdevbox/etc/hosts" } },
+            { "editor": { "id": "workbench.editors.settings2" } },
+        ]);
+
+        let files = parse_editor_history(&entries);
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            files[0].url().as_str(),
+            "file:///home/pam/art/watercolor.md"
+        );
+        assert_eq!(files[1].url().as_str(), "file:///home/pam/art/sketch.txt");
+        assert!(!files[2].is_local());
+        assert_eq!(
+            files[2].label().expect("expected a label"),
+            "vscode-remote://ssh-remote%2Bdevbox/etc/hosts"
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! Window utilities
+//!
+//! Opening an item that is already open in VSCode may not raise its window,
+//! e.g. on Wayland compositors that prevent focus stealing.
+//! The window is then activated through the window manager, with a [WindowBackend] selected at runtime:
+//! - [Hyprland], with `hyprctl`
+//! - [Sway], with `swaymsg`
+//! - [Wmctrl] on X11, with `wmctrl`, which works with any window manager supporting EWMH
+
+use std::{env, fmt, path::Path, process::Command};
+
+use percent_encoding::percent_decode_str;
+use serde_json::Value;
+use which::which;
+
+use crate::error::{other, Context, Result};
+use crate::vscode::workspaces::Recent;
+
+/// Separator of the parts of VSCode's window titles, see the `window.titleSeparator` setting
+const TITLE_SEPARATOR: &str = " - ";
+
+/// Parts of the window classes of the VSCode flavors, compared without case
+const EDITOR_CLASSES: &[&str] = &["code", "codium", "cursor", "windsurf"];
+
+/// A top-level window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    /// Identifier of the window for the backend
+    pub id: String,
+    /// Class or application id of the window
+    pub class: String,
+    /// Title of the window
+    pub title: String,
+}
+
+impl Window {
+    /// Whether the window belongs to one of the VSCode flavors
+    pub fn is_editor(&self) -> bool {
+        let class = self.class.to_lowercase();
+        EDITOR_CLASSES.iter().any(|c| class.contains(c))
+    }
+}
+
+/// A way to list and activate the windows of the graphical session
+pub trait WindowBackend: fmt::Debug + Send + Sync {
+    /// Name of the backend
+    fn name(&self) -> &'static str;
+
+    /// List the top-level windows
+    fn windows(&self) -> Result<Vec<Window>>;
+
+    /// Focus the window, switching to its workspace if needed
+    fn activate(&self, window: &Window) -> Result<()>;
+}
+
+/// The Hyprland compositor
+#[derive(Debug, Clone, Copy)]
+pub struct Hyprland;
+
+impl WindowBackend for Hyprland {
+    fn name(&self) -> &'static str {
+        "hyprctl"
+    }
+
+    fn windows(&self) -> Result<Vec<Window>> {
+        parse_hyprland_clients(&run("hyprctl", &["clients", "-j"])?)
+    }
+
+    fn activate(&self, window: &Window) -> Result<()> {
+        let target = format!("address:{}", window.id);
+        run("hyprctl", &["dispatch", "focuswindow", &target]).map(|_| ())
+    }
+}
+
+/// The Sway compositor
+#[derive(Debug, Clone, Copy)]
+pub struct Sway;
+
+impl WindowBackend for Sway {
+    fn name(&self) -> &'static str {
+        "swaymsg"
+    }
+
+    fn windows(&self) -> Result<Vec<Window>> {
+        parse_sway_tree(&run("swaymsg", &["-t", "get_tree", "-r"])?)
+    }
+
+    fn activate(&self, window: &Window) -> Result<()> {
+        let criteria = format!("[con_id={}] focus", window.id);
+        run("swaymsg", &[&criteria]).map(|_| ())
+    }
+}
+
+/// An X11 window manager supporting EWMH
+#[derive(Debug, Clone, Copy)]
+pub struct Wmctrl;
+
+impl WindowBackend for Wmctrl {
+    fn name(&self) -> &'static str {
+        "wmctrl"
+    }
+
+    fn windows(&self) -> Result<Vec<Window>> {
+        Ok(parse_wmctrl(&run("wmctrl", &["-l", "-x"])?))
+    }
+
+    fn activate(&self, window: &Window) -> Result<()> {
+        run("wmctrl", &["-i", "-a", &window.id]).map(|_| ())
+    }
+}
+
+/// Select the backend of the current session, if any
+///
+/// Hyprland and Sway are recognized by the variables they set, and X11 by `DISPLAY` if `wmctrl` is installed.
+/// Other Wayland compositors are not supported.
+pub fn detect_backend() -> Option<Box<dyn WindowBackend>> {
+    if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        Some(Box::new(Hyprland))
+    } else if env::var_os("SWAYSOCK").is_some() {
+        Some(Box::new(Sway))
+    } else if env::var_os("WAYLAND_DISPLAY").is_none()
+        && env::var_os("DISPLAY").is_some()
+        && which("wmctrl").is_ok()
+    {
+        Some(Box::new(Wmctrl))
+    } else {
+        None
+    }
+}
+
+/// Activate the VSCode window showing `recent`, if there is one
+///
+/// Returns whether a window was activated.
+///
+/// # Errors
+/// The call fails if the windows cannot be listed or activated.
+pub fn focus_existing(backend: &dyn WindowBackend, recent: &Recent) -> Result<bool> {
+    let Some(name) = root_name(recent) else {
+        return Ok(false);
+    };
+    let windows = backend.windows()?;
+    match windows
+        .iter()
+        .find(|w| w.is_editor() && title_shows(&w.title, &name))
+    {
+        Some(window) => backend.activate(window).map(|_| true),
+        None => Ok(false),
+    }
+}
+
+/// The name VSCode shows in the titles of the windows of a workspace or folder
+///
+/// This is the `${rootName}` of the `window.title` setting. Files have none.
+fn root_name(recent: &Recent) -> Option<String> {
+    let path = percent_decode_str(recent.url().path())
+        .decode_utf8_lossy()
+        .into_owned();
+    let name = Path::new(&path).file_name()?.to_string_lossy().into_owned();
+    match recent {
+        Recent::Workspace { .. } => {
+            let stem = name.strip_suffix(".code-workspace").unwrap_or(&name);
+            Some(format!("{} (Workspace)", stem))
+        }
+        Recent::Folder { .. } => Some(name),
+        Recent::File { .. } => None,
+    }
+}
+
+/// Whether a window title shows the root `name`, as one of its parts
+///
+/// Remote roots are followed by their remote, e.g. `beet-farm [SSH: devbox]`.
+fn title_shows(title: &str, name: &str) -> bool {
+    title.split(TITLE_SEPARATOR).any(|part| {
+        part == name
+            || part
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.starts_with(" ["))
+    })
+}
+
+/// Run a command of a backend, returning its output
+fn run(cmd: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(cmd)
+        .args(args)
+        .output()
+        .with_context(|| format!("Could not execute {}", cmd))?;
+    if !output.status.success() {
+        return Err(other!(
+            "{} failed: {}",
+            cmd,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse the output of `hyprctl clients -j`
+fn parse_hyprland_clients(output: &str) -> Result<Vec<Window>> {
+    let clients: Vec<Value> =
+        serde_json::from_str(output).with_context(|| "Could not parse the Hyprland clients")?;
+    Ok(clients
+        .iter()
+        .filter_map(|c| {
+            Some(Window {
+                id: c.get("address")?.as_str()?.to_string(),
+                class: c.get("class")?.as_str()?.to_string(),
+                title: c.get("title")?.as_str()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Parse the output of `swaymsg -t get_tree`, collecting the windows of the whole tree
+fn parse_sway_tree(output: &str) -> Result<Vec<Window>> {
+    let tree: Value =
+        serde_json::from_str(output).with_context(|| "Could not parse the Sway tree")?;
+    let mut windows = Vec::new();
+    let mut nodes = vec![&tree];
+    while let Some(node) = nodes.pop() {
+        // Only windows have a process
+        if node.get("pid").is_some_and(Value::is_number) {
+            let class = node
+                .get("app_id")
+                .and_then(Value::as_str)
+                .or_else(|| node.pointer("/window_properties/class")?.as_str());
+            if let (Some(id), Some(class)) = (node.get("id").and_then(Value::as_u64), class) {
+                windows.push(Window {
+                    id: id.to_string(),
+                    class: class.to_string(),
+                    title: node
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                });
+            }
+        }
+        for key in ["nodes", "floating_nodes"] {
+            nodes.extend(
+                node.get(key)
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten(),
+            );
+        }
+    }
+    Ok(windows)
+}
+
+/// Parse the output of `wmctrl -l -x`, whose lines are the id, desktop, class, host and title
+fn parse_wmctrl(output: &str) -> Vec<Window> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (id, rest) = line.split_once(char::is_whitespace)?;
+            let (_desktop, rest) = rest.trim_start().split_once(char::is_whitespace)?;
+            let (class, rest) = rest.trim_start().split_once(char::is_whitespace)?;
+            let title = match rest.trim_start().split_once(char::is_whitespace) {
+                Some((_host, title)) => title.trim_start(),
+                None => "",
+            };
+            Some(Window {
+                id: id.to_string(),
+                class: class.to_string(),
+                title: title.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{parse_hyprland_clients, parse_sway_tree, parse_wmctrl, root_name, title_shows};
+    use crate::vscode::workspaces::Recent;
+
+    #[test]
+    fn parse_windows() {
+        let wmctrl = "0x03e00003  0 code.Code  farm main.rs - beet-farm - Visual Studio Code\n\
+                      0x04000007 -1 xterm.XTerm  farm beet-farm - zsh\n";
+        let windows = parse_wmctrl(wmctrl);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].id, "0x03e00003");
+        assert_eq!(windows[0].title, "main.rs - beet-farm - Visual Studio Code");
+        assert!(windows[0].is_editor());
+        assert!(!windows[1].is_editor());
+
+        let hyprland = json!([
+            { "address": "0x55d1", "class": "VSCodium", "title": "beet-farm - VSCodium" },
+        ]);
+        let windows = parse_hyprland_clients(&hyprland.to_string()).unwrap();
+        assert_eq!(windows[0].id, "0x55d1");
+        assert!(windows[0].is_editor());
+
+        let sway = json!({
+            "id": 1, "nodes": [{
+                "id": 4, "nodes": [
+                    { "id": 12, "pid": 42, "app_id": "code-oss", "name": "beet-farm - Code - OSS" },
+                ],
+                "floating_nodes": [
+                    { "id": 13, "pid": 43, "app_id": null, "name": "barn",
+                      "window_properties": { "class": "Cursor" } },
+                ],
+            }],
+        });
+        let windows = parse_sway_tree(&sway.to_string()).unwrap();
+        let ids: Vec<&str> = windows.iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(ids, vec!["13", "12"]);
+        assert!(windows.iter().all(|w| w.is_editor()));
+    }
+
+    #[test]
+    fn match_titles() {
+        let folder: Recent =
+            serde_json::from_value(json!({ "folderUri": "file:///srv/beet%20farm" })).unwrap();
+        let name = root_name(&folder).unwrap();
+        assert!(title_shows(
+            "main.rs - beet farm - Visual Studio Code",
+            &name
+        ));
+        assert!(title_shows(
+            "beet farm [SSH: devbox] - Visual Studio Code",
+            &name
+        ));
+        assert!(!title_shows("beet farm 2 - Visual Studio Code", &name));
+
+        let workspace: Recent = serde_json::from_value(json!({
+            "workspace": {
+                "id": "0dd79faa9035cb0db768dbe5812a110c",
+                "configPath": "file:///srv/farm.code-workspace"
+            }
+        }))
+        .unwrap();
+        let name = root_name(&workspace).unwrap();
+        assert!(title_shows("farm (Workspace) - VSCodium", &name));
+
+        let file: Recent =
+            serde_json::from_value(json!({ "fileUri": "file:///srv/main.rs" })).unwrap();
+        assert_eq!(root_name(&file), None);
+    }
+}
@@ -0,0 +1,88 @@
+//! Windows Subsystem for Linux utilities
+//!
+//! When running inside WSL with interoperability enabled, VSCode can open folders in any installed
+//! distribution with the [WSL](https://code.visualstudio.com/docs/remote/wsl) extension.
+//! The distributions are listed with the `wsl.exe` command of the Windows host.
+
+use std::{env, path::Path, process::Command};
+
+use crate::error::{other, Context, Result};
+
+/// Theme icon shown next to WSL distributions and the items located in them
+pub const WSL_ICON_NAME: &str = "tux";
+/// Nerd font glyph shown next to WSL distributions and the items located in them
+pub const WSL_NERD_ICON: &str = "\u{f31a}";
+
+/// Command of the Windows host to manage the distributions
+const WSL_CMD: &str = "wsl.exe";
+/// Registered by WSL when it can run Windows executables
+const WSL_INTEROP_PATH: &str = "/proc/sys/fs/binfmt_misc/WSLInterop";
+
+/// Tells whether Windows executables, and thus `wsl.exe`, can be run from this system
+pub fn interop_available() -> bool {
+    env::var_os("WSL_DISTRO_NAME").is_some() || Path::new(WSL_INTEROP_PATH).exists()
+}
+
+/// The remote authority used by VSCode to open items in the given distribution
+pub fn remote_authority(distro: &str) -> String {
+    format!("wsl+{}", distro)
+}
+
+/// List the installed distributions
+///
+/// # Errors
+/// The call fails if `wsl.exe` cannot be executed or reports an error.
+pub fn list_distros() -> Result<Vec<String>> {
+    let output = Command::new(WSL_CMD)
+        .args(["--list", "--quiet"])
+        .output()
+        .with_context(|| format!("Could not execute {}", WSL_CMD))?;
+    if !output.status.success() {
+        return Err(other!(
+            "Could not list WSL distributions: {}",
+            parse_output(&output.stderr).trim()
+        ));
+    }
+    Ok(parse_distros(&parse_output(&output.stdout)))
+}
+
+/// Decode the output of `wsl.exe`, which is UTF-16LE unless `WSL_UTF8=1` is set
+fn parse_output(bytes: &[u8]) -> String {
+    if bytes.len() % 2 == 0 && bytes.iter().skip(1).step_by(2).all(|b| *b == 0) {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(bytes).to_string()
+    }
+}
+
+/// Parse the output of `--list --quiet`, one distribution per line
+fn parse_distros(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(|l| l.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}'))
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_distros, parse_output};
+
+    #[test]
+    fn parse_distro_list() {
+        let utf16: Vec<u8> = "Ubuntu-22.04\r\ndocker-desktop\r\n\r\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert_eq!(
+            parse_distros(&parse_output(&utf16)),
+            vec!["Ubuntu-22.04", "docker-desktop"]
+        );
+        assert_eq!(parse_distros(&parse_output(b"Debian\n")), vec!["Debian"]);
+    }
+}